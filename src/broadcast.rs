@@ -0,0 +1,91 @@
+/// Publishes the poses computed by the tracking thread to the subscribers (control server, pose streams)
+use crate::{
+    enums::rotation_order::RotationOrder,
+    structs::{
+        broadcast::{PoseBroadcaster, PoseSample},
+        pose::Pose,
+    },
+};
+use crossbeam_channel::{bounded, Receiver};
+
+// About 5 seconds at 60 fps
+const RECENT_POSES: usize = 300;
+
+// Poses kept for a subscriber not reading fast enough, newer poses are dropped after that
+const SUBSCRIBER_BUFFER: usize = 32;
+
+impl PoseSample {
+    pub fn new(timestamp: f32, pose: Pose, rotation_order: RotationOrder, quality: f32) -> Self {
+        let [x, y, z, yaw, pitch, roll] = pose.to_euler_data(rotation_order);
+        let q = pose.rotation;
+
+        Self {
+            timestamp,
+            x,
+            y,
+            z,
+            yaw,
+            pitch,
+            roll,
+            rotation: [q.x, q.y, q.z, q.w],
+            quality,
+        }
+    }
+}
+
+impl PoseBroadcaster {
+    pub fn subscribe(&self) -> Receiver<PoseSample> {
+        let (sender, receiver) = bounded(SUBSCRIBER_BUFFER);
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    // Never blocks the tracking thread, subscribers which disconnected are removed
+    pub fn publish(&self, sample: PoseSample) {
+        {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() == RECENT_POSES {
+                recent.pop_front();
+            }
+            recent.push_back(sample);
+        }
+
+        self.subscribers.lock().unwrap().retain(|subscriber| {
+            !matches!(
+                subscriber.try_send(sample),
+                Err(crossbeam_channel::TrySendError::Disconnected(_))
+            )
+        });
+    }
+
+    pub fn recent(&self) -> Vec<PoseSample> {
+        self.recent.lock().unwrap().iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pose_broadcaster() {
+        let broadcaster = PoseBroadcaster::default();
+        let first = broadcaster.subscribe();
+        let second = broadcaster.subscribe();
+
+        let sample = PoseSample::new(1., Pose::default(), RotationOrder::default(), 1.);
+        broadcaster.publish(sample);
+        assert_eq!(first.try_recv(), Ok(sample));
+        assert_eq!(second.try_recv(), Ok(sample));
+
+        // Dropped subscribers are forgotten on the next publish
+        drop(first);
+        broadcaster.publish(sample);
+        assert_eq!(broadcaster.subscribers.lock().unwrap().len(), 1);
+
+        for _ in 0..RECENT_POSES {
+            broadcaster.publish(sample);
+        }
+        assert_eq!(broadcaster.recent().len(), RECENT_POSES);
+    }
+}
@@ -11,11 +11,12 @@ use std::{
         atomic::{AtomicBool, Ordering},
     },
     thread,
+    time::Instant,
 };
 
 use std::collections::HashMap;
 
-use crate::structs::camera::ThreadedCamera;
+use crate::structs::camera::{Frame, ThreadedCamera};
 
 use anyhow::Result;
 
@@ -74,7 +75,7 @@ impl ThreadedCamera {
     }
 
     pub fn start_camera_thread(
-        tx: Sender<Mat>,
+        tx: Sender<Frame>,
         camera_index: i32,
         camera_name: String,
     ) -> Result<Self> {
@@ -122,9 +123,16 @@ impl ThreadedCamera {
                         // ));
                     }
                 }
+                let timestamp = Instant::now();
 
                 // Send the frame to the other thread for processing
-                if tx.send(frame).is_err() {
+                if tx
+                    .send(Frame {
+                        image: frame,
+                        timestamp,
+                    })
+                    .is_err()
+                {
                     break;
                 }
             }
@@ -159,7 +167,7 @@ impl ThreadedCamera {
 #[test]
 #[ignore = "Can only test this offline since it requires webcam, run cargo test -- --ignored"]
 pub fn test_threaded_camera() -> Result<()> {
-    let (tx, rx) = crossbeam_channel::unbounded::<Mat>();
+    let (tx, rx) = crossbeam_channel::unbounded::<Frame>();
 
     println!("{:?}", ThreadedCamera::get_available_cameras());
 
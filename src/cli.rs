@@ -0,0 +1,174 @@
+/// Parsing of the command line arguments, eg. `StableView --profile "DCS World"`
+use crate::{
+    enums::log_level::LogLevel,
+    portable::{export_bundle, export_file, export_profile, import_file},
+    structs::{
+        cli::CliArgs, diagnostics::Diagnostics, portable::ImportedConfig, profile::ProfileStore,
+    },
+};
+use anyhow::{bail, Context, Result};
+
+pub const USAGE: &str = "Usage: StableView [OPTIONS]
+
+Options:
+  --profile <NAME>     Use the existing profile NAME for this run, the active profile is not changed
+  --export <FILE>      Export the profile (--profile or the active one) to FILE and exit
+  --export-all <FILE>  Export every profile to FILE and exit
+  --import <FILE>      Import FILE into the profile, or every profile of an --export-all file, and exit
+  --dry-run            With --import, only print the changes
+  --diagnostics <FILE> Save the logs, the settings and the system information to the zip FILE and exit
+  --log-level <LEVEL>  Level of the log file: error, warn, info, debug or trace,
+                       also read from the STABLEVIEW_LOG environment variable
+  -h, --help           Print this help
+
+FILE of --export, --export-all and --import is a .toml or .json file";
+
+// Release builds have no console of their own on Windows, the output goes to the one StableView was started from
+#[cfg(target_os = "windows")]
+pub fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // Fails when not started from a console, there is nowhere to print then
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn attach_console() {}
+
+impl CliArgs {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut cli_args = CliArgs::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            // Both --option value and --option=value are accepted
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => {
+                    (name.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = || -> Result<String> {
+                match inline_value.clone() {
+                    Some(value) => Ok(value),
+                    None => args
+                        .next()
+                        .with_context(|| format!("Missing value for {}", name)),
+                }
+            };
+
+            match name.as_str() {
+                "--profile" => cli_args.profile = Some(value()?),
+                "--export" => cli_args.export = Some(value()?.into()),
+                "--export-all" => cli_args.export_all = Some(value()?.into()),
+                "--import" => cli_args.import = Some(value()?.into()),
+                "--dry-run" => cli_args.dry_run = true,
+                "--diagnostics" => cli_args.diagnostics = Some(value()?.into()),
+                "--log-level" => cli_args.log_level = Some(LogLevel::parse(&value()?)?),
+                "-h" | "--help" => cli_args.help = true,
+                _ => bail!("Unknown argument {:?}\n\n{}", arg, USAGE),
+            }
+        }
+
+        if cli_args.dry_run && cli_args.import.is_none() {
+            bail!("--dry-run can only be used with --import\n\n{}", USAGE);
+        }
+
+        Ok(cli_args)
+    }
+
+    // Import, export and diagnostics run without opening the window
+    pub fn has_command(&self) -> bool {
+        self.export.is_some()
+            || self.export_all.is_some()
+            || self.import.is_some()
+            || self.diagnostics.is_some()
+    }
+
+    pub fn run_command(&self) -> Result<()> {
+        let store = ProfileStore::new()?;
+        let target = self.profile.clone().unwrap_or_else(|| store.active());
+
+        if let Some(path) = &self.export {
+            if !store.exists(&target) {
+                bail!("Profile {:?} does not exist", target);
+            }
+            export_file(path, |format| export_profile(&store.load(&target)?, format))?;
+            println!("Exported profile {:?} to {:?}", target, path);
+        }
+
+        if let Some(path) = &self.export_all {
+            export_file(path, |format| export_bundle(&store.bundle(&store.active())?, format))?;
+            println!("Exported every profile to {:?}", path);
+        }
+
+        if let Some(path) = &self.import {
+            let imported = import_file(path)?;
+            for changes in store.import_changes(&imported, &target)? {
+                print!("{}", changes);
+            }
+
+            if self.dry_run {
+                println!("Dry run, nothing was imported");
+            } else {
+                store.import(&imported, &target)?;
+                println!("Imported {:?}", path);
+                if let ImportedConfig::Bundle(bundle) = &imported {
+                    println!("Active profile is now {:?}", bundle.active);
+                }
+            }
+        }
+
+        if let Some(path) = &self.diagnostics {
+            Diagnostics::collect(&target, store.load(&target)?, None, None, vec![]).write(path)?;
+            println!("Diagnostics saved to {:?}", path);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs> {
+        CliArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        assert_eq!(parse(&[])?, CliArgs::default());
+        assert_eq!(
+            parse(&["--profile", "DCS World"])?.profile.as_deref(),
+            Some("DCS World")
+        );
+        assert_eq!(
+            parse(&["--profile=Xplane"])?.profile.as_deref(),
+            Some("Xplane")
+        );
+        assert!(parse(&["-h"])?.help);
+
+        let import = parse(&["--import", "shared.json", "--dry-run", "--profile", "Xplane"])?;
+        assert_eq!(import.import, Some("shared.json".into()));
+        assert!(import.dry_run && import.has_command());
+        assert_eq!(
+            parse(&["--export-all=all.toml"])?.export_all,
+            Some("all.toml".into())
+        );
+        assert!(!parse(&["--profile", "Xplane"])?.has_command());
+        assert!(parse(&["--diagnostics", "report.zip"])?.has_command());
+        assert_eq!(
+            parse(&["--log-level=debug"])?.log_level,
+            Some(LogLevel::Debug)
+        );
+
+        assert!(parse(&["--profile"]).is_err());
+        assert!(parse(&["--unknown"]).is_err());
+        assert!(parse(&["--dry-run"]).is_err());
+        assert!(parse(&["--log-level", "loud"]).is_err());
+        Ok(())
+    }
+}
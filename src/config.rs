@@ -0,0 +1,590 @@
+/// Versioned config file format, the file is migrated forward to the current version and validated when loading
+/// Each migration changes the raw TOML from one version to the next, missing fields are then taken from the defaults
+use crate::{
+    consts::{
+        CONFIG_VERSION, FILTER_REFERENCE_FPS, FPS_MAX, FPS_MIN, PREVIEW_FPS_MAX, PREVIEW_FPS_MIN,
+    },
+    structs::{config::ConfigError, curve::ResponseCurve, network::NetworkTarget, state::AppConfig},
+};
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use toml::{value::Table, Value};
+
+type Migration = fn(&mut Table) -> Result<()>;
+
+// MIGRATIONS[i] migrates from version i to version i + 1
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
+// The speed slider saved its step s as 1 / s² per frame, then as FILTER_REFERENCE_FPS / s² Hz once the filter used
+// Hz, before the file was versioned, so the step matching the value tells its unit
+fn legacy_cutoff_to_hz(min_cutoff: f64) -> f64 {
+    let is_step = |step: f64| step.is_finite() && (step - step.round()).abs() < 1e-3;
+    let reference_fps = FILTER_REFERENCE_FPS as f64;
+
+    if !is_step((1. / min_cutoff).sqrt()) && is_step((reference_fps / min_cutoff).sqrt()) {
+        return min_cutoff;
+    }
+    min_cutoff * reference_fps
+}
+
+// Version 0 is the unversioned file of StableView 1.0, with a single min_cutoff/beta per frame for all axes
+fn migrate_v0_to_v1(table: &mut Table) -> Result<()> {
+    let min_cutoff = table
+        .remove("min_cutoff")
+        .and_then(|value| value.as_float());
+    let beta = table.remove("beta").and_then(|value| value.as_float());
+
+    if table.contains_key("axis_filters") {
+        return Ok(());
+    }
+
+    let mut axis_filters = Value::try_from(AppConfig::default().axis_filters)?;
+    for axis in axis_filters.as_array_mut().into_iter().flatten() {
+        let axis = axis.as_table_mut().context("Invalid default axis filter")?;
+        // The cutoff was given per frame, it is now in Hz
+        if let Some(min_cutoff) = min_cutoff {
+            axis.insert(
+                "min_cutoff".to_string(),
+                Value::Float(legacy_cutoff_to_hz(min_cutoff)),
+            );
+        }
+        if let Some(beta) = beta {
+            axis.insert("beta".to_string(), Value::Float(beta));
+        }
+    }
+    table.insert("axis_filters".to_string(), axis_filters);
+
+    Ok(())
+}
+
+// Fields missing from the file get their default value, nested tables are merged field by field
+// Arrays of tables (eg. [[axis_filters]]) are merged entry by entry, the missing entries keep their default
+fn merge(defaults: &mut Table, table: Table) {
+    for (key, value) in table {
+        match (defaults.get_mut(&key), value) {
+            (Some(Value::Table(default)), Value::Table(value)) => merge(default, value),
+            (Some(Value::Array(defaults)), Value::Array(values))
+                if values.first().is_some_and(Value::is_table) =>
+            {
+                for (i, value) in values.into_iter().enumerate() {
+                    match (defaults.get_mut(i), value) {
+                        (Some(Value::Table(default)), Value::Table(value)) => merge(default, value),
+                        (Some(default), value) => *default = value,
+                        (None, value) => defaults.push(value),
+                    }
+                }
+            }
+            (_, value) => {
+                defaults.insert(key, value);
+            }
+        }
+    }
+}
+
+// Returns the config in the current format and the version it was written in
+pub fn parse(contents: &str) -> Result<(AppConfig, u32)> {
+    let table: Table = toml::from_str(contents).context("The config file is not valid TOML")?;
+    parse_table(table)
+}
+
+pub fn parse_table(mut table: Table) -> Result<(AppConfig, u32)> {
+    let version = match table.get("version") {
+        Some(version) => version
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .context("The config version is not a positive integer")?,
+        None => 0,
+    };
+    if version > CONFIG_VERSION {
+        bail!(
+            "The config file was written by a newer version of StableView (format {}, supported up to {})",
+            version,
+            CONFIG_VERSION
+        );
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(&mut table)
+            .with_context(|| format!("Unable to migrate the config from version {}", from))?;
+    }
+    table.insert("version".to_string(), Value::Integer(CONFIG_VERSION.into()));
+
+    let mut config = match Value::try_from(AppConfig::default())? {
+        Value::Table(defaults) => defaults,
+        _ => bail!("Default config is not a table"),
+    };
+    merge(&mut config, table);
+
+    let config: AppConfig = Value::Table(config)
+        .try_into()
+        .context("The config file has a field with an invalid type")?;
+
+    Ok((config, version))
+}
+
+// Earlier backup with the same contents, eg. of a file failing to load at each start
+fn existing_backup(path: &Path, label: &str) -> Option<PathBuf> {
+    let contents = fs::read(path).ok()?;
+    let prefix = format!("{}.{}-", path.file_name()?.to_string_lossy(), label);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|backup| {
+            let name = backup.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with(&prefix)
+                && name.ends_with(".bak")
+                && fs::read(backup).is_ok_and(|backup| backup == contents)
+        })
+}
+
+// Copy of the file next to it, eg. config.toml.v0-1700000000.bak, unless an identical one already exists
+pub fn backup(path: &Path, label: &str) -> Result<PathBuf> {
+    if let Some(backup) = existing_backup(path, label) {
+        return Ok(backup);
+    }
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".{}-{}.bak", label, timestamp));
+    let backup = PathBuf::from(backup);
+
+    fs::copy(path, &backup).with_context(|| format!("Unable to back up {:?}", path))?;
+    Ok(backup)
+}
+
+// Loads, migrates and validates the config file, invalid values are replaced by their default and returned as errors
+pub fn load_file(path: &Path) -> Result<(AppConfig, Vec<ConfigError>)> {
+    if !path.exists() {
+        let config = AppConfig::default();
+        confy::store_path(path, &config)?;
+        return Ok((config, vec![]));
+    }
+
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+    let (mut config, version) = match parse(&contents) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            // Keeping the file as it is, the user can fix it from the backup
+            let backup = backup(path, "invalid")?;
+            return Err(e.context(format!("Your settings were saved to {:?}", backup)));
+        }
+    };
+
+    if version < CONFIG_VERSION {
+        let backup = backup(path, &format!("v{}", version))?;
+        tracing::warn!(
+            "Migrated config {:?} from version {} to {}, backup saved to {:?}",
+            path,
+            version,
+            CONFIG_VERSION,
+            backup
+        );
+        confy::store_path(path, &config)?;
+    }
+
+    let errors = config.sanitize();
+    for error in &errors {
+        tracing::error!("Invalid config value, using the default : {}", error);
+    }
+
+    Ok((config, errors))
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+fn validate_field<T>(field: &str, result: Result<T>, errors: &mut Vec<ConfigError>) {
+    if let Err(e) = result {
+        errors.push(ConfigError {
+            field: field.to_string(),
+            message: e.to_string(),
+        });
+    }
+}
+
+impl AppConfig {
+    pub fn validate(&self) -> Vec<ConfigError> {
+        let mut errors = vec![];
+
+        validate_field("ip", NetworkTarget::validate_host(&self.ip), &mut errors);
+        validate_field("port", NetworkTarget::validate_port(&self.port), &mut errors);
+        let extra_targets: Result<Vec<NetworkTarget>> = self
+            .extra_targets
+            .iter()
+            .map(|address| NetworkTarget::parse_address(address))
+            .collect();
+        validate_field("extra_targets", extra_targets, &mut errors);
+        validate_field("vmc_ip", NetworkTarget::validate_host(&self.vmc_ip), &mut errors);
+        validate_field("vmc_port", NetworkTarget::validate_port(&self.vmc_port), &mut errors);
+        validate_field(
+            "control_port",
+            NetworkTarget::validate_port(&self.control_port.to_string()),
+            &mut errors,
+        );
+        validate_field(
+            "pose_server_port",
+            NetworkTarget::validate_port(&self.pose_server_port.to_string()),
+            &mut errors,
+        );
+        validate_field(
+            "metrics_port",
+            NetworkTarget::validate_port(&self.metrics_port.to_string()),
+            &mut errors,
+        );
+        if self.pose_server_max_rate == 0 {
+            errors.push(ConfigError {
+                field: "pose_server_max_rate".to_string(),
+                message: "has to be at least 1 frame per second".to_string(),
+            });
+        }
+
+        if !(FPS_MIN..=FPS_MAX).contains(&self.fps) {
+            errors.push(ConfigError {
+                field: "fps".to_string(),
+                message: format!("{} is not between {} and {}", self.fps, FPS_MIN, FPS_MAX),
+            });
+        }
+
+        if !(PREVIEW_FPS_MIN..=PREVIEW_FPS_MAX).contains(&self.preview_fps) {
+            errors.push(ConfigError {
+                field: "preview_fps".to_string(),
+                message: format!(
+                    "{} is not between {} and {}",
+                    self.preview_fps, PREVIEW_FPS_MIN, PREVIEW_FPS_MAX
+                ),
+            });
+        }
+
+        if !(0. ..=1.).contains(&self.min_quality) {
+            errors.push(ConfigError {
+                field: "min_quality".to_string(),
+                message: format!("{} is not between 0 and 1", self.min_quality),
+            });
+        }
+
+        if let Err(e) = self.hotkeys.validate() {
+            errors.push(ConfigError {
+                field: "hotkeys".to_string(),
+                message: e.to_string(),
+            });
+        }
+
+        validate_field("logging", self.logging.validate(), &mut errors);
+        validate_field("palette", self.palette.validate(), &mut errors);
+        validate_field("validation", self.validation.validate(), &mut errors);
+        let curves: Result<Vec<()>> = self.curves.iter().map(ResponseCurve::validate).collect();
+        validate_field("curves", curves, &mut errors);
+
+        let parameters_valid = self.axis_filters.iter().all(|axis| {
+            [
+                axis.min_cutoff,
+                axis.beta,
+                axis.ema_time_constant,
+                axis.kalman_process_noise,
+                axis.kalman_measurement_noise,
+                axis.accela_smoothing,
+                axis.accela_deadzone,
+            ]
+            .iter()
+            .all(|value| value.is_finite() && *value >= 0.)
+        });
+        if !parameters_valid {
+            errors.push(ConfigError {
+                field: "axis_filters".to_string(),
+                message: "filter parameters have to be positive numbers".to_string(),
+            });
+        }
+
+        errors
+    }
+
+    // Replaces the invalid fields by their default value, returning the errors found
+    pub fn sanitize(&mut self) -> Vec<ConfigError> {
+        let errors = self.validate();
+        if errors.is_empty() {
+            return errors;
+        }
+
+        let default = AppConfig::default();
+        for error in &errors {
+            match error.field.as_str() {
+                "ip" => self.ip = default.ip.clone(),
+                "port" => self.port = default.port.clone(),
+                "extra_targets" => self.extra_targets = default.extra_targets.clone(),
+                "vmc_ip" => self.vmc_ip = default.vmc_ip.clone(),
+                "vmc_port" => self.vmc_port = default.vmc_port.clone(),
+                "control_port" => self.control_port = default.control_port,
+                "pose_server_port" => self.pose_server_port = default.pose_server_port,
+                "pose_server_max_rate" => self.pose_server_max_rate = default.pose_server_max_rate,
+                "metrics_port" => self.metrics_port = default.metrics_port,
+                "fps" => self.fps = default.fps,
+                "preview_fps" => self.preview_fps = default.preview_fps,
+                "min_quality" => self.min_quality = default.min_quality,
+                "hotkeys" => self.hotkeys = default.hotkeys.clone(),
+                "logging" => self.logging = default.logging.clone(),
+                "palette" => self.palette = default.palette.clone(),
+                "validation" => self.validation = default.validation,
+                "axis_filters" => self.axis_filters = default.axis_filters,
+                "curves" => self.curves = default.curves.clone(),
+                _ => {}
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::app_theme::AppTheme;
+
+    // config.toml as written by StableView 1.0
+    const LEGACY_CONFIG: &str = r#"
+ip = "192.168.1.20"
+port = "5555"
+min_cutoff = 0.0025
+beta = 0.02
+fps = 30
+selected_camera = "Webcam"
+hide_camera = false
+"#;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("stableview-config-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("config.toml");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_migrate_legacy_config() -> Result<()> {
+        let (config, version) = parse(LEGACY_CONFIG)?;
+
+        assert_eq!(version, 0);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.ip, "192.168.1.20");
+        assert_eq!(config.port, "5555");
+        assert_eq!(config.fps, 30);
+        assert!(!config.hide_camera);
+        assert_eq!(config.theme, AppTheme::System);
+
+        // Per frame cutoff converted to Hz, for every axis
+        for axis in config.axis_filters {
+            assert!((axis.min_cutoff - 0.15).abs() < 1e-6);
+            assert!((axis.beta - 0.02).abs() < 1e-6);
+        }
+
+        // New fields get their default value
+        assert_eq!(config.vmc_port, AppConfig::default().vmc_port);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_cutoff_in_hz() -> Result<()> {
+        // Saved with the cutoff in Hz, before the file was versioned, slider step 10
+        let config =
+            LEGACY_CONFIG.replace("min_cutoff = 0.0025", "min_cutoff = 0.6000000238418579");
+        let (config, version) = parse(&config)?;
+
+        assert_eq!(version, 0);
+        for axis in config.axis_filters {
+            assert!((axis.min_cutoff - 0.6).abs() < 1e-6, "{}", axis.min_cutoff);
+        }
+
+        // Per frame values of every step of the 1.0 slider are converted
+        for step in 1..=50 {
+            // Saved as f32
+            let min_cutoff = (1. / (step * step) as f32) as f64;
+            let expected = min_cutoff * FILTER_REFERENCE_FPS as f64;
+            assert!(
+                (legacy_cutoff_to_hz(min_cutoff) - expected).abs() < 1e-9,
+                "step {}",
+                step
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_current_config_round_trip() -> Result<()> {
+        let mut config = AppConfig {
+            fps: 90,
+            theme: AppTheme::Custom,
+            ..AppConfig::default()
+        };
+        config.palette.primary = "#ff8800".to_string();
+        config.curves[4].points[2] = [0.5, 0.3];
+        let (parsed, version) = parse(&toml::to_string(&config)?)?;
+
+        assert_eq!(version, CONFIG_VERSION);
+        assert_eq!(parsed.fps, 90);
+        assert_eq!(parsed.theme, AppTheme::Custom);
+        assert_eq!(parsed.palette, config.palette);
+        assert_eq!(parsed.axis_filters, config.axis_filters);
+        assert_eq!(parsed.curves, config.curves);
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_arrays_of_tables() -> Result<()> {
+        let (config, _) = parse(
+            r#"
+version = 1
+extra_targets = ["127.0.0.1:5000"]
+
+[[axis_filters]]
+beta = 0.5
+
+[[curves]]
+
+[[curves]]
+max_input = 90.0
+"#,
+        )?;
+        let default = AppConfig::default();
+
+        // Only the given fields of the given entries change
+        assert_eq!(config.axis_filters[0].beta, 0.5);
+        assert_eq!(
+            config.axis_filters[0].min_cutoff,
+            default.axis_filters[0].min_cutoff
+        );
+        assert_eq!(config.axis_filters[1..], default.axis_filters[1..]);
+        assert_eq!(config.curves[0], default.curves[0]);
+        assert_eq!(config.curves[1].max_input, 90.);
+        assert_eq!(config.curves[1].points, default.curves[1].points);
+        assert_eq!(config.curves[2..], default.curves[2..]);
+
+        // Other arrays are replaced
+        assert_eq!(config.extra_targets, ["127.0.0.1:5000"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_or_invalid_config() {
+        assert!(parse("version = 999").is_err());
+        assert!(parse("version = -1").is_err());
+        assert!(parse("fps = \"fast\"").is_err());
+        assert!(parse("ip = ").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = AppConfig {
+            ip: "localhost:4242".to_string(),
+            port: "70000".to_string(),
+            extra_targets: vec!["[::1]:4242".to_string(), "::1:4242".to_string()],
+            vmc_ip: "my-pc.local".to_string(),
+            vmc_port: "0".to_string(),
+            fps: 500,
+            preview_fps: 0,
+            min_quality: f32::NAN,
+            ..AppConfig::default()
+        };
+        config.axis_filters[3].beta = -1.;
+        config.curves[1].max_input = 0.;
+        config.validation.max_angular_speed = 0.;
+        config.palette.text = "white".to_string();
+
+        let fields: Vec<String> = config
+            .validate()
+            .into_iter()
+            .map(|error| error.field)
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "ip",
+                "port",
+                "extra_targets",
+                "vmc_port",
+                "fps",
+                "preview_fps",
+                "min_quality",
+                "palette",
+                "validation",
+                "curves",
+                "axis_filters"
+            ]
+        );
+
+        // Only the invalid fields are reset
+        config.hide_camera = false;
+        config.sanitize();
+        assert!(config.validate().is_empty());
+        assert_eq!(config.port, AppConfig::default().port);
+        assert!(!config.hide_camera);
+
+        assert!(AppConfig::default().validate().is_empty());
+    }
+
+    #[test]
+    fn test_load_file_backs_up_before_migrating() -> Result<()> {
+        let path = temp_file("migrate", LEGACY_CONFIG);
+        let (config, errors) = load_file(&path)?;
+        assert!(errors.is_empty());
+        assert_eq!(config.fps, 30);
+
+        // The original file is kept as a backup and the file is rewritten in the current format
+        let backups: Vec<PathBuf> = fs::read_dir(path.parent().unwrap())?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|backup| backup.to_string_lossy().contains(".v0-"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(&backups[0])?, LEGACY_CONFIG);
+        assert_eq!(parse(&fs::read_to_string(&path)?)?.1, CONFIG_VERSION);
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_file_keeps_unreadable_config() -> Result<()> {
+        let path = temp_file("invalid", "fps = \"fast\"");
+        let backups = || -> Result<Vec<PathBuf>> {
+            Ok(fs::read_dir(path.parent().unwrap())?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|backup| backup.to_string_lossy().contains(".invalid-"))
+                .collect())
+        };
+        assert!(load_file(&path).is_err());
+
+        // Left untouched, with a backup
+        assert_eq!(fs::read_to_string(&path)?, "fps = \"fast\"");
+        assert_eq!(backups()?.len(), 1);
+
+        // Made at an earlier start, it is not copied again while the file is unchanged
+        let earlier = path.with_extension("toml.invalid-1.bak");
+        fs::rename(&backups()?[0], &earlier)?;
+        assert!(load_file(&path).is_err());
+        assert_eq!(backups()?, [earlier]);
+
+        fs::write(&path, "fps = \"faster\"")?;
+        assert!(load_file(&path).is_err());
+        assert_eq!(backups()?.len(), 2);
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+}
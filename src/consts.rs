@@ -4,8 +4,35 @@ pub const APP_NAME: &str = env!("CARGO_PKG_NAME");
 pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const APP_REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
 pub const APP_AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
-pub const APP_GITHUB_API: &str =
-    "https://api.github.com/repos/shubhamai/stableview/releases/latest";
+// Every release, as the latest one is never a pre-release
+pub const APP_GITHUB_API: &str = "https://api.github.com/repos/shubhamai/stableview/releases";
+
+// Version of the config file format, increased with each migration in config.rs
+pub const CONFIG_VERSION: u32 = 1;
+
+// Frame rates allowed in the config, same as the bounds of the FPS slider
+pub const FPS_MIN: u32 = 15;
+pub const FPS_MAX: u32 = 120;
+
+// Rates allowed for the camera preview, redrawn independently of the tracking
+pub const PREVIEW_FPS_MIN: u32 = 1;
+pub const PREVIEW_FPS_MAX: u32 = 60;
+// Height of the preview widget, frames are downscaled to it before being given to the GUI
+pub const PREVIEW_HEIGHT: i32 = 200;
+
+// Profile stored in the historical config.toml
+pub const DEFAULT_PROFILE: &str = "Default";
+
+// Frame rate at which the filter sliders were tuned, used to convert them to cutoff frequencies in Hz
+pub const FILTER_REFERENCE_FPS: f32 = 60.;
+
+// Smallest roi box area the landmark crop is used for, tddfa crops the face box instead below it
+pub const MIN_ROI_AREA: f32 = 2020.;
+
+// A head hardly moves faster than this, in degrees and units sent to opentrack per second
+// Default limits of the validation, and speeds scoring 0 in the tracking quality
+pub const MAX_ANGULAR_SPEED: f32 = 720.;
+pub const MAX_LINEAR_SPEED: f32 = 200.;
 
 pub const MODEL: &[u8] = include_bytes!("../assets/model/mb05_120x120.onnx");
 pub const DATA: &[u8] = include_bytes!("../assets/model/data.json");
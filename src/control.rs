@@ -0,0 +1,478 @@
+/// Local control server, so scripts can start/stop/recenter the tracking and change its configuration
+/// Requests and responses are newline delimited JSON, over localhost TCP and a Unix socket (on unix systems)
+/// Each request carries the control_token of the config, so a web page posting to localhost can not send commands
+use crate::{
+    consts::APP_NAME,
+    enums::control_request::ControlRequest,
+    structs::{
+        broadcast::PoseBroadcaster,
+        control::{ControlCommand, ControlResponse, ControlServer},
+    },
+};
+use anyhow::{bail, Context, Result};
+use crossbeam_channel::{bounded, Sender};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+// Time given to the GUI to answer a request
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl ControlResponse {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+}
+
+impl ControlServer {
+    pub fn start(
+        port: u16,
+        socket_path: Option<PathBuf>,
+        token: String,
+        commands: Sender<ControlCommand>,
+        poses: PoseBroadcaster,
+    ) -> Result<Self> {
+        if token.trim().is_empty() {
+            bail!("control_token has to be set in the config file to use the control server");
+        }
+
+        // Only reachable from this computer
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("Unable to bind control server on port {}", port))?;
+        let tcp_address = listener.local_addr()?;
+        tracing::info!("Control server listening on {}", tcp_address);
+
+        {
+            let token = token.clone();
+            let commands = commands.clone();
+            let poses = poses.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    spawn_client(stream.try_clone(), stream, &token, &commands, &poses);
+                }
+            });
+        }
+
+        #[cfg(unix)]
+        let socket_path = match socket_path {
+            Some(path) => Some(Self::listen_unix(path, token, commands, poses)?),
+            None => None,
+        };
+        #[cfg(not(unix))]
+        let socket_path = {
+            let _ = (socket_path, token);
+            None
+        };
+
+        Ok(Self {
+            tcp_address,
+            socket_path,
+        })
+    }
+
+    #[cfg(unix)]
+    fn listen_unix(
+        path: PathBuf,
+        token: String,
+        commands: Sender<ControlCommand>,
+        poses: PoseBroadcaster,
+    ) -> Result<PathBuf> {
+        use std::{
+            io::ErrorKind,
+            os::unix::{
+                fs::PermissionsExt,
+                net::{UnixListener, UnixStream},
+            },
+        };
+
+        // A socket still accepting connections belongs to another instance, one refusing them was left behind
+        // when the application was killed
+        match UnixStream::connect(&path) {
+            Ok(_) => bail!(
+                "Another instance is running, its control socket {:?} is in use",
+                path
+            ),
+            Err(e) if e.kind() == ErrorKind::ConnectionRefused => std::fs::remove_file(&path)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Unable to check control socket {:?}", path))
+            }
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Unable to bind control socket {:?}", path))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        tracing::info!("Control server listening on {:?}", path);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                spawn_client(stream.try_clone(), stream, &token, &commands, &poses);
+            }
+        });
+
+        Ok(path)
+    }
+
+    // Socket in the runtime directory when available, eg. /run/user/1000/StableView/control.sock
+    pub fn default_socket_path() -> Option<PathBuf> {
+        let dirs = directories::ProjectDirs::from("rs", "", APP_NAME)?;
+        Some(
+            dirs.runtime_dir()
+                .unwrap_or_else(|| dirs.data_dir())
+                .join("control.sock"),
+        )
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        if let Some(path) = &self.socket_path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn write_line<T: serde::Serialize>(writer: &mut impl Write, value: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Each connection gets its own thread, reader being a clone of the stream
+fn spawn_client<S: Read + Write + Send + 'static>(
+    reader: std::io::Result<S>,
+    stream: S,
+    token: &str,
+    commands: &Sender<ControlCommand>,
+    poses: &PoseBroadcaster,
+) {
+    let token = token.to_string();
+    let commands = commands.clone();
+    let poses = poses.clone();
+    thread::spawn(move || {
+        let reader = match reader {
+            Ok(reader) => reader,
+            Err(e) => return tracing::error!("Control connection error: {}", e),
+        };
+        if let Err(e) = handle_client(reader, stream, &token, &commands, &poses) {
+            tracing::info!("Control connection closed: {}", e);
+        }
+    });
+}
+
+// Anything which is not a JSON request with the token, eg. the headers of an HTTP request, closes the connection
+fn handle_client(
+    reader: impl Read,
+    mut writer: impl Write,
+    token: &str,
+    commands: &Sender<ControlCommand>,
+    poses: &PoseBroadcaster,
+) -> Result<()> {
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let message: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                write_line(&mut writer, &ControlResponse::error(e.to_string()))?;
+                bail!("Not a JSON request");
+            }
+        };
+        if message.get("token").and_then(|value| value.as_str()) != Some(token) {
+            write_line(&mut writer, &ControlResponse::error("Invalid token"))?;
+            bail!("Invalid token");
+        }
+        let request: ControlRequest = match serde_json::from_value(message) {
+            Ok(request) => request,
+            Err(e) => {
+                write_line(&mut writer, &ControlResponse::error(e.to_string()))?;
+                continue;
+            }
+        };
+
+        // Streaming until the client disconnects, the write failing
+        if request == ControlRequest::SubscribePose {
+            let receiver = poses.subscribe();
+            write_line(&mut writer, &ControlResponse::ok())?;
+            for sample in receiver {
+                write_line(&mut writer, &sample)?;
+            }
+            return Ok(());
+        }
+
+        let (reply, response) = bounded(1);
+        let response = match commands.send(ControlCommand { request, reply }) {
+            Ok(_) => response
+                .recv_timeout(REPLY_TIMEOUT)
+                .unwrap_or_else(|_| ControlResponse::error("The application did not answer")),
+            Err(_) => ControlResponse::error("The application is closing"),
+        };
+        write_line(&mut writer, &response)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        enums::rotation_order::RotationOrder,
+        structs::{broadcast::PoseSample, control::ControlStatus, pose::Pose},
+    };
+    use crossbeam_channel::unbounded;
+    use std::net::TcpStream;
+
+    const TOKEN: &str = "secret";
+
+    // Answers the commands like the GUI would, returning the requests received
+    fn start_server() -> (
+        ControlServer,
+        PoseBroadcaster,
+        thread::JoinHandle<Vec<ControlRequest>>,
+    ) {
+        let (sender, receiver) = unbounded::<ControlCommand>();
+        let poses = PoseBroadcaster::default();
+        let server =
+            ControlServer::start(0, None, TOKEN.to_string(), sender, poses.clone()).unwrap();
+
+        let app = thread::spawn(move || {
+            let mut requests = vec![];
+            while let Ok(command) = receiver.recv_timeout(Duration::from_secs(2)) {
+                let response = match command.request {
+                    ControlRequest::GetStatus => ControlResponse {
+                        status: Some(ControlStatus {
+                            running: true,
+                            output_paused: false,
+                            error: String::new(),
+                            camera: "Camera".to_owned(),
+                            fps: 60,
+                        }),
+                        ..ControlResponse::ok()
+                    },
+                    _ => ControlResponse::ok(),
+                };
+                requests.push(command.request);
+                command.reply.send(response).unwrap();
+            }
+            requests
+        });
+
+        (server, poses, app)
+    }
+
+    // Written at once, the server closing with unread data would reset the connection
+    fn request(stream: &mut TcpStream, line: &str) -> ControlResponse {
+        stream.write_all(format!("{}\n", line).as_bytes()).unwrap();
+        let mut response = String::new();
+        BufReader::new(stream.try_clone().unwrap())
+            .read_line(&mut response)
+            .unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_control_commands() {
+        let (server, _, app) = start_server();
+        let mut stream = TcpStream::connect(server.tcp_address).unwrap();
+
+        assert_eq!(
+            request(&mut stream, r#"{"token": "secret", "command": "start"}"#),
+            ControlResponse::ok()
+        );
+        assert_eq!(
+            request(&mut stream, r#"{"token": "secret", "command": "recenter"}"#),
+            ControlResponse::ok()
+        );
+
+        let response = request(
+            &mut stream,
+            r#"{"token": "secret", "command": "get_status"}"#,
+        );
+        assert!(response.ok);
+        assert_eq!(response.status.unwrap().fps, 60);
+
+        // Invalid requests are answered without reaching the application
+        let response = request(&mut stream, r#"{"token": "secret", "command": "fly"}"#);
+        assert!(!response.ok);
+        assert!(response.error.is_some());
+
+        request(
+            &mut stream,
+            r#"{"token": "secret", "command": "set_config", "config": {"fps": 30}}"#,
+        );
+        drop(stream);
+
+        assert_eq!(
+            app.join().unwrap(),
+            [
+                ControlRequest::Start,
+                ControlRequest::Recenter,
+                ControlRequest::GetStatus,
+                ControlRequest::SetConfig {
+                    config: serde_json::json!({"fps": 30})
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_control_requires_token() {
+        let (sender, _) = unbounded::<ControlCommand>();
+        assert!(
+            ControlServer::start(0, None, " ".to_string(), sender, PoseBroadcaster::default())
+                .is_err()
+        );
+
+        let (server, _, app) = start_server();
+        let closed = |stream: TcpStream| {
+            let mut rest = String::new();
+            BufReader::new(stream).read_to_string(&mut rest).unwrap();
+            rest.is_empty()
+        };
+
+        // Wrong or missing token
+        for line in [
+            r#"{"token": "guess", "command": "stop"}"#,
+            r#"{"command": "stop"}"#,
+        ] {
+            let mut stream = TcpStream::connect(server.tcp_address).unwrap();
+            assert_eq!(
+                request(&mut stream, line).error.as_deref(),
+                Some("Invalid token")
+            );
+            assert!(closed(stream));
+        }
+
+        // A web page posting to the port, the body is never read
+        let mut stream = TcpStream::connect(server.tcp_address).unwrap();
+        let response = request(
+            &mut stream,
+            "POST / HTTP/1.1\r\nContent-Type: text/plain\r\n\r\n{\"command\": \"stop\"}",
+        );
+        assert!(!response.ok);
+        assert!(closed(stream));
+
+        assert!(app.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_control_pose_stream() {
+        let (server, poses, _) = start_server();
+        let mut stream = TcpStream::connect(server.tcp_address).unwrap();
+
+        assert_eq!(
+            request(
+                &mut stream,
+                r#"{"token": "secret", "command": "subscribe_pose"}"#
+            ),
+            ControlResponse::ok()
+        );
+
+        let sample = PoseSample::new(0.5, Pose::default(), RotationOrder::default(), 1.);
+        poses.publish(sample);
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert_eq!(serde_json::from_str::<PoseSample>(&line).unwrap(), sample);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_control_unix_socket() {
+        use std::os::unix::net::UnixStream;
+
+        let path =
+            std::env::temp_dir().join(format!("stableview-test-{}.sock", std::process::id()));
+        let (sender, receiver) = unbounded::<ControlCommand>();
+        let server = ControlServer::start(
+            0,
+            Some(path.clone()),
+            TOKEN.to_string(),
+            sender,
+            PoseBroadcaster::default(),
+        )
+        .unwrap();
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        writeln!(stream, r#"{{"token": "secret", "command": "stop"}}"#).unwrap();
+
+        let command = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(command.request, ControlRequest::Stop);
+        command.reply.send(ControlResponse::ok()).unwrap();
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ControlResponse>(&line).unwrap(),
+            ControlResponse::ok()
+        );
+
+        // The socket file is removed with the server
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_control_unix_socket_in_use() {
+        use std::os::unix::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!(
+            "stableview-test-in-use-{}.sock",
+            std::process::id()
+        ));
+        let (sender, _receiver) = unbounded::<ControlCommand>();
+
+        // Left behind by a killed instance
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        let server = ControlServer::start(
+            0,
+            Some(path.clone()),
+            TOKEN.to_string(),
+            sender.clone(),
+            PoseBroadcaster::default(),
+        )
+        .unwrap();
+
+        // The socket of the running instance is kept
+        match ControlServer::start(
+            0,
+            Some(path.clone()),
+            TOKEN.to_string(),
+            sender,
+            PoseBroadcaster::default(),
+        ) {
+            Ok(_) => panic!("The control socket of the running instance was taken over"),
+            Err(e) => assert!(e.to_string().contains("Another instance")),
+        }
+        assert!(path.exists());
+
+        drop(server);
+        assert!(!path.exists());
+    }
+}
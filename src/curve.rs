@@ -0,0 +1,207 @@
+/// Response curves shaping the pose after the filter, one per axis, as edited in the curve editor of the GUI
+use crate::{
+    enums::{curve_preset::CurvePreset, rotation_order::RotationOrder},
+    structs::{curve::ResponseCurve, pose::Pose},
+};
+use anyhow::{bail, Result};
+
+// Control points of the presets, evenly spaced inputs
+const PRESET_POINTS: usize = 5;
+// Closest two control points can get, as a fraction of max_input
+const MIN_POINT_GAP: f32 = 0.02;
+
+impl CurvePreset {
+    pub fn points(self) -> Vec<[f32; 2]> {
+        (0..PRESET_POINTS)
+            .map(|i| {
+                let x = i as f32 / (PRESET_POINTS - 1) as f32;
+                let y = match self {
+                    CurvePreset::Linear => x,
+                    // Smoothstep, less sensitive at the center and the ends
+                    CurvePreset::SCurve => x * x * (3. - 2. * x),
+                    // Small movements are damped, large ones amplified
+                    CurvePreset::Exponential => (x * 2_f32).exp_m1() / 2_f32.exp_m1(),
+                };
+                [x, y]
+            })
+            .collect()
+    }
+}
+
+impl ResponseCurve {
+    pub fn new(max_input: f32, preset: CurvePreset) -> Self {
+        Self {
+            max_input,
+            points: preset.points(),
+        }
+    }
+
+    // x, y and z in the units sent to opentrack, then yaw, pitch and roll in degrees
+    pub fn default_axes() -> [Self; 6] {
+        [
+            Self::new(30., CurvePreset::Linear),
+            Self::new(30., CurvePreset::Linear),
+            Self::new(30., CurvePreset::Linear),
+            Self::new(90., CurvePreset::Linear),
+            Self::new(90., CurvePreset::Linear),
+            Self::new(90., CurvePreset::Linear),
+        ]
+    }
+
+    // Output leaves the input unchanged
+    pub fn is_linear(&self) -> bool {
+        self.points
+            .iter()
+            .all(|point| (point[0] - point[1]).abs() < f32::EPSILON)
+    }
+
+    // Output of the curve for a fraction of max_input, past the end the output goes on with a slope of 1
+    pub fn evaluate(&self, x: f32) -> f32 {
+        let last = match self.points.last() {
+            Some(last) => *last,
+            None => return x,
+        };
+        match self.points.windows(2).find(|pair| x <= pair[1][0]) {
+            Some(pair) => {
+                let [x0, y0] = pair[0];
+                let [x1, y1] = pair[1];
+                y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+            }
+            None => last[1] + x - last[0],
+        }
+    }
+
+    pub fn apply(&self, value: f32) -> f32 {
+        if self.max_input <= 0. || self.points.len() < 2 {
+            return value;
+        }
+        value.signum() * self.evaluate(value.abs() / self.max_input) * self.max_input
+    }
+
+    // Moves a control point while dragging it, the first one stays at [0, 0], the last one at an input of 1
+    // and the others between their neighbours
+    pub fn move_point(&mut self, index: usize, point: [f32; 2]) {
+        let count = self.points.len();
+        if index == 0 || index >= count {
+            return;
+        }
+        let x = match index == count - 1 {
+            true => 1.,
+            false => point[0].clamp(
+                self.points[index - 1][0] + MIN_POINT_GAP,
+                self.points[index + 1][0] - MIN_POINT_GAP,
+            ),
+        };
+        self.points[index] = [x, point[1].clamp(0., 1.)];
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(self.max_input.is_finite() && self.max_input > 0.) {
+            bail!("{} is not a positive max_input", self.max_input);
+        }
+        if self.points.len() < 2 {
+            bail!("a curve needs at least 2 points");
+        }
+        if self.points[0] != [0., 0.] || self.points[self.points.len() - 1][0] != 1. {
+            bail!("a curve has to go from [0, 0] to an input of 1");
+        }
+        let valid = self
+            .points
+            .iter()
+            .all(|point| (0. ..=1.).contains(&point[0]) && (0. ..=1.).contains(&point[1]))
+            && self.points.windows(2).all(|pair| pair[0][0] < pair[1][0]);
+        if !valid {
+            bail!("points have to be between 0 and 1, sorted by input");
+        }
+        Ok(())
+    }
+}
+
+// Applies the curve of each axis to the pose, converting the rotation to the euler angles sent to opentrack and back
+pub fn apply_curves(curves: &[ResponseCurve; 6], pose: Pose, order: RotationOrder) -> Pose {
+    // Nothing to change, avoiding the round trip through the euler angles
+    if curves.iter().all(ResponseCurve::is_linear) {
+        return pose;
+    }
+
+    let mut data = pose.to_euler_data(order);
+    for (value, curve) in data.iter_mut().zip(curves) {
+        *value = curve.apply(*value);
+    }
+    Pose::from_euler_data(data, order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for preset in CurvePreset::ALL {
+            let curve = ResponseCurve::new(90., preset);
+            assert!(curve.validate().is_ok(), "{}", preset);
+            assert_eq!(curve.apply(0.), 0.);
+            assert!((curve.apply(90.) - 90.).abs() < 1e-4);
+            assert!((curve.apply(45.) + curve.apply(-45.)).abs() < 1e-4);
+        }
+        assert!(ResponseCurve::new(90., CurvePreset::Linear).is_linear());
+
+        // Damped around the center
+        assert!(ResponseCurve::new(90., CurvePreset::SCurve).apply(10.) < 10.);
+        assert!(ResponseCurve::new(90., CurvePreset::Exponential).apply(10.) < 10.);
+        assert!((ResponseCurve::new(90., CurvePreset::SCurve).apply(45.) - 45.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_apply() {
+        let curve = ResponseCurve {
+            max_input: 10.,
+            points: vec![[0., 0.], [0.5, 0.2], [1., 1.]],
+        };
+        assert!((curve.apply(2.5) - 1.).abs() < 1e-5);
+        assert!((curve.apply(-7.5) + 6.).abs() < 1e-5);
+        // Past the end of the curve
+        assert!((curve.apply(12.) - 12.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_move_point() {
+        let mut curve = ResponseCurve::new(90., CurvePreset::Linear);
+        curve.move_point(0, [0.3, 0.3]);
+        assert_eq!(curve.points[0], [0., 0.]);
+
+        curve.move_point(4, [0.5, 0.8]);
+        assert_eq!(curve.points[4], [1., 0.8]);
+
+        // Kept between its neighbours (0.25 and 0.75)
+        curve.move_point(2, [0.9, 1.5]);
+        assert_eq!(curve.points[2], [0.75 - MIN_POINT_GAP, 1.]);
+        assert!(curve.validate().is_ok());
+
+        curve.points.swap(1, 2);
+        assert!(curve.validate().is_err());
+        assert!(ResponseCurve::new(0., CurvePreset::Linear)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_apply_curves() {
+        let order = RotationOrder::default();
+        let pose = Pose::from_euler_data([1., 2., 3., 30., -20., 10.], order);
+
+        let mut curves = ResponseCurve::default_axes();
+        assert_eq!(apply_curves(&curves, pose, order), pose);
+
+        curves[3] = ResponseCurve {
+            max_input: 90.,
+            points: vec![[0., 0.], [1., 0.5]],
+        };
+        curves[0].points[1] = [0.25, 0.5];
+        let data = apply_curves(&curves, pose, order).to_euler_data(order);
+        let expected = [2., 2., 3., 15., -20., 10.];
+        for (value, expected) in data.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-3, "{:?}", data);
+        }
+    }
+}
@@ -0,0 +1,323 @@
+/// Diagnostics bundle for bug reports, a zip archive with the logs, the settings, the cameras, the system and the last poses
+use crate::{
+    consts::{APP_NAME, APP_VERSION},
+    enums::config_format::ConfigFormat,
+    logging::{format_timestamp, log_directory, SESSION_ID},
+    portable::export_profile,
+    structs::{
+        broadcast::PoseSample, camera::ThreadedCamera, diagnostics::Diagnostics, metrics::Metrics,
+        state::AppConfig,
+    },
+};
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, File},
+    io::Write,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+const REDACTED: &str = "<redacted>";
+
+// Addresses of the other computers are not needed to look into an issue, the ports are kept
+fn redact(config: &AppConfig) -> AppConfig {
+    AppConfig {
+        ip: REDACTED.to_string(),
+        extra_targets: config
+            .extra_targets
+            .iter()
+            .map(|address| match address.rsplit_once(':') {
+                Some((_, port)) => format!("{}:{}", REDACTED, port),
+                None => REDACTED.to_string(),
+            })
+            .collect(),
+        vmc_ip: REDACTED.to_string(),
+        ..config.clone()
+    }
+}
+
+// Host names of the config, the addresses logged by the sinks and servers are also redacted from the logs
+fn configured_hosts(config: &AppConfig) -> Vec<&str> {
+    let extra_hosts = config.extra_targets.iter().map(|address| {
+        address
+            .rsplit_once(':')
+            .map_or(address.as_str(), |(host, _)| host)
+    });
+    [config.ip.as_str(), config.vmc_ip.as_str()]
+        .into_iter()
+        .chain(extra_hosts)
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+// Loopback and unspecified addresses only tell on which interface a server listens, they are kept
+fn redact_token(token: &str, hosts: &[&str]) -> String {
+    let is_address = |host: &str| match host.parse::<IpAddr>() {
+        Ok(ip) => !ip.is_loopback() && !ip.is_unspecified(),
+        Err(_) => hosts.contains(&host),
+    };
+
+    // Punctuation ending a sentence is not part of the address
+    let address = token.trim_end_matches(['.', ':']);
+    let suffix = &token[address.len()..];
+    if is_address(address) {
+        return format!("{}{}", REDACTED, suffix);
+    }
+    match address.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>().is_ok() && is_address(host) => {
+            format!("{}:{}{}", REDACTED, port, suffix)
+        }
+        _ => token.to_string(),
+    }
+}
+
+fn redact_log(contents: &str, hosts: &[&str]) -> String {
+    // IPv6 addresses are split from their port by the brackets
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '-');
+
+    let mut redacted = String::with_capacity(contents.len());
+    let mut rest = contents;
+    while let Some(start) = rest.find(is_token_char) {
+        redacted.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c| !is_token_char(c)).unwrap_or(rest.len());
+        redacted.push_str(&redact_token(&rest[..end], hosts));
+        rest = &rest[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+impl Diagnostics {
+    // Metrics, execution providers and poses are only given by the running application
+    pub fn collect(
+        profile: &str,
+        config: AppConfig,
+        metrics: Option<&Metrics>,
+        inference: Option<String>,
+        poses: Vec<PoseSample>,
+    ) -> Self {
+        let cameras = match ThreadedCamera::get_available_cameras() {
+            Ok(cameras) => {
+                let mut cameras: Vec<(String, i32)> = cameras.into_iter().collect();
+                cameras.sort_by_key(|(_, index)| *index);
+                cameras
+                    .iter()
+                    .map(|(name, index)| format!("{}: {}\n", index, name))
+                    .collect()
+            }
+            Err(e) => format!("Unable to list the cameras: {:#}\n", e),
+        };
+
+        let mut inference = inference.unwrap_or_else(|| {
+            "Not available, the models are loaded when the tracking starts\n".to_string()
+        });
+        if let Ok(devices) = std::env::var("CUDA_VISIBLE_DEVICES") {
+            inference.push_str(&format!("CUDA_VISIBLE_DEVICES={}\n", devices));
+        }
+
+        Self {
+            system: system_info(profile),
+            profile: profile.to_string(),
+            config,
+            cameras,
+            inference,
+            metrics: metrics.map(Metrics::render),
+            poses,
+            log_directory: log_directory().ok(),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path).with_context(|| format!("Unable to create {:?}", path))?;
+        let mut zip = ZipWriter::new(file);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let metrics = match &self.metrics {
+            Some(metrics) => metrics.clone(),
+            None => "Not available, exported from the command line\n".to_string(),
+        };
+        let entries = [
+            ("system.txt", self.system.clone()),
+            (
+                "config.toml",
+                export_profile(&redact(&self.config), ConfigFormat::Toml)?,
+            ),
+            ("cameras.txt", self.cameras.clone()),
+            ("inference.txt", self.inference.clone()),
+            ("metrics.txt", metrics),
+            ("poses.csv", poses_csv(&self.poses)),
+        ];
+        for (name, contents) in entries {
+            zip.start_file(name, options)?;
+            zip.write_all(contents.as_bytes())?;
+        }
+
+        let hosts = configured_hosts(&self.config);
+        for log in self.log_files() {
+            let name = log.file_name().unwrap_or_default().to_string_lossy();
+            let contents = fs::read(&log).with_context(|| format!("Unable to read {:?}", log))?;
+            zip.start_file(format!("logs/{}", name), options)?;
+            zip.write_all(redact_log(&String::from_utf8_lossy(&contents), &hosts).as_bytes())?;
+        }
+
+        zip.finish()?;
+        tracing::info!(
+            "Diagnostics of profile {} saved to {:?}",
+            self.profile,
+            path
+        );
+        Ok(())
+    }
+
+    // Current and rotated log files
+    fn log_files(&self) -> Vec<PathBuf> {
+        let entries = match self.log_directory.as_ref().map(fs::read_dir) {
+            Some(Ok(entries)) => entries,
+            _ => return vec![],
+        };
+        let mut logs: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("StableView") && name.ends_with(".log"))
+            })
+            .collect();
+        logs.sort();
+        logs
+    }
+}
+
+fn system_info(profile: &str) -> String {
+    format!(
+        "{} {}\nOS: {} ({}, {})\nSession: {}\nProfile: {}\nExported: {} UTC\n",
+        APP_NAME,
+        APP_VERSION,
+        std::env::consts::OS,
+        std::env::consts::FAMILY,
+        std::env::consts::ARCH,
+        *SESSION_ID,
+        profile,
+        format_timestamp(SystemTime::now())
+    )
+}
+
+// Only the pose numbers are recorded, with the time starting at 0, never the camera image
+fn poses_csv(poses: &[PoseSample]) -> String {
+    let start = poses.first().map_or(0., |pose| pose.timestamp);
+    let mut csv = "timestamp,x,y,z,yaw,pitch,roll\n".to_string();
+    for pose in poses {
+        csv.push_str(&format!(
+            "{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3}\n",
+            pose.timestamp - start,
+            pose.x,
+            pose.y,
+            pose.z,
+            pose.yaw,
+            pose.pitch,
+            pose.roll
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{enums::rotation_order::RotationOrder, structs::pose::Pose};
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_write_diagnostics() -> Result<()> {
+        let directory =
+            std::env::temp_dir().join(format!("stableview-diagnostics-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let log_directory = directory.join("logs");
+        fs::create_dir_all(&log_directory)?;
+        fs::write(
+            log_directory.join("StableView.log"),
+            "Config : ip 192.168.1.20, vmc my-pc.local\n\
+             Sending data to [192.168.1.20:4242, [fe80::1]:4243]\n\
+             Sending VMC data to my-pc.local:39539.\n\
+             Metrics server listening on 127.0.0.1:9100, version 1.0.0\n",
+        )?;
+        fs::write(
+            log_directory.join("StableView.2026-01-01_00-00-00.log"),
+            "old\n",
+        )?;
+        fs::write(log_directory.join("notes.txt"), "not a log\n")?;
+
+        let diagnostics = Diagnostics {
+            system: system_info("Xplane"),
+            profile: "Xplane".to_string(),
+            config: AppConfig {
+                fps: 30,
+                ip: "192.168.1.20".to_string(),
+                extra_targets: vec!["10.0.0.5:4243".to_string()],
+                vmc_ip: "my-pc.local".to_string(),
+                ..AppConfig::default()
+            },
+            cameras: "0: Webcam\n".to_string(),
+            inference: "Landmarks: 3DDFA mb05_120x120 on ONNX Runtime, CPU provider\n".to_string(),
+            metrics: None,
+            poses: vec![
+                PoseSample::new(12., Pose::default(), RotationOrder::default(), 1.),
+                PoseSample::new(12.5, Pose::default(), RotationOrder::default(), 1.),
+            ],
+            log_directory: Some(log_directory),
+        };
+        let path = directory.join("diagnostics.zip");
+        diagnostics.write(&path)?;
+
+        let mut archive = ZipArchive::new(File::open(&path)?)?;
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "cameras.txt",
+                "config.toml",
+                "inference.txt",
+                "logs/StableView.2026-01-01_00-00-00.log",
+                "logs/StableView.log",
+                "metrics.txt",
+                "poses.csv",
+                "system.txt"
+            ]
+        );
+
+        let mut read = |name: &str| -> Result<String> {
+            let mut contents = String::new();
+            archive.by_name(name)?.read_to_string(&mut contents)?;
+            Ok(contents)
+        };
+        assert!(read("system.txt")?.contains(&format!("Session: {}\n", *SESSION_ID)));
+        let (config, _) = crate::config::parse(&read("config.toml")?)?;
+        assert_eq!(config.fps, 30);
+        // Only the ports of the addresses are kept
+        assert_eq!(config.ip, REDACTED);
+        assert_eq!(config.extra_targets, ["<redacted>:4243"]);
+        assert_eq!(config.vmc_ip, REDACTED);
+        assert_eq!(config.port, AppConfig::default().port);
+        let poses = read("poses.csv")?;
+        assert_eq!(poses.lines().count(), 3);
+        assert!(poses.lines().nth(1).unwrap().starts_with("0.000,"));
+        assert!(poses.lines().nth(2).unwrap().starts_with("0.500,"));
+        // Addresses of the other computers are removed from the logs
+        assert_eq!(
+            read("logs/StableView.log")?,
+            "Config : ip <redacted>, vmc <redacted>\n\
+             Sending data to [<redacted>:4242, [<redacted>]:4243]\n\
+             Sending VMC data to <redacted>:39539.\n\
+             Metrics server listening on 127.0.0.1:9100, version 1.0.0\n"
+        );
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+}
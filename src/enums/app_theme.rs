@@ -0,0 +1,38 @@
+// Colors of the interface, System follows the light or dark mode of the operating system and Custom uses the
+// palette of the config file
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppTheme {
+    Light,
+    Dark,
+    #[default]
+    System,
+    Custom,
+}
+
+impl AppTheme {
+    pub const ALL: [AppTheme; 4] = [
+        AppTheme::Light,
+        AppTheme::Dark,
+        AppTheme::System,
+        AppTheme::Custom,
+    ];
+}
+
+impl std::fmt::Display for AppTheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AppTheme::Light => "Light",
+                AppTheme::Dark => "Dark",
+                AppTheme::System => "System",
+                AppTheme::Custom => "Custom",
+            }
+        )
+    }
+}
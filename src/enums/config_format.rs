@@ -0,0 +1,24 @@
+// Formats of the exported config files, chosen from the file extension
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    pub const ALL: [ConfigFormat; 2] = [ConfigFormat::Toml, ConfigFormat::Json];
+}
+
+impl std::fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ConfigFormat::Toml => "TOML",
+                ConfigFormat::Json => "JSON",
+            }
+        )
+    }
+}
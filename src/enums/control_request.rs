@@ -0,0 +1,18 @@
+// Commands accepted by the local control server, one JSON object per line with the control_token of the config,
+// eg. {"token": "secret", "command": "start"}
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlRequest {
+    Start,
+    Stop,
+    Recenter,
+    GetStatus,
+    GetConfig,
+    // Only the given fields are changed, eg. {"command": "set_config", "config": {"fps": 30}}
+    SetConfig { config: serde_json::Value },
+    // The connection then only receives a JSON line for each tracked pose
+    SubscribePose,
+}
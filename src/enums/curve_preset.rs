@@ -0,0 +1,30 @@
+// Shapes the response curve of an axis can be reset to
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurvePreset {
+    Linear,
+    SCurve,
+    Exponential,
+}
+
+impl CurvePreset {
+    pub const ALL: [CurvePreset; 3] = [
+        CurvePreset::Linear,
+        CurvePreset::SCurve,
+        CurvePreset::Exponential,
+    ];
+}
+
+impl std::fmt::Display for CurvePreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CurvePreset::Linear => "Linear",
+                CurvePreset::SCurve => "S-Curve",
+                CurvePreset::Exponential => "Exponential",
+            }
+        )
+    }
+}
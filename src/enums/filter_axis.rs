@@ -0,0 +1,60 @@
+// Axes edited by the filter sliders, either a whole group or a single axis
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterAxis {
+    #[default]
+    Translation,
+    Rotation,
+    X,
+    Y,
+    Z,
+    Yaw,
+    Pitch,
+    Roll,
+}
+
+impl FilterAxis {
+    pub const ALL: [FilterAxis; 8] = [
+        FilterAxis::Translation,
+        FilterAxis::Rotation,
+        FilterAxis::X,
+        FilterAxis::Y,
+        FilterAxis::Z,
+        FilterAxis::Yaw,
+        FilterAxis::Pitch,
+        FilterAxis::Roll,
+    ];
+
+    // Indices in the x, y, z, yaw, pitch, roll order of the axis filters
+    pub fn indices(self) -> &'static [usize] {
+        match self {
+            FilterAxis::Translation => &[0, 1, 2],
+            FilterAxis::Rotation => &[3, 4, 5],
+            FilterAxis::X => &[0],
+            FilterAxis::Y => &[1],
+            FilterAxis::Z => &[2],
+            FilterAxis::Yaw => &[3],
+            FilterAxis::Pitch => &[4],
+            FilterAxis::Roll => &[5],
+        }
+    }
+}
+
+impl std::fmt::Display for FilterAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FilterAxis::Translation => "Translation",
+                FilterAxis::Rotation => "Rotation",
+                FilterAxis::X => "X",
+                FilterAxis::Y => "Y",
+                FilterAxis::Z => "Depth",
+                FilterAxis::Yaw => "Yaw",
+                FilterAxis::Pitch => "Pitch",
+                FilterAxis::Roll => "Roll",
+            }
+        )
+    }
+}
@@ -0,0 +1,39 @@
+// Filters available to smooth each axis of the head pose
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum FilterKind {
+    #[default]
+    OneEuro,
+    Kalman,
+    Ema,
+    Accela,
+    None,
+}
+
+impl FilterKind {
+    pub const ALL: [FilterKind; 5] = [
+        FilterKind::OneEuro,
+        FilterKind::Kalman,
+        FilterKind::Ema,
+        FilterKind::Accela,
+        FilterKind::None,
+    ];
+}
+
+impl std::fmt::Display for FilterKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FilterKind::OneEuro => "One Euro",
+                FilterKind::Kalman => "Kalman",
+                FilterKind::Ema => "EMA",
+                FilterKind::Accela => "Accela",
+                FilterKind::None => "None",
+            }
+        )
+    }
+}
@@ -0,0 +1,25 @@
+// Reason a pose was found implausible before the filter
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glitch {
+    // NaN or infinite 3DDFA parameters or pose
+    NonFinite,
+    // Landmark crop too small, inverted or bigger than the frame
+    RoiBox,
+    // Head moving faster than the speed limits
+    Jump,
+}
+
+impl std::fmt::Display for Glitch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Glitch::NonFinite => "non finite pose",
+                Glitch::RoiBox => "implausible roi box",
+                Glitch::Jump => "pose jump",
+            }
+        )
+    }
+}
@@ -0,0 +1,30 @@
+// What the validation does with a pose moving faster than the speed limits
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlitchAction {
+    // The last valid pose is kept
+    #[default]
+    Reject,
+    // The pose is moved toward, at the highest speed allowed
+    Clamp,
+}
+
+impl GlitchAction {
+    pub const ALL: [GlitchAction; 2] = [GlitchAction::Reject, GlitchAction::Clamp];
+}
+
+impl std::fmt::Display for GlitchAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                GlitchAction::Reject => "Reject",
+                GlitchAction::Clamp => "Clamp",
+            }
+        )
+    }
+}
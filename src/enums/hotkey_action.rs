@@ -0,0 +1,9 @@
+// Actions which can be bound to a hotkey
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    Recenter,
+    PauseOutput,
+    ToggleTracking,
+    TogglePreview,
+}
@@ -0,0 +1,30 @@
+// Format of the lines of the log file
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    // Human readable, one event per line
+    #[default]
+    Text,
+    // One JSON object per line, for log processing tools
+    Json,
+}
+
+impl LogFormat {
+    pub const ALL: [LogFormat; 2] = [LogFormat::Text, LogFormat::Json];
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LogFormat::Text => "Text",
+                LogFormat::Json => "JSON",
+            }
+        )
+    }
+}
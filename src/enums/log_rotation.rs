@@ -0,0 +1,30 @@
+// When the log file is closed and a new one started
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    // At the first event of a new day (UTC), or when the file reaches the maximum size
+    #[default]
+    Daily,
+    // Only when the file reaches the maximum size
+    Size,
+}
+
+impl LogRotation {
+    pub const ALL: [LogRotation; 2] = [LogRotation::Daily, LogRotation::Size];
+}
+
+impl std::fmt::Display for LogRotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LogRotation::Daily => "Daily",
+                LogRotation::Size => "Size",
+            }
+        )
+    }
+}
@@ -2,19 +2,70 @@
 
 use iced_native::Event;
 
+use crate::structs::release::Release;
+
+use super::{
+    app_theme::AppTheme, curve_preset::CurvePreset, filter_axis::FilterAxis,
+    hotkey_action::HotkeyAction, overlay_element::OverlayElement, rotation_order::RotationOrder,
+    tray_action::TrayAction,
+};
+
 #[derive(Debug, Clone)]
 pub enum Message {
     Toggle,
+    Recenter,
+    PauseOutput,
+    Hotkey(HotkeyAction),
     DefaultSettings,
     Tick,
+    FilterAxis(FilterAxis),
     MinCutoffSliderChanged(u32),
     BetaSliderChanged(u32),
     FPSSliderChanged(u32),
+    MinQualitySliderChanged(u32),
     InputIP(String),
     InputPort(String),
+    InputExtraTargets(String),
+    RotationOrder(RotationOrder),
+    VmcToggled(bool),
+    InputVmcIP(String),
+    InputVmcPort(String),
     Camera(String),
+    SwitchProfile(String),
+    InputProfileName(String),
+    NewProfile,
+    CloneProfile,
+    RenameProfile,
+    DeleteProfile,
+    ImportConfig,
+    ApplyImport,
+    CancelImport,
+    ExportProfile,
+    ExportAll,
+    ExportDiagnostics,
     HideCamera(bool),
+    Theme(AppTheme),
+    DebugOverlay(bool),
+    ShowGraphs(bool),
+    ShowCurves(bool),
+    CurveAxis(FilterAxis),
+    CurvePreset(CurvePreset),
+    CurveMaxInput(u32),
+    // Axis, index of the control point and its new position
+    CurvePointMoved(usize, usize, [f32; 2]),
+    CurvePointReleased,
+    StartTuning,
+    ApplyTuning,
+    CancelTuning,
+    OverlayElement(OverlayElement, bool),
     OpenURL(String),
+    UpdateChecked(Option<Release>),
     OpenLogs,
     EventOccurred(Event),
+    ControlTick,
+    HotkeyTick,
+    TrayTick,
+    Tray(TrayAction),
+    MinimizeToTray(bool),
+    StartMinimized(bool),
 }
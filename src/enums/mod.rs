@@ -1,3 +1,22 @@
+pub mod app_theme;
+pub mod config_format;
+pub mod control_request;
 pub mod crop_policy;
+pub mod curve_preset;
 pub mod extreme;
+pub mod filter_axis;
+pub mod filter_kind;
+pub mod glitch;
+pub mod glitch_action;
+pub mod hotkey_action;
+pub mod log_format;
+pub mod log_level;
+pub mod log_rotation;
 pub mod message;
+pub mod osc_arg;
+pub mod overlay_element;
+pub mod pose_format;
+pub mod rotation_order;
+pub mod tray_action;
+pub mod tuning_step;
+pub mod update_channel;
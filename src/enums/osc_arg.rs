@@ -0,0 +1,8 @@
+// Arguments that can be encoded in an OSC message
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
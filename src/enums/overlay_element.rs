@@ -0,0 +1,36 @@
+// Elements of the debug overlay drawn over the camera preview, each can be shown or hidden
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayElement {
+    Landmarks,
+    FaceBox,
+    RoiBox,
+    CameraBox,
+    Axes,
+}
+
+impl OverlayElement {
+    pub const ALL: [OverlayElement; 5] = [
+        OverlayElement::Landmarks,
+        OverlayElement::FaceBox,
+        OverlayElement::RoiBox,
+        OverlayElement::CameraBox,
+        OverlayElement::Axes,
+    ];
+}
+
+impl std::fmt::Display for OverlayElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                OverlayElement::Landmarks => "Landmarks",
+                OverlayElement::FaceBox => "Face Box",
+                OverlayElement::RoiBox => "ROI Box",
+                OverlayElement::CameraBox => "3D Box",
+                OverlayElement::Axes => "Axes",
+            }
+        )
+    }
+}
@@ -0,0 +1,13 @@
+// Encoding of the frames sent by the pose server
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoseFormat {
+    // One JSON object per line, or per text message on WebSocket
+    #[default]
+    Json,
+    // Fixed size little endian frame, see PoseFrame::to_bytes
+    Binary,
+}
@@ -0,0 +1,42 @@
+// Order in which yaw, pitch and roll are applied when converting the head rotation to euler angles
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum RotationOrder {
+    YawPitchRoll,
+    YawRollPitch,
+    PitchYawRoll,
+    PitchRollYaw,
+    #[default]
+    RollYawPitch,
+    RollPitchYaw,
+}
+
+impl RotationOrder {
+    pub const ALL: [RotationOrder; 6] = [
+        RotationOrder::YawPitchRoll,
+        RotationOrder::YawRollPitch,
+        RotationOrder::PitchYawRoll,
+        RotationOrder::PitchRollYaw,
+        RotationOrder::RollYawPitch,
+        RotationOrder::RollPitchYaw,
+    ];
+}
+
+impl std::fmt::Display for RotationOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                RotationOrder::YawPitchRoll => "Yaw, Pitch, Roll",
+                RotationOrder::YawRollPitch => "Yaw, Roll, Pitch",
+                RotationOrder::PitchYawRoll => "Pitch, Yaw, Roll",
+                RotationOrder::PitchRollYaw => "Pitch, Roll, Yaw",
+                RotationOrder::RollYawPitch => "Roll, Yaw, Pitch",
+                RotationOrder::RollPitchYaw => "Roll, Pitch, Yaw",
+            }
+        )
+    }
+}
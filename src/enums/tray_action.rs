@@ -0,0 +1,10 @@
+// Entries of the tray icon menu, sent to the GUI when clicked
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrayAction {
+    ShowWindow,
+    ToggleTracking,
+    Recenter,
+    SwitchProfile(String),
+    Quit,
+}
@@ -0,0 +1,33 @@
+// Steps of the filter tuning wizard, the movements are recorded for a fixed time each
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuningStep {
+    HoldStill,
+    SlowMoves,
+    FastMoves,
+    Done,
+}
+
+impl TuningStep {
+    pub const ALL: [TuningStep; 4] = [
+        TuningStep::HoldStill,
+        TuningStep::SlowMoves,
+        TuningStep::FastMoves,
+        TuningStep::Done,
+    ];
+}
+
+impl std::fmt::Display for TuningStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TuningStep::HoldStill => "Hold still",
+                TuningStep::SlowMoves => "Slow movements",
+                TuningStep::FastMoves => "Fast movements",
+                TuningStep::Done => "Done",
+            }
+        )
+    }
+}
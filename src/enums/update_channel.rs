@@ -0,0 +1,29 @@
+// Releases offered by the update checker
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    // Also the beta versions, marked as pre-releases on GitHub
+    Prerelease,
+}
+
+impl UpdateChannel {
+    pub const ALL: [UpdateChannel; 2] = [UpdateChannel::Stable, UpdateChannel::Prerelease];
+}
+
+impl std::fmt::Display for UpdateChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                UpdateChannel::Stable => "Stable",
+                UpdateChannel::Prerelease => "Pre-release",
+            }
+        )
+    }
+}
@@ -17,8 +17,7 @@ use crate::structs::face::FaceDetect;
 
 impl FaceDetect {
     pub fn new() -> Self {
-
-
+        let (cuda_device, threads) = (0, 5);
 
         let face_detector =
             FaceDetectorBuilder::new(FaceDetection::BlazeFace320(BlazeFaceParams {
@@ -30,15 +29,24 @@ impl FaceDetect {
             // .from_file(FACE_DETECTOR_MODEL)
             .download()
             .infer_params(InferParams {
-                provider: Provider::OrtCuda(0),
-                intra_threads: Some(5),
+                provider: Provider::OrtCuda(cuda_device),
+                intra_threads: Some(threads),
 
                 ..Default::default()
             })
             .build()
             .expect("Fail to load the face detector.");
 
-        Self { face_detector }
+        // ONNX Runtime silently runs the session on the CPU when CUDA can not be loaded
+        let inference = format!(
+            "Face detector: BlazeFace 320 on ONNX Runtime, CUDA provider (device {}) with CPU fallback, intra-op threads: {}",
+            cuda_device, threads
+        );
+
+        Self {
+            face_detector,
+            inference,
+        }
     }
 
     pub fn preprocess_frame(
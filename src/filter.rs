@@ -1,30 +1,46 @@
 /// Rust Implementation of OneEuroFilter https://gery.casiez.net/1euro/ to filter real-time noisy signals
 /// Visit the site to learn more about the parameters involved and how to tune them
 /// The pseudocode is originajlly from https://github.com/jaantollander/OneEuroFilter, which is further modified for our use case  
-use std::f32;
+/// Timestamps are in seconds and the cutoff frequencies in Hz, so the smoothing does not depend on the frame rate
+use crate::{
+    consts::FILTER_REFERENCE_FPS,
+    enums::filter_kind::FilterKind,
+    structs::{
+        atomic::AtomicF32,
+        pose::{Pose, Quaternion},
+        state::AxisFilterSettings,
+    },
+};
+use std::{
+    f32,
+    sync::{atomic::Ordering, Arc},
+};
+
+// Cutoff frequency (Hz) for the derivative, equivalent to the per frame value of 1 used before timestamps at 60 fps
+const D_CUTOFF: f32 = 60.;
 
-// ! Need Default values
 struct OneEuroFilter {
     // Parameters
     min_cutoff: f32,
     beta: f32,
     d_cutoff: f32,
 
-    // Previous Values
+    // Previous Values, the filter is initialized with the first sample
     x_prev: f32,
     dx_prev: f32,
+    t_prev: Option<f32>,
 }
 
-// TODO : A way to have default value in filter
 impl OneEuroFilter {
-    fn new(x0: f32, dx0: f32, min_cutoff: f32, beta: f32, d_cutoff: f32) -> Self {
+    fn new(min_cutoff: f32, beta: f32, d_cutoff: f32) -> Self {
         Self {
             min_cutoff,
             beta,
             d_cutoff,
 
-            x_prev: x0,
-            dx_prev: dx0,
+            x_prev: 0.,
+            dx_prev: 0.,
+            t_prev: None,
         }
     }
 
@@ -37,7 +53,22 @@ impl OneEuroFilter {
         a.mul_add(x, (1.0 - a) * x_prev)
     }
 
-    fn run(&mut self, x: f32, min_cutoff: Option<f32>, beta: Option<f32>) -> f32 {
+    fn run(&mut self, x: f32, t: f32, min_cutoff: Option<f32>, beta: Option<f32>) -> f32 {
+        let t_e = match self.t_prev {
+            Some(t_prev) => t - t_prev,
+            None => {
+                self.x_prev = x;
+                self.t_prev = Some(t);
+                return x;
+            }
+        };
+
+        // Same frame received again, nothing new to filter
+        if t_e <= 0. {
+            return self.x_prev;
+        }
+        self.t_prev = Some(t);
+
         let min_cutoff = match min_cutoff {
             Some(min_cutoff) => min_cutoff,
             None => self.min_cutoff,
@@ -48,8 +79,6 @@ impl OneEuroFilter {
             None => self.beta,
         };
 
-        let t_e = 1.; // constant change in time
-
         let a_d = self.smoothing_factor(t_e, self.d_cutoff);
         let dx = (x - self.x_prev) / t_e;
 
@@ -63,44 +92,364 @@ impl OneEuroFilter {
     }
 }
 
-// TODO : Need to clean this up
-pub struct EuroDataFilter {
-    x: OneEuroFilter,
-    y: OneEuroFilter,
-    z: OneEuroFilter,
-    yaw: OneEuroFilter,
-    pitch: OneEuroFilter,
-    roll: OneEuroFilter,
+// Parameters set by the positions of the Speed and Smooth sliders, 0 turns the parameter off
+pub fn slider_min_cutoff(value: u32) -> f32 {
+    match value {
+        0 => 0.,
+        _ => FILTER_REFERENCE_FPS / ((value * value) as f32),
+    }
+}
+
+pub fn slider_beta(value: u32) -> f32 {
+    match value {
+        0 => 0.,
+        _ => 1. / ((value * value) as f32),
+    }
+}
+
+// Output of a One Euro filter with fixed parameters over recorded (time, value) samples, used to score parameters
+pub fn one_euro_response(samples: &[(f32, f32)], min_cutoff: f32, beta: f32) -> Vec<f32> {
+    let mut filter = OneEuroFilter::new(min_cutoff, beta, D_CUTOFF);
+    samples
+        .iter()
+        .map(|&(t, x)| filter.run(x, t, None, None))
+        .collect()
+}
+
+// Filter smoothing a single axis of the head pose
+pub trait PoseFilter: Send {
+    // x is the raw value and t the capture time of the frame in seconds
+    fn run(&mut self, x: f32, t: f32) -> f32;
+}
+
+// One Euro filter reading its parameters from the shared configuration, so slider changes apply while tracking
+struct SharedOneEuroFilter {
+    filter: OneEuroFilter,
+    min_cutoff: Arc<AtomicF32>,
+    beta: Arc<AtomicF32>,
+}
+
+impl PoseFilter for SharedOneEuroFilter {
+    fn run(&mut self, x: f32, t: f32) -> f32 {
+        self.filter.run(
+            x,
+            t,
+            Some(self.min_cutoff.load(Ordering::SeqCst)),
+            Some(self.beta.load(Ordering::SeqCst)),
+        )
+    }
+}
+
+// Exponential moving average, with the smoothing factor computed from the time between frames
+struct EmaFilter {
+    time_constant: f32,
+    x_prev: f32,
+    t_prev: Option<f32>,
+}
+
+impl EmaFilter {
+    fn new(time_constant: f32) -> Self {
+        Self {
+            time_constant,
+            x_prev: 0.,
+            t_prev: None,
+        }
+    }
+}
+
+impl PoseFilter for EmaFilter {
+    fn run(&mut self, x: f32, t: f32) -> f32 {
+        let t_e = match self.t_prev {
+            Some(t_prev) => t - t_prev,
+            None => {
+                self.x_prev = x;
+                self.t_prev = Some(t);
+                return x;
+            }
+        };
+        if t_e <= 0. {
+            return self.x_prev;
+        }
+        self.t_prev = Some(t);
+
+        let a = if self.time_constant <= 0. {
+            1.
+        } else {
+            1. - (-t_e / self.time_constant).exp()
+        };
+        self.x_prev = a.mul_add(x, (1.0 - a) * self.x_prev);
+
+        self.x_prev
+    }
+}
+
+// Kalman filter with a constant velocity model, the state being the position and the velocity of the axis
+struct KalmanFilter {
+    process_noise: f32,
+    measurement_noise: f32,
+
+    state: [f32; 2],
+    covariance: [[f32; 2]; 2],
+    t_prev: Option<f32>,
+}
+
+impl KalmanFilter {
+    fn new(process_noise: f32, measurement_noise: f32) -> Self {
+        Self {
+            process_noise,
+            measurement_noise,
+
+            state: [0.; 2],
+            covariance: [[0.; 2]; 2],
+            t_prev: None,
+        }
+    }
+}
+
+impl PoseFilter for KalmanFilter {
+    fn run(&mut self, x: f32, t: f32) -> f32 {
+        let r = self.measurement_noise * self.measurement_noise;
+
+        let dt = match self.t_prev {
+            Some(t_prev) => t - t_prev,
+            None => {
+                self.state = [x, 0.];
+                self.covariance = [[r, 0.], [0., r]];
+                self.t_prev = Some(t);
+                return x;
+            }
+        };
+        if dt <= 0. {
+            return self.state[0];
+        }
+        self.t_prev = Some(t);
+
+        // Predict, x = F x and P = F P F' + Q with F = [[1, dt], [0, 1]]
+        let [p, v] = self.state;
+        let [[p00, p01], [p10, p11]] = self.covariance;
+        let q = self.process_noise * self.process_noise;
+
+        let predicted = [p + v * dt, v];
+        let p00 = p00 + dt * (p10 + p01) + dt * dt * p11 + q * dt.powi(4) / 4.;
+        let p01 = p01 + dt * p11 + q * dt.powi(3) / 2.;
+        let p10 = p10 + dt * p11 + q * dt.powi(3) / 2.;
+        let p11 = p11 + q * dt * dt;
+
+        // Update with the measured position
+        let innovation = x - predicted[0];
+        let s = p00 + r;
+        let k = [p00 / s, p10 / s];
+
+        self.state = [
+            predicted[0] + k[0] * innovation,
+            predicted[1] + k[1] * innovation,
+        ];
+        self.covariance = [
+            [(1. - k[0]) * p00, (1. - k[0]) * p01],
+            [p10 - k[1] * p00, p11 - k[1] * p01],
+        ];
+
+        self.state[0]
+    }
+}
+
+// Normalized distance to gain curve of opentrack's accela filter, extrapolated linearly after the last point
+// https://github.com/opentrack/opentrack/blob/master/filter-accela/accela-settings.hpp
+const ACCELA_GAINS: [(f32, f32); 8] = [
+    (0., 0.),
+    (0.5, 0.4),
+    (1., 1.5),
+    (1.5, 8.),
+    (2.5, 35.),
+    (5., 100.),
+    (8., 200.),
+    (9., 300.),
+];
+
+// Accela style filter, the output moves towards the input with a speed growing with their distance,
+// keeping small movements (noise) slow while large movements are followed quickly
+struct AccelaFilter {
+    smoothing: f32,
+    deadzone: f32,
+    x_prev: f32,
+    t_prev: Option<f32>,
+}
+
+impl AccelaFilter {
+    fn new(smoothing: f32, deadzone: f32) -> Self {
+        Self {
+            smoothing,
+            deadzone,
+            x_prev: 0.,
+            t_prev: None,
+        }
+    }
+
+    fn gain(distance: f32) -> f32 {
+        for window in ACCELA_GAINS.windows(2) {
+            let ((x0, y0), (x1, y1)) = (window[0], window[1]);
+            if distance <= x1 {
+                return y0 + (y1 - y0) * (distance - x0) / (x1 - x0);
+            }
+        }
+
+        let ((x0, y0), (x1, y1)) = (ACCELA_GAINS[6], ACCELA_GAINS[7]);
+        y1 + (y1 - y0) * (distance - x1) / (x1 - x0)
+    }
+}
+
+impl PoseFilter for AccelaFilter {
+    fn run(&mut self, x: f32, t: f32) -> f32 {
+        let t_e = match self.t_prev {
+            Some(t_prev) => t - t_prev,
+            None => {
+                self.x_prev = x;
+                self.t_prev = Some(t);
+                return x;
+            }
+        };
+        if t_e <= 0. {
+            return self.x_prev;
+        }
+        self.t_prev = Some(t);
+
+        let delta = x - self.x_prev;
+        let distance = (delta.abs() - self.deadzone).max(0.);
+        if distance <= 0. || self.smoothing <= 0. {
+            return self.x_prev;
+        }
+
+        // Never move past the input
+        let step = (Self::gain(distance / self.smoothing) * self.smoothing * t_e).min(distance);
+        self.x_prev += step.copysign(delta);
+
+        self.x_prev
+    }
+}
+
+// Passing the values as they are
+struct NoFilter;
+
+impl PoseFilter for NoFilter {
+    fn run(&mut self, x: f32, _t: f32) -> f32 {
+        x
+    }
 }
 
-impl EuroDataFilter {
-    pub fn new(min_cutoff: f32, beta: f32) -> Self {
+pub fn build_filter(
+    settings: &AxisFilterSettings,
+    min_cutoff: Arc<AtomicF32>,
+    beta: Arc<AtomicF32>,
+) -> Box<dyn PoseFilter> {
+    match settings.kind {
+        FilterKind::OneEuro => Box::new(SharedOneEuroFilter {
+            filter: OneEuroFilter::new(
+                min_cutoff.load(Ordering::SeqCst),
+                beta.load(Ordering::SeqCst),
+                D_CUTOFF,
+            ),
+            min_cutoff,
+            beta,
+        }),
+        FilterKind::Kalman => Box::new(KalmanFilter::new(
+            settings.kalman_process_noise,
+            settings.kalman_measurement_noise,
+        )),
+        FilterKind::Ema => Box::new(EmaFilter::new(settings.ema_time_constant)),
+        FilterKind::Accela => Box::new(AccelaFilter::new(
+            settings.accela_smoothing,
+            settings.accela_deadzone,
+        )),
+        FilterKind::None => Box::new(NoFilter),
+    }
+}
+
+// Filters the rotation per axis without euler angles: the difference between the new rotation and the last output
+// is taken as a rotation vector (in degrees) and accumulated into a continuous signal for each axis,
+// avoiding the yaw wrap around and gimbal lock of euler angles
+struct RotationFilter {
+    filters: [Box<dyn PoseFilter>; 3],
+    q_prev: Quaternion,
+    u_prev: [f32; 3],
+    initialized: bool,
+}
+
+impl RotationFilter {
+    fn new(filters: [Box<dyn PoseFilter>; 3]) -> Self {
         Self {
-            x: OneEuroFilter::new(0., 0., min_cutoff, beta, 1.),
-            y: OneEuroFilter::new(0., 0., min_cutoff, beta, 1.),
-            z: OneEuroFilter::new(0., 0., min_cutoff, beta, 1.),
-            yaw: OneEuroFilter::new(0., 0., min_cutoff, beta, 1.),
-            pitch: OneEuroFilter::new(0., 0., min_cutoff, beta, 1.),
-            roll: OneEuroFilter::new(0., 0., min_cutoff, beta, 1.),
+            filters,
+            q_prev: Quaternion::IDENTITY,
+            u_prev: [0.; 3],
+            initialized: false,
+        }
+    }
+
+    fn run(&mut self, q: Quaternion, t: f32) -> Quaternion {
+        if !self.initialized {
+            self.initialized = true;
+            self.q_prev = q;
+            self.u_prev = q.log().map(f32::to_degrees);
+            for (filter, u) in self.filters.iter_mut().zip(self.u_prev) {
+                filter.run(u, t);
+            }
+            return q;
+        }
+
+        let delta = (self.q_prev.conjugate() * q).log();
+
+        let mut u = [0.; 3];
+        let mut step = [0.; 3];
+        for i in 0..3 {
+            u[i] = self.filters[i].run(self.u_prev[i] + delta[i].to_degrees(), t);
+            step[i] = (u[i] - self.u_prev[i]).to_radians();
         }
+
+        self.q_prev = (self.q_prev * Quaternion::exp(step)).normalize();
+        self.u_prev = u;
+
+        self.q_prev
     }
+}
 
-    pub fn filter_data(
-        &mut self,
-        data: [f32; 6],
-        min_cutoff: Option<f32>,
-        beta: Option<f32>,
-    ) -> [f32; 6] {
-        let mut filtered_data = [0.; 6];
+pub struct PoseDataFilter {
+    translation: [Box<dyn PoseFilter>; 3],
+    rotation: RotationFilter,
+}
 
-        filtered_data[0] = self.x.run(data[0], min_cutoff, beta);
-        filtered_data[1] = self.y.run(data[1], min_cutoff, beta);
-        filtered_data[2] = self.z.run(data[2], min_cutoff, beta);
-        filtered_data[3] = self.yaw.run(data[3], min_cutoff, beta);
-        filtered_data[4] = self.pitch.run(data[4], min_cutoff, beta);
-        filtered_data[5] = self.roll.run(data[5], min_cutoff, beta);
+impl PoseDataFilter {
+    // One Euro filters read the live min_cutoff and beta of their axis, other filters use their settings
+    pub fn new(
+        settings: &[AxisFilterSettings; 6],
+        min_cutoff: &[Arc<AtomicF32>; 6],
+        beta: &[Arc<AtomicF32>; 6],
+    ) -> Self {
+        let mut filters: [Option<Box<dyn PoseFilter>>; 6] = std::array::from_fn(|i| {
+            Some(build_filter(
+                &settings[i],
+                min_cutoff[i].clone(),
+                beta[i].clone(),
+            ))
+        });
+        let mut take =
+            |i: usize| -> Box<dyn PoseFilter> { filters[i].take().unwrap_or(Box::new(NoFilter)) };
 
-        filtered_data
+        Self {
+            translation: [take(0), take(1), take(2)],
+            // Rotation vectors are ordered by axis, pitch being around x and yaw around y
+            rotation: RotationFilter::new([take(4), take(3), take(5)]),
+        }
+    }
+
+    // t is the capture time of the frame in seconds
+    pub fn filter_data(&mut self, pose: Pose, t: f32) -> Pose {
+        Pose {
+            translation: [
+                self.translation[0].run(pose.translation[0], t),
+                self.translation[1].run(pose.translation[1], t),
+                self.translation[2].run(pose.translation[2], t),
+            ],
+            rotation: self.rotation.run(pose.rotation, t),
+        }
     }
 }
 
@@ -109,16 +458,18 @@ fn test_euro_filter() {
     use rand::Rng;
 
     // Create the filter with the initial values
-    let mut filter = OneEuroFilter::new(1., 0.0, 0.0001, 0.1, 1.0);
+    let mut filter = OneEuroFilter::new(0.0001, 0.1, 1.0);
 
     // Iterate over the sin values and apply the filter
     for i in 1..100 {
+        let t = i as f32 / 30.;
+
         // Compute the noisy sin value
         let x = (0.1 * i as f32).sin();
         let x_noisy = x + (rand::thread_rng().gen_range(0..10) as f32 / 10.0);
 
         // Filter the noisy sin value
-        let x_filtered = filter.run(x_noisy, None, None);
+        let x_filtered = filter.run(x_noisy, t, None, None);
 
         // Print the original and filtered sin values
         println!(
@@ -127,3 +478,157 @@ fn test_euro_filter() {
         );
     }
 }
+
+#[test]
+fn test_quaternion_euro_filter() {
+    // Yaw oscillating around 180 degrees, where euler angles wrap around
+    let yaw = |degrees: f32| Quaternion::exp([0., degrees.to_radians(), 0.]);
+
+    let one_euro = || -> Box<dyn PoseFilter> {
+        Box::new(SharedOneEuroFilter {
+            filter: OneEuroFilter::new(0.1, 0.01, 1.0),
+            min_cutoff: Arc::new(AtomicF32::new(0.1)),
+            beta: Arc::new(AtomicF32::new(0.01)),
+        })
+    };
+    let mut filter = RotationFilter::new([one_euro(), one_euro(), one_euro()]);
+    filter.run(yaw(175.), 0.);
+
+    for i in 1..100 {
+        let degrees = if i % 2 == 0 { 178. } else { -178. };
+        let filtered = filter.run(yaw(degrees), i as f32 / 30.);
+
+        // The filtered rotation has to stay close to 180 degrees instead of passing through 0
+        let distance = (filtered.conjugate() * yaw(180.)).log();
+        assert!(distance[1].abs().to_degrees() < 6.);
+    }
+}
+
+#[test]
+fn test_euro_filter_matches_reference() {
+    // Outputs of the reference python implementation https://github.com/jaantollander/OneEuroFilter
+    // for sin(2t) + 0.1 sin(40t) sampled at 30 fps with dropped frames, min_cutoff = 1, beta = 0.5, d_cutoff = 1
+    let timestamps: [f32; 20] = [
+        0.0, 0.033333, 0.066667, 0.1, 0.133333, 0.166667, 0.233333, 0.266667, 0.3, 0.333333,
+        0.366667, 0.4, 0.466667, 0.533333, 0.566667, 0.6, 0.633333, 0.666667, 0.7, 0.733333,
+    ];
+    let expected = [
+        0.0, 0.037662, 0.074974, 0.087682, 0.113964, 0.196526, 0.331576, 0.359658, 0.41321,
+        0.520199, 0.616522, 0.644846, 0.720559, 0.836038, 0.838504, 0.839447, 0.883853, 0.950233,
+        0.971586, 0.952208,
+    ];
+
+    let mut filter = OneEuroFilter::new(1.0, 0.5, 1.0);
+
+    for (t, expected) in timestamps.iter().zip(expected) {
+        let x = (2. * t).sin() + 0.1 * (40. * t).sin();
+        let x_filtered = filter.run(x, *t, None, None);
+
+        assert!(
+            (x_filtered - expected).abs() < 1e-4,
+            "t {}, filtered {}, expected {}",
+            t,
+            x_filtered,
+            expected
+        );
+    }
+
+    // The same frame received twice does not change the output
+    let last = filter.run(5., 0.733333, None, None);
+    assert!((last - 0.952208).abs() < 1e-4);
+}
+
+#[test]
+fn test_euro_filter_frame_rate_independent() {
+    // Filtering the same step signal at 30 and 120 fps should give the same response after one second
+    let response = |fps: f32| {
+        let mut filter = OneEuroFilter::new(1.0, 0.0, 1.0);
+        filter.run(0., 0., None, None);
+
+        let mut x_filtered = 0.;
+        for i in 1..=(fps as usize) {
+            x_filtered = filter.run(1., i as f32 / fps, None, None);
+        }
+        x_filtered
+    };
+
+    assert!((response(30.) - response(120.)).abs() < 0.05);
+}
+
+#[test]
+fn test_filter_jitter_and_lag() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const FPS: f32 = 30.;
+    const NOISE: f32 = 0.5;
+    const STEP: f32 = 10.;
+
+    // Still signal with noise for two seconds, then a step, each filter is scored on the
+    // jitter (RMS of the frame to frame change) while still and the lag (time to reach 90% of the step)
+    let score = |kind: FilterKind| {
+        let settings = AxisFilterSettings {
+            kind,
+            ..AxisFilterSettings::rotation()
+        };
+        let mut filter = build_filter(
+            &settings,
+            Arc::new(AtomicF32::new(0.15)),
+            Arc::new(AtomicF32::new(0.01)),
+        );
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let mut jitter = 0.;
+        let mut lag = f32::INFINITY;
+        let mut previous = 0.;
+        for i in 0..(4. * FPS) as usize {
+            let t = i as f32 / FPS;
+            let target = if t < 2. { 0. } else { STEP };
+            let x = filter.run(target + rng.gen_range(-NOISE..NOISE), t);
+
+            if (1. ..2.).contains(&t) {
+                jitter += (x - previous).powi(2) / FPS;
+            }
+            if t >= 2. && x >= 0.9 * STEP && lag.is_infinite() {
+                lag = t - 2.;
+            }
+            previous = x;
+        }
+
+        (jitter.sqrt(), lag)
+    };
+
+    let (raw_jitter, _) = score(FilterKind::None);
+    for kind in FilterKind::ALL {
+        let (jitter, lag) = score(kind);
+
+        assert!(
+            jitter <= raw_jitter,
+            "{}: jitter {:.3} above the raw signal {:.3}",
+            kind,
+            jitter,
+            raw_jitter
+        );
+        assert!(lag < 1., "{}: lag {:.3}s", kind, lag);
+    }
+}
+
+#[test]
+fn test_per_axis_parameters() {
+    let settings = AxisFilterSettings::default_axes();
+    let min_cutoff = settings.map(|axis| Arc::new(AtomicF32::new(axis.min_cutoff)));
+    let beta = settings.map(|axis| Arc::new(AtomicF32::new(axis.beta)));
+    let mut filter = PoseDataFilter::new(&settings, &min_cutoff, &beta);
+
+    let pose = |x: f32| Pose {
+        translation: [x, x, 0.],
+        rotation: Quaternion::IDENTITY,
+    };
+    filter.filter_data(pose(0.), 0.);
+
+    // Raising the cutoff of x only while running makes x follow the input, y stays smoothed
+    min_cutoff[0].store(1000., Ordering::SeqCst);
+    let filtered = filter.filter_data(pose(1.), 1. / 30.);
+
+    assert!(filtered.translation[0] > 0.99);
+    assert!(filtered.translation[1] < 0.1);
+}
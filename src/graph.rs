@@ -0,0 +1,130 @@
+/// Recent raw and filtered poses for the live graphs of the GUI, scaled to the size of the plots
+use crate::structs::graph::{GraphSample, PoseHistory};
+
+// Time shown by the graphs, in seconds
+pub const GRAPH_SECONDS: f32 = 10.;
+
+// Smallest range of values of a plot, so the noise of a still head is not zoomed into large waves
+const MIN_RANGE: [f32; 6] = [2., 2., 2., 5., 5., 5.];
+
+impl PoseHistory {
+    // Samples older than GRAPH_SECONDS before the newest one are dropped
+    pub fn push(&mut self, sample: GraphSample) {
+        // Tracking restarted, the time went back to 0
+        if self
+            .samples
+            .back()
+            .is_some_and(|last| sample.timestamp < last.timestamp)
+        {
+            self.samples.clear();
+        }
+        self.samples.push_back(sample);
+
+        while self
+            .samples
+            .front()
+            .is_some_and(|first| sample.timestamp - first.timestamp > GRAPH_SECONDS)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn latest(&self) -> Option<&GraphSample> {
+        self.samples.back()
+    }
+
+    // Lowest and highest value of an axis, raw and filtered, widened to MIN_RANGE around its middle
+    pub fn range(&self, axis: usize) -> (f32, f32) {
+        let (min, max) = self
+            .samples
+            .iter()
+            .flat_map(|sample| [sample.raw[axis], sample.filtered[axis]])
+            .filter(|value| value.is_finite())
+            .fold((f32::MAX, f32::MIN), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+        if min > max {
+            return (-MIN_RANGE[axis] / 2., MIN_RANGE[axis] / 2.);
+        }
+
+        let middle = (min + max) / 2.;
+        let half = ((max - min) / 2.).max(MIN_RANGE[axis] / 2.);
+        (middle - half, middle + half)
+    }
+
+    // Points of an axis in a plot of the given size, the newest sample on the right edge and the highest value on top
+    pub fn points(&self, axis: usize, filtered: bool, width: f32, height: f32) -> Vec<[f32; 2]> {
+        let newest = match self.latest() {
+            Some(sample) => sample.timestamp,
+            None => return vec![],
+        };
+        let (min, max) = self.range(axis);
+
+        self.samples
+            .iter()
+            .map(|sample| {
+                let value = match filtered {
+                    true => sample.filtered[axis],
+                    false => sample.raw[axis],
+                };
+                [
+                    width * (1. - (newest - sample.timestamp) / GRAPH_SECONDS),
+                    height * (max - value) / (max - min),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::pose::Quaternion;
+
+    fn sample(timestamp: f32, yaw: f32) -> GraphSample {
+        GraphSample {
+            timestamp,
+            raw: [0., 0., 0., yaw, 0., 0.],
+            filtered: [0., 0., 0., yaw / 2., 0., 0.],
+            rotation: Quaternion::IDENTITY,
+        }
+    }
+
+    #[test]
+    fn test_pose_history_window() {
+        let mut history = PoseHistory::default();
+        for i in 0..=300 {
+            history.push(sample(i as f32 / 20., 0.));
+        }
+        // 15 seconds pushed, only the last 10 are kept
+        assert_eq!(history.samples.len(), 201);
+        assert_eq!(history.samples.front().unwrap().timestamp, 5.);
+
+        // A new tracking starts again from 0
+        history.push(sample(0., 0.));
+        assert_eq!(history.samples.len(), 1);
+    }
+
+    #[test]
+    fn test_pose_history_points() {
+        let mut history = PoseHistory::default();
+        assert_eq!(history.range(3), (-2.5, 2.5));
+        assert!(history.points(3, false, 100., 50.).is_empty());
+
+        history.push(sample(0., -10.));
+        history.push(sample(5., 30.));
+        history.push(sample(10., 10.));
+        assert_eq!(history.range(3), (-10., 30.));
+        // Still values are shown in the middle of the minimum range
+        assert_eq!(history.range(0), (-1., 1.));
+
+        assert_eq!(
+            history.points(3, false, 100., 40.),
+            [[0., 40.], [50., 0.], [100., 20.]]
+        );
+        assert_eq!(
+            history.points(3, true, 100., 40.),
+            [[0., 35.], [50., 15.], [100., 25.]]
+        );
+    }
+}
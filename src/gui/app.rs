@@ -1,24 +1,49 @@
 // Handing the events and updating the state of the application
 
-use crate::consts::APP_NAME;
-use crate::gui::view::run_page;
+use crate::consts::{APP_NAME, PREVIEW_FPS_MAX, PREVIEW_FPS_MIN, PREVIEW_HEIGHT};
+use crate::gui::{style, view::run_page};
 use crate::{
-    enums::message::Message,
-    filter::EuroDataFilter,
+    curve::apply_curves,
+    enums::{
+        app_theme::AppTheme, control_request::ControlRequest, hotkey_action::HotkeyAction,
+        message::Message, tray_action::TrayAction, tuning_step::TuningStep,
+    },
+    filter::{slider_beta, slider_min_cutoff, PoseDataFilter},
     structs::{app::HeadTracker, state::AppConfig},
     structs::{camera::ThreadedCamera, network::SocketNetwork, pose::ProcessHeadPose},
+    structs::{camera::Frame, vmc::VmcNetwork},
+    structs::{
+        broadcast::PoseSample,
+        graph::GraphSample,
+        control::{ControlResponse, ControlStatus},
+        pose::Pose,
+        quality::QualityEstimator,
+        release::{Release, UpdateChecker},
+        tray::TrayState,
+        tuning::TuningWizard,
+        validation::PoseValidator,
+    },
+    update::UPDATE_CACHE_FILE,
+    utils::{image::preview_pixels, visualize::draw_overlay},
 };
 use iced::{
-    application, executor, theme, widget::Container, Application, Color, Command, Element, Length,
+    executor, futures::channel::oneshot, widget::{image, Container}, Application, Command, Element, Length,
     Subscription, Theme,
 };
-use iced_native::{mouse, window, Event};
+use crossbeam_channel::RecvTimeoutError;
+use iced_native::{keyboard, mouse, window, Event};
 use std::{
     sync::atomic::Ordering,
     thread,
     time::{Duration, Instant},
 };
 
+// Interval at which the requests of the control server and the global hotkeys are handled
+const CONTROL_POLL_MS: u64 = 50;
+
+// Longest wait for a new camera frame before checking again whether the tracking was stopped
+const FRAME_WAIT: Duration = Duration::from_millis(100);
+
 // Log the error and break the block expression
 macro_rules! trace_error {
     ($error:expr) => {
@@ -33,8 +58,22 @@ impl Application for HeadTracker {
     type Message = Message;
     type Theme = Theme;
 
-    fn new(flags: HeadTracker) -> (HeadTracker, Command<Message>) {
-        (flags, Command::none())
+    fn new(mut flags: HeadTracker) -> (HeadTracker, Command<Message>) {
+        flags.system_dark = style::system_is_dark();
+        let command = match flags.config.update_check {
+            true => Command::perform(
+                check_for_update(UpdateChecker::new(
+                    flags.config.update_channel,
+                    flags
+                        .profile_store
+                        .as_ref()
+                        .map(|store| store.config_dir.join(UPDATE_CACHE_FILE)),
+                )),
+                Message::UpdateChecked,
+            ),
+            false => Command::none(),
+        };
+        (flags, command)
     }
 
     fn title(&self) -> String {
@@ -42,35 +81,51 @@ impl Application for HeadTracker {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        // If camera is hidden, only listen for events, otherwise listen for events and ticks to update camera frame in GUI
-        match self.config.hide_camera {
-            true => iced_native::subscription::events().map(Message::EventOccurred),
-            false => {
-                if self.headtracker_running.load(Ordering::SeqCst) {
-                    let ticks = iced::time::every(Duration::from_millis(1)).map(|_| Message::Tick);
-                    let runtime_events =
-                        iced_native::subscription::events().map(Message::EventOccurred);
-                    Subscription::batch(vec![runtime_events, ticks])
-                } else {
-                    iced_native::subscription::events().map(Message::EventOccurred)
-                }
-            }
+        let mut subscriptions =
+            vec![iced_native::subscription::events().map(Message::EventOccurred)];
+
+        // If camera, graphs or curves are visible, also listen for ticks to update them in GUI, at the preview rate
+        // The tuning wizard records the pose history on the same ticks
+        if (!self.config.hide_camera
+            || self.show_graphs
+            || self.show_curves
+            || self.tuning.is_some())
+            && self.headtracker_running.load(Ordering::SeqCst)
+            && !self.window_hidden
+        {
+            let interval = 1000 / self.config.preview_fps.clamp(PREVIEW_FPS_MIN, PREVIEW_FPS_MAX);
+            subscriptions.push(
+                iced::time::every(Duration::from_millis(interval.into())).map(|_| Message::Tick),
+            );
         }
-    }
 
-    fn theme(&self) -> Theme {
-        Theme::Light
-    }
+        // Polling the requests of the control server
+        if self.control_server.is_some() {
+            subscriptions.push(
+                iced::time::every(Duration::from_millis(CONTROL_POLL_MS))
+                    .map(|_| Message::ControlTick),
+            );
+        }
 
-    fn style(&self) -> theme::Application {
-        fn dark_background(_theme: &Theme) -> application::Appearance {
-            application::Appearance {
-                background_color: Color::from_rgb8(245, 245, 245),
-                text_color: Color::BLACK,
-            }
+        if self.tray.is_some() {
+            subscriptions.push(
+                iced::time::every(Duration::from_millis(CONTROL_POLL_MS))
+                    .map(|_| Message::TrayTick),
+            );
         }
 
-        theme::Application::from(dark_background as fn(&Theme) -> _)
+        if self.global_hotkeys_active {
+            subscriptions.push(
+                iced::time::every(Duration::from_millis(CONTROL_POLL_MS))
+                    .map(|_| Message::HotkeyTick),
+            );
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    fn theme(&self) -> Theme {
+        style::theme(self.config.theme, self.system_dark, &self.config.palette)
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
@@ -99,6 +154,14 @@ impl Application for HeadTracker {
                     let tx = self.sender.clone();
                     let rx = self.receiver.clone();
                     let error_tracker = self.error_tracker.clone();
+                    let recenter = self.recenter.clone();
+                    let pose_broadcaster = self.pose_broadcaster.clone();
+                    let output_paused = self.output_paused.clone();
+                    let metrics = self.metrics.clone();
+                    let inference = self.inference.clone();
+                    let overlay_data = self.overlay_data.clone();
+                    let preview_frame = self.preview_frame.clone();
+                    let pose_history = self.pose_history.clone();
 
                     // Spawning the thread
                     self.headtracker_thread = Some(thread::spawn(move || {
@@ -112,21 +175,45 @@ impl Application for HeadTracker {
 
                         'inner: {
                             // Creating the filter
-                            let mut euro_filter = EuroDataFilter::new(
-                                config.min_cutoff.load(Ordering::SeqCst),
-                                config.beta.load(Ordering::SeqCst),
+                            let mut pose_filter = PoseDataFilter::new(
+                                &config.axis_filters,
+                                &config.min_cutoff,
+                                &config.beta,
                             );
 
                             // Creating the network to send data to OpenTrack
-                            let mut socket_network =
-                                match SocketNetwork::new(config.ip.clone(), config.port.clone()) {
-                                    Ok(socket) => socket,
+                            let mut socket_network = match config.targets().and_then(
+                                |targets| {
+                                    SocketNetwork::new(
+                                        &targets,
+                                        config.rotation_order,
+                                        metrics.clone(),
+                                    )
+                                },
+                            ) {
+                                Ok(socket) => socket,
+                                Err(error) => {
+                                    // If an error occurs, set the error message and break the block expression
+                                    trace_error!(error);
+                                    break 'inner;
+                                }
+                            };
+
+                            // Creating the optional network to send data to VTuber/avatar apps using VMC protocol
+                            let mut vmc_network = match config.vmc_enabled {
+                                true => match config
+                                    .vmc_target()
+                                    .and_then(|target| VmcNetwork::new(&target))
+                                {
+                                    Ok(socket) => Some(socket),
                                     Err(error) => {
                                         // If an error occurs, set the error message and break the block expression
                                         trace_error!(error);
                                         break 'inner;
                                     }
-                                };
+                                },
+                                false => None,
+                            };
 
                             // Create a channel to communicate between threads
                             let mut thr_cam = match ThreadedCamera::start_camera_thread(
@@ -150,6 +237,7 @@ impl Application for HeadTracker {
                                     break 'inner;
                                 }
                             };
+                            *inference.lock().unwrap() = Some(head_pose.inference());
 
                             // Getting the first frame from the camera, if an error occurs, set the error message and use an empty frame
                             let mut frame = match rx.recv() {
@@ -158,32 +246,86 @@ impl Application for HeadTracker {
                                     error_message =
                                         format!("Unable to receive image data: {}", error);
                                     tracing::error!(error_message);
-                                    opencv::core::Mat::default()
+                                    Frame {
+                                        image: opencv::core::Mat::default(),
+                                        timestamp: Instant::now(),
+                                    }
                                 }
                             };
 
-                            // Contains x, y, z, yaw, pitch, roll
+                            // Capture time of the first frame, used as origin of the filter timestamps
+                            let epoch = frame.timestamp;
+
+                            // Contains x, y, z and the head rotation
                             let mut data;
 
+                            // Pose considered as looking straight ahead, set when recentering
+                            let mut center = Pose::default();
+
+                            let mut face_found = false;
+                            let mut last_pose_time: Option<Instant> = None;
+
+                            let mut pose_validator = PoseValidator::new(config.validation);
+                            let mut quality_estimator = QualityEstimator::new();
+                            // Last pose scoring at least min_quality, sent in place of the poorer ones
+                            let mut last_good: Option<Pose> = None;
+
+                            // Frames received since the last processed one, starting with the first frame
+                            let mut received: u64 = 1;
+
                             // Looping until headtracker_running is set to false ( ie. user clicks on the Stop button )
                             while headtracker_running.load(Ordering::SeqCst) {
                                 let start_time = Instant::now();
 
-                                // Getting the frame from the camera, if an error occurs, use the previous frame
-                                frame = match rx.try_recv() {
-                                    Ok(result) => result,
-                                    Err(_) => frame.clone(),
-                                };
+                                // Newest frame from the camera, the older ones are dropped to keep the latency low
+                                // A frame is processed only once, without a new one the next is waited for
+                                for next_frame in rx.try_iter() {
+                                    frame = next_frame;
+                                    received += 1;
+                                }
+                                if received == 0 {
+                                    match rx.recv_timeout(FRAME_WAIT) {
+                                        Ok(next_frame) => {
+                                            frame = next_frame;
+                                            received = 1;
+                                        }
+                                        Err(RecvTimeoutError::Timeout) => continue,
+                                        Err(RecvTimeoutError::Disconnected) => {
+                                            error_message =
+                                                "The camera stopped sending frames".to_string();
+                                            tracing::error!(error_message);
+                                            break;
+                                        }
+                                    }
+                                }
+                                metrics.frames_captured.fetch_add(received, Ordering::Relaxed);
+                                metrics
+                                    .frames_dropped
+                                    .fetch_add(received - 1, Ordering::Relaxed);
+                                received = 0;
+                                metrics.capture_latency.observe(frame.timestamp.elapsed());
 
                                 // Getting the head pose from the frame
-                                let out = head_pose.single_iter(&frame);
+                                let inference_start = Instant::now();
+                                let out = head_pose.single_iter(&frame.image);
+                                metrics.inference_latency.observe(inference_start.elapsed());
+                                // Shown by the preview, which no longer takes frames from the camera channel
+                                *preview_frame.lock().unwrap() = Some(frame.clone());
 
                                 // If an error occurs, skip the loop
                                 match out {
                                     Ok(value) => {
                                         data = value;
+                                        metrics.frames_processed.fetch_add(1, Ordering::Relaxed);
+                                        metrics.face_detect_runs.fetch_add(1, Ordering::Relaxed);
+                                        if face_found && !head_pose.face_found {
+                                            metrics.tracking_lost.fetch_add(1, Ordering::Relaxed);
+                                        }
+                                        face_found = head_pose.face_found;
+                                        *overlay_data.lock().unwrap() = Some(head_pose.overlay());
                                     }
                                     Err(_) => {
+                                        metrics.processing_errors.fetch_add(1, Ordering::Relaxed);
                                         // println!("An error: {}; skipped.", e);
                                         // head_pose.face_box =  [150., 150., 400., 400.];
                                         // head_pose.pts_3d =
@@ -195,24 +337,105 @@ impl Application for HeadTracker {
                                 };
 
                                 // Smoothing and Filtering the data
-                                data = euro_filter.filter_data(
+                                let filter_start = Instant::now();
+                                let timestamp =
+                                    frame.timestamp.duration_since(epoch).as_secs_f32();
+                                let raw = data;
+                                let input = head_pose.quality_input(timestamp, data);
+
+                                // Glitches of the fit are rejected, or clamped to the speed limits, before the filter
+                                let validated = pose_validator.check(&input, &head_pose.param);
+                                if let Some(glitch) = validated.glitch {
+                                    metrics.glitches.fetch_add(1, Ordering::Relaxed);
+                                    tracing::debug!("Glitch at {:.2}s: {}", timestamp, glitch);
+                                }
+                                data = validated.pose;
+
+                                // Poor frames are replaced by the last good pose, which the filter then holds
+                                let quality = quality_estimator.update(&input);
+                                metrics.quality.store(quality.score, Ordering::Relaxed);
+                                if quality.score < config.min_quality.load(Ordering::SeqCst) {
+                                    metrics.frames_rejected.fetch_add(1, Ordering::Relaxed);
+                                    data = last_good.unwrap_or(data);
+                                } else {
+                                    last_good = Some(data);
+                                }
+
+                                data = pose_filter.filter_data(data, timestamp);
+
+                                if recenter.swap(false, Ordering::SeqCst) {
+                                    center = data;
+                                }
+                                data = data.relative_to(center);
+                                metrics.filter_latency.observe(filter_start.elapsed());
+
+                                // Raw pose with the same center, so both can be compared in the graphs
+                                pose_history.lock().unwrap().push(GraphSample {
+                                    timestamp,
+                                    raw: raw
+                                        .relative_to(center)
+                                        .to_euler_data(config.rotation_order),
+                                    filtered: data.to_euler_data(config.rotation_order),
+                                    rotation: data.rotation,
+                                });
+
+                                // Shaping the output with the response curves
+                                data = apply_curves(
+                                    &config.curves.lock().unwrap(),
                                     data,
-                                    Some(config.min_cutoff.load(Ordering::SeqCst)),
-                                    Some(config.beta.load(Ordering::SeqCst)),
+                                    config.rotation_order,
                                 );
 
-                                // Sending the data to OpenTrack, if an error occurs, set the error message and break the loop
-                                match socket_network.send(data) {
-                                    Ok(_) => {}
-                                    Err(_) => {
-                                        error_message = format!(
-                                            "Unable to send data to {}:{}",
-                                            &config.ip, &config.port
-                                        );
-                                        tracing::error!(error_message);
-                                        break;
+                                // Still tracking while paused, so the pose is right when resuming
+                                let send_start = Instant::now();
+                                if !output_paused.load(Ordering::SeqCst) {
+                                    // Sending the data to OpenTrack, stopping only once no receiver could be reached for a while
+                                    match socket_network.send(data) {
+                                        Ok(_) => {}
+                                        Err(e) => {
+                                            error_message = format!("{:#}", e);
+                                            tracing::error!(error_message);
+                                            break;
+                                        }
+                                    };
+
+                                    // Sending the data to VMC receivers, if an error occurs, set the error message and break the loop
+                                    if let Some(vmc_network) = vmc_network.as_mut() {
+                                        match vmc_network.send(data, head_pose.get_blendshapes()) {
+                                            Ok(_) => {}
+                                            Err(_) => {
+                                                error_message = format!(
+                                                    "Unable to send VMC data to {}",
+                                                    vmc_network.address
+                                                );
+                                                tracing::error!(error_message);
+                                                break;
+                                            }
+                                        };
                                     }
-                                };
+                                }
+
+                                metrics.send_latency.observe(send_start.elapsed());
+                                metrics.total_latency.observe(frame.timestamp.elapsed());
+
+                                // Smoothed rate of the poses
+                                let now = Instant::now();
+                                if let Some(last_pose_time) = last_pose_time {
+                                    let fps = 1. / now.duration_since(last_pose_time).as_secs_f32();
+                                    let smoothed = match metrics.fps.load(Ordering::Relaxed) {
+                                        previous if previous > 0. => previous * 0.9 + fps * 0.1,
+                                        _ => fps,
+                                    };
+                                    metrics.fps.store(smoothed, Ordering::Relaxed);
+                                }
+                                last_pose_time = Some(now);
+
+                                pose_broadcaster.publish(PoseSample::new(
+                                    timestamp,
+                                    data,
+                                    config.rotation_order,
+                                    quality.score,
+                                ));
 
                                 // Calculating the delay time and sleeping for that amount of time, Used to set the fps
                                 let elapsed_time = start_time.elapsed();
@@ -225,6 +448,11 @@ impl Application for HeadTracker {
 
                             thr_cam.shutdown();
                         }
+                        metrics.fps.store(0., Ordering::Relaxed);
+                        metrics.quality.store(0., Ordering::Relaxed);
+                        metrics.receivers.store(0, Ordering::Relaxed);
+                        *overlay_data.lock().unwrap() = None;
+                        *preview_frame.lock().unwrap() = None;
 
                         // Setting the error message
                         let mut error_guard = error_tracker.lock().unwrap();
@@ -246,39 +474,140 @@ impl Application for HeadTracker {
                 }
             }
 
+            Message::Recenter => {
+                self.recenter.store(true, Ordering::SeqCst);
+            }
+            Message::PauseOutput => {
+                let paused = !self.output_paused.load(Ordering::SeqCst);
+                self.output_paused.store(paused, Ordering::SeqCst);
+                tracing::info!("Output paused : {}", paused);
+            }
+            Message::Hotkey(action) => {
+                let message = match action {
+                    HotkeyAction::Recenter => Message::Recenter,
+                    HotkeyAction::PauseOutput => Message::PauseOutput,
+                    HotkeyAction::ToggleTracking => Message::Toggle,
+                    HotkeyAction::TogglePreview => Message::HideCamera(!self.config.hide_camera),
+                };
+                return self.update(message);
+            }
+            Message::HotkeyTick => {
+                while let Ok(action) = self.hotkey_receiver.try_recv() {
+                    #[allow(unused_must_use)]
+                    {
+                        self.update(Message::Hotkey(action));
+                    }
+                }
+            }
+
+            // Clicks on the tray menu, then the menu is updated if the state it shows changed
+            Message::TrayTick => {
+                let actions: Vec<TrayAction> = match &self.tray {
+                    Some(tray) => tray.actions.try_iter().collect(),
+                    None => vec![],
+                };
+                let commands: Vec<Command<Message>> = actions
+                    .into_iter()
+                    .map(|action| self.update(Message::Tray(action)))
+                    .collect();
+
+                let state = self.tray_state();
+                if let Some(tray) = &mut self.tray {
+                    if tray.state != state && tray.updates.send(state.clone()).is_ok() {
+                        tray.state = state;
+                    }
+                }
+                return Command::batch(commands);
+            }
+            Message::Tray(action) => {
+                let message = match action {
+                    TrayAction::ShowWindow => {
+                        self.window_hidden = false;
+                        return Command::batch([
+                            iced::window::change_mode(iced::window::Mode::Windowed),
+                            iced::window::gain_focus(),
+                        ]);
+                    }
+                    TrayAction::ToggleTracking => Message::Toggle,
+                    TrayAction::Recenter => Message::Recenter,
+                    TrayAction::SwitchProfile(name) => Message::SwitchProfile(name),
+                    TrayAction::Quit => self.quit(),
+                };
+                return self.update(message);
+            }
+            Message::MinimizeToTray(value) => {
+                self.config.minimize_to_tray = value;
+                self.save_config()
+            }
+            Message::StartMinimized(value) => {
+                self.config.start_minimized = value;
+                self.save_config()
+            }
+
+            // Answering the requests received by the control server
+            Message::ControlTick => {
+                while let Ok(command) = self.control_receiver.try_recv() {
+                    let response = self.control_response(command.request);
+                    // The client may have disconnected in the meantime
+                    let _ = command.reply.send(response);
+                }
+            }
+
             // If camera is set visible, get the frame and show it in the GUI
+            // The graphs are drawn from the pose history on each redraw
             Message::Tick => {
-                self.frame = match self.receiver.try_recv() {
-                    Ok(result) => result,
-                    Err(_) => self.frame.clone(),
-                };
+                if !self.show_graphs {
+                    if let Some(frame) = self.preview_frame.lock().unwrap().take() {
+                        self.frame = frame.image;
+                    }
+                    self.preview = self.preview_handle();
+                }
+
+                if let Some(wizard) = &mut self.tuning {
+                    if wizard.step != TuningStep::Done {
+                        wizard.record(&self.pose_history.lock().unwrap());
+                        // Scored once, against the parameters in use when the recording ended
+                        if wizard.step == TuningStep::Done {
+                            let config = &self.config;
+                            wizard.tune(
+                                std::array::from_fn(|i| {
+                                    config.min_cutoff[i].load(Ordering::SeqCst)
+                                }),
+                                std::array::from_fn(|i| config.beta[i].load(Ordering::SeqCst)),
+                            );
+                        }
+                    }
+                }
             }
 
             // Deals with the filter values
+            Message::FilterAxis(filter_axis) => {
+                self.filter_axis = filter_axis;
+            }
             Message::MinCutoffSliderChanged(value) => {
-                if value == 0 {
-                    self.config.min_cutoff.store(0., Ordering::SeqCst)
-                } else {
-                    self.config
-                        .min_cutoff
-                        .store(1. / ((value * value) as f32), Ordering::SeqCst)
-                };
+                let min_cutoff = slider_min_cutoff(value);
+                for &i in self.filter_axis.indices() {
+                    self.config.min_cutoff[i].store(min_cutoff, Ordering::SeqCst);
+                }
                 self.save_config()
             }
             Message::BetaSliderChanged(value) => {
-                if value == 0 {
-                    self.config.beta.store(0., Ordering::SeqCst)
-                } else {
-                    self.config
-                        .beta
-                        .store(1. / ((value * value) as f32), Ordering::SeqCst)
-                };
+                let beta = slider_beta(value);
+                for &i in self.filter_axis.indices() {
+                    self.config.beta[i].store(beta, Ordering::SeqCst);
+                }
                 self.save_config()
             }
             Message::FPSSliderChanged(fps) => {
                 self.config.fps.store(fps, Ordering::SeqCst);
                 self.save_config()
             }
+            Message::MinQualitySliderChanged(percent) => {
+                self.config
+                    .min_quality
+                    .store(percent as f32 / 100., Ordering::SeqCst);
+                self.save_config()
+            }
             Message::InputIP(ip) => {
                 self.config.ip = ip;
                 self.save_config()
@@ -287,6 +616,26 @@ impl Application for HeadTracker {
                 self.config.port = port;
                 self.save_config()
             }
+            Message::InputExtraTargets(targets) => {
+                self.config.extra_targets = targets;
+                self.save_config()
+            }
+            Message::RotationOrder(rotation_order) => {
+                self.config.rotation_order = rotation_order;
+                self.save_config()
+            }
+            Message::VmcToggled(value) => {
+                self.config.vmc_enabled = value;
+                self.save_config()
+            }
+            Message::InputVmcIP(ip) => {
+                self.config.vmc_ip = ip;
+                self.save_config()
+            }
+            Message::InputVmcPort(port) => {
+                self.config.vmc_port = port;
+                self.save_config()
+            }
 
             Message::Camera(camera_name) => {
                 self.config.selected_camera = camera_name;
@@ -303,27 +652,177 @@ impl Application for HeadTracker {
 
                 self.save_config()
             }
+            Message::SwitchProfile(name) => {
+                let result = self.switch_profile(&name);
+                self.profile_changed(result, true);
+            }
+            Message::InputProfileName(name) => {
+                self.profile_name = name;
+            }
+            Message::NewProfile => {
+                let name = self.profile_name.trim().to_string();
+                let result = self.create_profile(&name);
+                self.profile_changed(result, true);
+            }
+            Message::CloneProfile => {
+                let name = self.profile_name.trim().to_string();
+                let result = self.clone_profile(&name);
+                self.profile_changed(result, false);
+            }
+            Message::RenameProfile => {
+                let name = self.profile_name.trim().to_string();
+                let result = self.rename_profile(&name);
+                self.profile_changed(result, false);
+            }
+            Message::DeleteProfile => {
+                let result = self.delete_profile();
+                self.profile_changed(result, true);
+            }
+            Message::ImportConfig => {
+                if let Some(path) = config_file_dialog("Import settings").pick_file() {
+                    let result = self.import_config(&path);
+                    self.profile_changed(result, false);
+                }
+            }
+            Message::ApplyImport => {
+                let result = self.apply_import();
+                self.profile_changed(result, true);
+            }
+            Message::CancelImport => {
+                self.pending_import = None;
+            }
+            Message::ExportProfile => {
+                if let Some(path) = config_file_dialog("Export profile")
+                    .set_file_name(&format!("{}.toml", self.profile))
+                    .save_file()
+                {
+                    let result = self.export_profile(&path);
+                    self.profile_changed(result, false);
+                }
+            }
+            Message::ExportAll => {
+                if let Some(path) = config_file_dialog("Export all profiles")
+                    .set_file_name("StableView profiles.toml")
+                    .save_file()
+                {
+                    let result = self.export_all(&path);
+                    self.profile_changed(result, false);
+                }
+            }
+            Message::ExportDiagnostics => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .set_title("Export diagnostics")
+                    .add_filter("Zip", &["zip"])
+                    .set_file_name("StableView diagnostics.zip")
+                    .save_file()
+                {
+                    let result = self.export_diagnostics(&path);
+                    self.profile_changed(result, false);
+                }
+            }
+            Message::Theme(theme) => {
+                if theme == AppTheme::System {
+                    self.system_dark = style::system_is_dark();
+                }
+                self.config.theme = theme;
+                self.save_config()
+            }
             Message::HideCamera(value) => {
                 self.config.hide_camera = value;
                 self.save_config()
             }
+            Message::ShowGraphs(value) => {
+                self.show_graphs = value;
+            }
+            // The curve editor and the tuning wizard take the place of the filter sliders
+            Message::ShowCurves(value) => {
+                self.show_curves = value;
+                self.tuning = None;
+            }
+            Message::CurveAxis(axis) => {
+                self.curve_axis = axis;
+            }
+            Message::CurvePreset(preset) => {
+                let axis = self.curve_axis.indices()[0];
+                self.config.curves.lock().unwrap()[axis].points = preset.points();
+                self.save_config()
+            }
+            Message::CurveMaxInput(max_input) => {
+                let axis = self.curve_axis.indices()[0];
+                self.config.curves.lock().unwrap()[axis].max_input = max_input as f32;
+                self.save_config()
+            }
+            // Saved once the point is released, not while it is dragged
+            Message::CurvePointMoved(axis, index, point) => {
+                self.config.curves.lock().unwrap()[axis].move_point(index, point);
+            }
+            Message::CurvePointReleased => self.save_config(),
+            // Frames already in the pose history are not part of the recording
+            Message::StartTuning => {
+                let last_timestamp = self
+                    .pose_history
+                    .lock()
+                    .unwrap()
+                    .latest()
+                    .map(|sample| sample.timestamp);
+                self.tuning = Some(TuningWizard::new(last_timestamp));
+                self.show_curves = false;
+            }
+            Message::ApplyTuning => {
+                if let Some(result) = self.tuning.take().and_then(|wizard| wizard.result) {
+                    for (i, axis) in result.iter().enumerate() {
+                        self.config.min_cutoff[i].store(axis.min_cutoff, Ordering::SeqCst);
+                        self.config.beta[i].store(axis.beta, Ordering::SeqCst);
+                    }
+                    self.save_config()
+                }
+            }
+            Message::CancelTuning => {
+                self.tuning = None;
+            }
+            Message::DebugOverlay(value) => {
+                self.config.overlay.enabled = value;
+                self.save_config()
+            }
+            Message::OverlayElement(element, value) => {
+                self.config.overlay.set(element, value);
+                self.save_config()
+            }
 
             Message::DefaultSettings => {
-                self.config
-                    .min_cutoff
-                    .store(AppConfig::default().min_cutoff, Ordering::SeqCst);
-                self.config
-                    .beta
-                    .store(AppConfig::default().beta, Ordering::SeqCst);
+                for (i, axis) in AppConfig::default().axis_filters.iter().enumerate() {
+                    self.config.min_cutoff[i].store(axis.min_cutoff, Ordering::SeqCst);
+                    self.config.beta[i].store(axis.beta, Ordering::SeqCst);
+                }
                 self.config
                     .fps
                     .store(AppConfig::default().fps, Ordering::SeqCst);
+                self.config
+                    .min_quality
+                    .store(AppConfig::default().min_quality, Ordering::SeqCst);
                 self.config.ip = AppConfig::default().ip;
                 self.config.port = AppConfig::default().port;
+                self.config.extra_targets = String::new();
+                self.config.rotation_order = AppConfig::default().rotation_order;
+                self.config.vmc_enabled = AppConfig::default().vmc_enabled;
+                self.config.vmc_ip = AppConfig::default().vmc_ip;
+                self.config.vmc_port = AppConfig::default().vmc_port;
                 self.config.hide_camera = AppConfig::default().hide_camera;
+                self.config.preview_fps = AppConfig::default().preview_fps;
+                self.config.theme = AppConfig::default().theme;
+                self.config.palette = AppConfig::default().palette;
+                self.config.minimize_to_tray = AppConfig::default().minimize_to_tray;
+                self.config.start_minimized = AppConfig::default().start_minimized;
+                self.config.overlay = AppConfig::default().overlay;
+                self.config.validation = AppConfig::default().validation;
+                self.config.axis_filters = AppConfig::default().axis_filters;
+                *self.config.curves.lock().unwrap() = AppConfig::default().curves;
 
                 self.save_config();
             }
+            Message::UpdateChecked(release) => {
+                self.release_info = release;
+            }
             Message::OpenURL(url) => {
                 #[cfg(target_os = "windows")]
                 let program = "explorer";
@@ -371,23 +870,33 @@ impl Application for HeadTracker {
                 }
             }
             Message::EventOccurred(event) => {
-                // If the user request to close the window, stop the thread ( if running ) and exit the program
+                // If the user request to close the window, either hide it and keep tracking in the background,
+                // or stop the thread ( if running ) and exit the program
                 if let Event::Window(window::Event::CloseRequested) = event {
-                    if self.headtracker_running.load(Ordering::SeqCst) {
-                        self.headtracker_running.store(false, Ordering::SeqCst);
-                        match self.headtracker_thread.take() {
-                            Some(thread) => match thread.join() {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    tracing::error!("Could not join spawned thread: {:?}", e);
-                                }
-                            },
-                            None => {
-                                tracing::error!("Called stop on non-running thread");
-                            }
+                    if self.config.minimize_to_tray && self.tray.is_some() {
+                        self.window_hidden = true;
+                        return iced::window::change_mode(iced::window::Mode::Hidden);
+                    }
+                    self.quit();
+                }
+
+                // Hotkeys pressed while the window has the focus, unless the global ones already handle them
+                if let Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }) = event
+                {
+                    if !self.global_hotkeys_active {
+                        let action = self.config.hotkeys.lock().unwrap().action(
+                            &format!("{:?}", key_code),
+                            modifiers.control(),
+                            modifiers.shift(),
+                            modifiers.alt(),
+                        );
+                        if let Some(action) = action {
+                            return self.update(Message::Hotkey(action));
                         }
                     }
-                    std::process::exit(0);
                 }
 
                 // TODO : Refresh the camera list when use clicks anywhere in the app, need better approach,
@@ -420,3 +929,184 @@ impl Application for HeadTracker {
             .into()
     }
 }
+
+// The request is blocking, it is made from its own thread to not hold the executor
+async fn check_for_update(checker: UpdateChecker) -> Option<Release> {
+    let (sender, receiver) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = sender.send(checker.check());
+    });
+    receiver.await.ok().flatten()
+}
+
+// The format of the file is given by its extension
+fn config_file_dialog(title: &str) -> rfd::FileDialog {
+    rfd::FileDialog::new()
+        .set_title(title)
+        .add_filter("TOML", &["toml"])
+        .add_filter("JSON", &["json"])
+}
+
+impl HeadTracker {
+    // Stops the thread ( if running ) and exits the program
+    fn quit(&mut self) -> ! {
+        if self.headtracker_running.load(Ordering::SeqCst) {
+            self.headtracker_running.store(false, Ordering::SeqCst);
+            match self.headtracker_thread.take() {
+                Some(thread) => match thread.join() {
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::error!("Could not join spawned thread: {:?}", e);
+                    }
+                },
+                None => {
+                    tracing::error!("Called stop on non-running thread");
+                }
+            }
+        }
+        std::process::exit(0);
+    }
+
+    // What the tray menu shows
+    pub fn tray_state(&self) -> TrayState {
+        TrayState {
+            running: self.headtracker_running.load(Ordering::SeqCst),
+            profile: self.profile.clone(),
+            profiles: self.profiles.clone(),
+        }
+    }
+
+    // Shows the error of a profile action, or restarts the tracking to use the settings of the new profile
+    fn profile_changed(&mut self, result: anyhow::Result<()>, restart: bool) {
+        match result {
+            Ok(_) => {
+                self.profile_name.clear();
+                if restart && self.headtracker_running.load(Ordering::SeqCst) {
+                    #[allow(unused_must_use)]
+                    {
+                        self.update(Message::Toggle);
+                        self.update(Message::Toggle);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                let mut error_guard = self.error_tracker.lock().unwrap();
+                *error_guard = e.to_string();
+            }
+        }
+    }
+
+    // Latest frame with the debug overlay, downscaled to the preview widget
+    fn preview_handle(&self) -> Option<image::Handle> {
+        let overlay = self.config.overlay;
+        let overlay_data = self.overlay_data.lock().unwrap().clone();
+        // The overlay is drawn on a copy, before downscaling so the thin lines stay visible
+        let overlaid = match (overlay.enabled, overlay_data) {
+            (true, Some(data)) => match draw_overlay(&self.frame, &data, &overlay) {
+                Ok(frame) => Some(frame),
+                Err(e) => {
+                    tracing::error!("Error drawing the debug overlay: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        match preview_pixels(overlaid.as_ref().unwrap_or(&self.frame), PREVIEW_HEIGHT) {
+            Ok((0, _, _)) => None,
+            Ok((width, height, pixels)) => Some(image::Handle::from_pixels(width, height, pixels)),
+            Err(e) => {
+                tracing::error!("Error converting the preview frame: {}", e);
+                None
+            }
+        }
+    }
+
+    fn control_response(&mut self, request: ControlRequest) -> ControlResponse {
+        let running = self.headtracker_running.load(Ordering::SeqCst);
+
+        match request {
+            ControlRequest::Start => {
+                if !running {
+                    #[allow(unused_must_use)]
+                    {
+                        self.update(Message::Toggle);
+                    }
+                }
+                ControlResponse::ok()
+            }
+            ControlRequest::Stop => {
+                if running {
+                    #[allow(unused_must_use)]
+                    {
+                        self.update(Message::Toggle);
+                    }
+                }
+                ControlResponse::ok()
+            }
+            ControlRequest::Recenter => {
+                if !running {
+                    return ControlResponse::error("Tracking is not running");
+                }
+                self.recenter.store(true, Ordering::SeqCst);
+                ControlResponse::ok()
+            }
+            ControlRequest::GetStatus => ControlResponse {
+                status: Some(ControlStatus {
+                    running,
+                    output_paused: self.output_paused.load(Ordering::SeqCst),
+                    error: self.error_tracker.lock().unwrap().clone(),
+                    camera: self.config.selected_camera.clone(),
+                    fps: self.config.fps.load(Ordering::SeqCst),
+                }),
+                ..ControlResponse::ok()
+            },
+            ControlRequest::GetConfig => match serde_json::to_value(self.app_config()) {
+                Ok(config) => ControlResponse {
+                    config: Some(config),
+                    ..ControlResponse::ok()
+                },
+                Err(e) => ControlResponse::error(e.to_string()),
+            },
+            ControlRequest::SetConfig { config } => {
+                // Only the given fields are replaced
+                let mut merged = match serde_json::to_value(self.app_config()) {
+                    Ok(merged) => merged,
+                    Err(e) => return ControlResponse::error(e.to_string()),
+                };
+                match (merged.as_object_mut(), config) {
+                    (Some(merged), serde_json::Value::Object(changes)) => merged.extend(changes),
+                    _ => return ControlResponse::error("config has to be a JSON object"),
+                }
+
+                let cfg: AppConfig = match serde_json::from_value(merged) {
+                    Ok(cfg) => cfg,
+                    Err(e) => return ControlResponse::error(e.to_string()),
+                };
+                let errors = cfg.validate();
+                if !errors.is_empty() {
+                    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                    return ControlResponse::error(errors.join(", "));
+                }
+                let camera_changed = cfg.selected_camera != self.config.selected_camera;
+
+                self.apply_config(cfg);
+                self.save_config();
+
+                // Same as changing the camera in the GUI, restarting to use the new camera
+                if camera_changed && running {
+                    #[allow(unused_must_use)]
+                    {
+                        self.update(Message::Toggle);
+                        self.update(Message::Toggle);
+                    }
+                }
+
+                self.control_response(ControlRequest::GetConfig)
+            }
+            // Handled by the connection itself
+            ControlRequest::SubscribePose => ControlResponse::ok(),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// Editor of the response curve of an axis, the control points are dragged with the mouse
+
+use iced::{
+    mouse,
+    widget::canvas::{self, event, Cursor, Event, Frame, Geometry, Path, Stroke, Text},
+    Point, Rectangle, Size, Theme, Vector,
+};
+
+use crate::{enums::message::Message, structs::curve::ResponseCurve};
+
+use super::style::{filtered_color, grid_color, label_color, raw_color};
+
+// Space around the plot, in pixels
+const PADDING: f32 = 14.;
+// Distance from a control point at which it can be grabbed, in pixels
+const GRAB_RADIUS: f32 = 10.;
+const POINT_RADIUS: f32 = 5.;
+
+pub struct CurveEditor {
+    // Index of the axis in the x, y, z, yaw, pitch, roll order
+    pub axis: usize,
+    pub curve: ResponseCurve,
+    // Current filtered value of the axis, while tracking
+    pub input: Option<f32>,
+}
+
+// Index of the control point being dragged
+#[derive(Default)]
+pub struct DragState {
+    dragging: Option<usize>,
+}
+
+impl CurveEditor {
+    fn plot(bounds: Rectangle) -> Rectangle {
+        Rectangle::new(
+            Point::new(PADDING, PADDING),
+            Size::new(
+                (bounds.width - 2. * PADDING).max(1.),
+                (bounds.height - 2. * PADDING).max(1.),
+            ),
+        )
+    }
+
+    // Curve coordinates (fractions of max_input) to a position in the canvas, the output goes up
+    fn to_screen(plot: Rectangle, point: [f32; 2]) -> Point {
+        Point::new(
+            plot.x + point[0] * plot.width,
+            plot.y + (1. - point[1]) * plot.height,
+        )
+    }
+
+    fn from_screen(plot: Rectangle, position: Point) -> [f32; 2] {
+        [
+            (position.x - plot.x) / plot.width,
+            1. - (position.y - plot.y) / plot.height,
+        ]
+    }
+
+    // Control point under the cursor, the first one is fixed and cannot be grabbed
+    fn point_at(&self, plot: Rectangle, position: Point) -> Option<usize> {
+        self.curve
+            .points
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, point)| (i, Self::to_screen(plot, *point).distance(position)))
+            .filter(|(_, distance)| *distance <= GRAB_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+}
+
+impl canvas::Program<Message> for CurveEditor {
+    type State = DragState;
+
+    fn update(
+        &self,
+        state: &mut DragState,
+        event: Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let plot = Self::plot(bounds);
+        // Relative to the canvas, also outside of it while dragging
+        let position = match cursor.position() {
+            Some(position) => position - Vector::new(bounds.x, bounds.y),
+            None => return (event::Status::Ignored, None),
+        };
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if cursor.is_over(&bounds) =>
+            {
+                state.dragging = self.point_at(plot, position);
+                match state.dragging {
+                    Some(_) => (event::Status::Captured, None),
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => match state.dragging {
+                Some(index) => (
+                    event::Status::Captured,
+                    Some(Message::CurvePointMoved(
+                        self.axis,
+                        index,
+                        Self::from_screen(plot, position),
+                    )),
+                ),
+                None => (event::Status::Ignored, None),
+            },
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                match state.dragging.take() {
+                    Some(_) => (event::Status::Captured, Some(Message::CurvePointReleased)),
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &DragState,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        let plot = Self::plot(bounds);
+        let grid = Stroke::default()
+            .with_color(grid_color(theme))
+            .with_width(1.);
+
+        // Quarters of the range and the unchanged output as a reference
+        for i in 0..=4 {
+            let t = i as f32 / 4.;
+            frame.stroke(
+                &Path::line(
+                    Self::to_screen(plot, [t, 0.]),
+                    Self::to_screen(plot, [t, 1.]),
+                ),
+                grid.clone(),
+            );
+            frame.stroke(
+                &Path::line(
+                    Self::to_screen(plot, [0., t]),
+                    Self::to_screen(plot, [1., t]),
+                ),
+                grid.clone(),
+            );
+        }
+        frame.stroke(
+            &Path::line(
+                Self::to_screen(plot, [0., 0.]),
+                Self::to_screen(plot, [1., 1.]),
+            ),
+            Stroke::default()
+                .with_color(raw_color(theme))
+                .with_width(1.),
+        );
+        frame.fill_text(Text {
+            content: format!("{}", self.curve.max_input),
+            position: Point::new(plot.x + plot.width - 20., plot.y + plot.height + 1.),
+            color: label_color(theme),
+            size: 11.,
+            ..Text::default()
+        });
+
+        let curve = Path::new(|builder| {
+            for (i, point) in self.curve.points.iter().enumerate() {
+                match i {
+                    0 => builder.move_to(Self::to_screen(plot, *point)),
+                    _ => builder.line_to(Self::to_screen(plot, *point)),
+                }
+            }
+        });
+        frame.stroke(
+            &curve,
+            Stroke::default()
+                .with_color(filtered_color(theme))
+                .with_width(2.),
+        );
+        for point in &self.curve.points {
+            frame.fill(
+                &Path::circle(Self::to_screen(plot, *point), POINT_RADIUS),
+                filtered_color(theme),
+            );
+        }
+
+        // Where the current input is on the curve
+        if let Some(input) = self.input.filter(|_| self.curve.max_input > 0.) {
+            let x = (input.abs() / self.curve.max_input).min(1.);
+            let marker = Self::to_screen(plot, [x, self.curve.evaluate(x)]);
+            frame.stroke(
+                &Path::line(Self::to_screen(plot, [x, 0.]), marker),
+                Stroke::default()
+                    .with_color(label_color(theme))
+                    .with_width(1.),
+            );
+            frame.fill(&Path::circle(marker, POINT_RADIUS - 1.), label_color(theme));
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &DragState,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> mouse::Interaction {
+        if state.dragging.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        match cursor.position_in(&bounds) {
+            Some(position) if self.point_at(Self::plot(bounds), position).is_some() => {
+                mouse::Interaction::Grab
+            }
+            _ => mouse::Interaction::default(),
+        }
+    }
+}
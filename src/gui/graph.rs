@@ -0,0 +1,169 @@
+// Live graphs of the raw and filtered pose, and a 3D head following the filtered rotation
+
+use iced::{
+    widget::canvas::{self, Cursor, Frame, Geometry, Path, Stroke, Text},
+    Point, Rectangle, Size, Theme,
+};
+
+use crate::{
+    enums::{filter_axis::FilterAxis, message::Message},
+    structs::{graph::PoseHistory, pose::Quaternion},
+};
+
+use super::style::{axis_colors, filtered_color, grid_color, label_color, raw_color};
+
+// Six plots in two columns, translation on the left and rotation on the right
+pub struct PoseGraphs {
+    pub history: PoseHistory,
+}
+
+impl canvas::Program<Message> for PoseGraphs {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        let size = Size::new(bounds.width / 2., bounds.height / 3.);
+
+        for axis in 0..6 {
+            let origin = Point::new(
+                (axis / 3) as f32 * size.width,
+                (axis % 3) as f32 * size.height,
+            );
+            self.draw_plot(&mut frame, theme, axis, origin, size);
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+impl PoseGraphs {
+    fn draw_plot(&self, frame: &mut Frame, theme: &Theme, axis: usize, origin: Point, size: Size) {
+        // Space between the plots, leaving room for the label
+        let plot = Rectangle::new(
+            Point::new(origin.x + 4., origin.y + 16.),
+            Size::new(size.width - 8., size.height - 22.),
+        );
+        let (min, max) = self.history.range(axis);
+
+        frame.stroke(
+            &Path::rectangle(plot.position(), plot.size()),
+            Stroke::default()
+                .with_color(grid_color(theme))
+                .with_width(1.),
+        );
+        frame.fill_text(Text {
+            content: format!("{}  {:.1} to {:.1}", FilterAxis::ALL[axis + 2], min, max),
+            position: Point::new(origin.x + 4., origin.y + 2.),
+            color: label_color(theme),
+            size: 12.,
+            ..Text::default()
+        });
+
+        // Zero line, when it is in the plot
+        if min < 0. && max > 0. {
+            let y = plot.y + plot.height * max / (max - min);
+            frame.stroke(
+                &Path::line(Point::new(plot.x, y), Point::new(plot.x + plot.width, y)),
+                Stroke::default()
+                    .with_color(grid_color(theme))
+                    .with_width(1.),
+            );
+        }
+
+        for (filtered, color) in [(false, raw_color(theme)), (true, filtered_color(theme))] {
+            let points = self.history.points(axis, filtered, plot.width, plot.height);
+            if points.len() < 2 {
+                continue;
+            }
+            let path = Path::new(|builder| {
+                builder.move_to(Point::new(plot.x + points[0][0], plot.y + points[0][1]));
+                for point in &points[1..] {
+                    builder.line_to(Point::new(plot.x + point[0], plot.y + point[1]));
+                }
+            });
+            frame.stroke(&path, Stroke::default().with_color(color).with_width(1.5));
+        }
+    }
+}
+
+// Box standing for the head with its axes and nose, seen from the camera
+pub struct HeadView {
+    pub rotation: Quaternion,
+}
+
+impl canvas::Program<Message> for HeadView {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        let center = frame.center();
+        let scale = bounds.width.min(bounds.height) / 5.;
+        let r = self.rotation.to_rotation_matrix();
+
+        // Orthographic projection, the y of the screen goes down
+        let project = |p: [f32; 3]| {
+            Point::new(
+                center.x + scale * (r[0][0] * p[0] + r[0][1] * p[1] + r[0][2] * p[2]),
+                center.y - scale * (r[1][0] * p[0] + r[1][1] * p[1] + r[1][2] * p[2]),
+            )
+        };
+
+        // Head about as deep as wide, a bit taller
+        let corners: Vec<[f32; 3]> = (0..8)
+            .map(|i| {
+                [
+                    if i & 1 == 0 { -0.8 } else { 0.8 },
+                    if i & 2 == 0 { -1. } else { 1. },
+                    if i & 4 == 0 { -0.8 } else { 0.8 },
+                ]
+            })
+            .collect();
+        let edges = Path::new(|builder| {
+            for i in 0..8 {
+                for bit in [1, 2, 4] {
+                    if i & bit == 0 {
+                        builder.move_to(project(corners[i]));
+                        builder.line_to(project(corners[i | bit]));
+                    }
+                }
+            }
+        });
+        frame.stroke(
+            &edges,
+            Stroke::default()
+                .with_color(label_color(theme))
+                .with_width(1.),
+        );
+
+        // Nose pointing to the camera
+        frame.stroke(
+            &Path::line(project([0., 0., 0.8]), project([0., -0.2, 1.3])),
+            Stroke::default()
+                .with_color(label_color(theme))
+                .with_width(3.),
+        );
+
+        for (axis, color) in axis_colors(theme).iter().enumerate() {
+            let mut end = [0.; 3];
+            end[axis] = 1.8;
+            frame.stroke(
+                &Path::line(project([0., 0., 0.]), project(end)),
+                Stroke::default().with_color(*color).with_width(2.),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
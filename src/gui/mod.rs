@@ -1,3 +1,5 @@
 pub mod app;
+pub mod curve;
+pub mod graph;
 pub mod style;
 pub mod view;
@@ -1,2 +1,89 @@
+use iced::{theme::Palette, Color, Theme};
+
+use crate::{enums::app_theme::AppTheme, structs::palette::ThemePalette};
+
 pub const HEIGHT_BODY: u16 = 80;
 pub const HEIGHT_FOOTER: u16 = 10;
+
+// Palettes of the themes, every widget takes its colors from them
+pub const LIGHT_PALETTE: Palette = Palette {
+    background: Color::from_rgb(0.96, 0.96, 0.96),
+    text: Color::BLACK,
+    primary: Color::from_rgb(0.15, 0.35, 0.8),
+    success: Color::from_rgb(0.15, 0.6, 0.35),
+    danger: Color::from_rgb(0.85, 0.25, 0.25),
+};
+
+// Dim enough to sit next to a dark cockpit at night
+pub const DARK_PALETTE: Palette = Palette {
+    background: Color::from_rgb(0.1, 0.11, 0.12),
+    text: Color::from_rgb(0.85, 0.85, 0.85),
+    primary: Color::from_rgb(0.3, 0.45, 0.8),
+    success: Color::from_rgb(0.25, 0.6, 0.4),
+    danger: Color::from_rgb(0.85, 0.35, 0.35),
+};
+
+// Read once at startup and when the System theme is selected, not on each redraw
+pub fn system_is_dark() -> bool {
+    matches!(dark_light::detect(), dark_light::Mode::Dark)
+}
+
+// The palette of the config file is validated when loaded, the dark one is only used if it was edited since
+fn custom_palette(palette: &ThemePalette) -> Palette {
+    let color = |[r, g, b]: [f32; 3]| Color::from_rgb(r, g, b);
+    match palette.colors() {
+        Ok([background, text, primary, success, danger]) => Palette {
+            background: color(background),
+            text: color(text),
+            primary: color(primary),
+            success: color(success),
+            danger: color(danger),
+        },
+        Err(_) => DARK_PALETTE,
+    }
+}
+
+pub fn theme(app_theme: AppTheme, system_dark: bool, palette: &ThemePalette) -> Theme {
+    Theme::custom(match app_theme {
+        AppTheme::Light => LIGHT_PALETTE,
+        AppTheme::Dark => DARK_PALETTE,
+        AppTheme::System => match system_dark {
+            true => DARK_PALETTE,
+            false => LIGHT_PALETTE,
+        },
+        AppTheme::Custom => custom_palette(palette),
+    })
+}
+
+// Labels and grid lines of the canvases, following the text color of the theme
+pub fn label_color(theme: &Theme) -> Color {
+    Color {
+        a: 0.8,
+        ..theme.palette().text
+    }
+}
+
+pub fn grid_color(theme: &Theme) -> Color {
+    Color {
+        a: 0.2,
+        ..theme.palette().text
+    }
+}
+
+// Raw and filtered values in the graphs and the curve editor
+pub fn raw_color(theme: &Theme) -> Color {
+    Color {
+        a: 0.6,
+        ..theme.palette().danger
+    }
+}
+
+pub fn filtered_color(theme: &Theme) -> Color {
+    theme.palette().primary
+}
+
+// x, y and z axes of the head
+pub fn axis_colors(theme: &Theme) -> [Color; 3] {
+    let palette = theme.palette();
+    [palette.danger, palette.success, palette.primary]
+}
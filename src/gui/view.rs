@@ -5,49 +5,84 @@ use std::{borrow::Cow, sync::atomic::Ordering};
 use iced::{
     alignment::{self, Horizontal, Vertical},
     widget::{
-        button, horizontal_space, pick_list, slider, text, text_input, toggler, vertical_space,
-        Column, Container, Row, Text,
+        button, canvas, checkbox, horizontal_space, image, pick_list, scrollable, slider, text,
+        text_input, toggler, vertical_space, Column, Container, Row, Text,
     },
-    Alignment, Length, Renderer,
+    Alignment, Length, Renderer, Theme,
 };
-use opencv::{
-    imgcodecs,
-    types::{VectorOfi32, VectorOfu8},
-};
-use opencv::prelude::VectorToVec;
 
-use crate::{consts::NO_VIDEO_IMG, enums::message::Message, structs::app::HeadTracker};
+use crate::{
+    consts::NO_VIDEO_IMG,
+    enums::{
+        app_theme::AppTheme, curve_preset::CurvePreset, filter_axis::FilterAxis,
+        message::Message, overlay_element::OverlayElement, rotation_order::RotationOrder,
+        tuning_step::TuningStep,
+    },
+    structs::{app::HeadTracker, portable::ProfileChanges, pose::Quaternion, tuning::TuningWizard},
+};
 
-use super::style::{HEIGHT_BODY, HEIGHT_FOOTER};
-use crate::consts::{APP_AUTHORS, APP_NAME, APP_REPOSITORY, APP_VERSION, ICONS};
+use super::{
+    curve::CurveEditor,
+    graph::{HeadView, PoseGraphs},
+    style::{self, filtered_color, raw_color, HEIGHT_BODY, HEIGHT_FOOTER},
+};
+use crate::consts::{
+    APP_AUTHORS, APP_NAME, APP_REPOSITORY, APP_VERSION, FILTER_REFERENCE_FPS, FPS_MAX, FPS_MIN,
+    ICONS,
+};
 
 pub fn run_page(headtracker: &HeadTracker) -> Column<Message> {
-    // Convert the min_cutoff and beta values to u32
+    // Same theme as the window, for the colors set on single widgets
+    let theme = style::theme(
+        headtracker.config.theme,
+        headtracker.system_dark,
+        &headtracker.config.palette,
+    );
+
+    // Convert the min_cutoff and beta values to u32, the sliders show the first axis of the selection
+    let axis = headtracker.filter_axis.indices()[0];
     let min_cutoff = {
-        if (headtracker.config.min_cutoff.load(Ordering::SeqCst) - 0.).abs() < f32::EPSILON {
+        let min_cutoff = headtracker.config.min_cutoff[axis].load(Ordering::SeqCst);
+        if (min_cutoff - 0.).abs() < f32::EPSILON {
             0
         } else {
-            (1. / headtracker.config.min_cutoff.load(Ordering::SeqCst)).sqrt() as u32
+            (FILTER_REFERENCE_FPS / min_cutoff).sqrt().round() as u32
         }
     };
 
     let beta = {
-        if (headtracker.config.beta.load(Ordering::SeqCst) - 0.).abs() < f32::EPSILON {
+        let beta = headtracker.config.beta[axis].load(Ordering::SeqCst);
+        if (beta - 0.).abs() < f32::EPSILON {
             0
         } else {
-            (1. / headtracker.config.beta.load(Ordering::SeqCst)).sqrt() as u32
+            (1. / beta).sqrt() as u32
         }
     };
     let fps = headtracker.config.fps.load(Ordering::SeqCst);
+    let min_quality = (headtracker.config.min_quality.load(Ordering::SeqCst) * 100.).round() as u32;
 
     let ip = headtracker.config.ip.as_str();
     let port = headtracker.config.port.as_str();
     let hide_camera = headtracker.config.hide_camera;
+    let overlay = headtracker.config.overlay;
+    let vmc_enabled = headtracker.config.vmc_enabled;
+    let vmc_ip = headtracker.config.vmc_ip.as_str();
+    let vmc_port = headtracker.config.vmc_port.as_str();
+    let extra_targets = headtracker.config.extra_targets.as_str();
+
+    // Checked while typing, so an invalid address is shown before starting the tracking
+    let target_error = headtracker.config.targets().err();
+    let vmc_error = match vmc_enabled {
+        true => headtracker.config.vmc_target().err(),
+        false => None,
+    };
 
     // Create the sliders
     let min_cutoff_slider = slider(0..=50, min_cutoff, Message::MinCutoffSliderChanged).step(1);
     let beta_slider = slider(0..=50, beta, Message::BetaSliderChanged).step(1);
-    let fps_slider = slider(15..=120, fps, Message::FPSSliderChanged).step(1);
+    let fps_slider = slider(FPS_MIN..=FPS_MAX, fps, Message::FPSSliderChanged).step(1);
+    let min_quality_slider =
+        slider(0..=90, min_quality, Message::MinQualitySliderChanged).step(5);
 
     // The main Start/Stop button
     let toggle_start = {
@@ -55,19 +90,39 @@ pub fn run_page(headtracker: &HeadTracker) -> Column<Message> {
             true => "Stop",
             false => "Start",
         };
-        button(
+        let start = button(
             text(label)
                 .vertical_alignment(Vertical::Center)
                 .horizontal_alignment(Horizontal::Center),
         )
         .height(Length::Fixed(40.))
-        .width(Length::Fixed(180.))
-        .on_press(Message::Toggle)
+        .width(Length::Fixed(180.));
+        // Stopping is always possible, starting needs valid receivers
+        match headtracker.headtracker_running.load(Ordering::SeqCst)
+            || (target_error.is_none() && vmc_error.is_none())
+        {
+            true => start.on_press(Message::Toggle),
+            false => start,
+        }
     };
 
     let sliders_row = Container::new(
         Column::new()
-            .push(text("Filter Settings").size(15))
+            .push(
+                Row::new()
+                    .align_items(Alignment::Center)
+                    .spacing(8)
+                    .push(text("Filter Settings").size(15).width(Length::Fill))
+                    .push(button(text("Auto-tune").size(14)).on_press(Message::StartTuning))
+                    .push(
+                        pick_list(
+                            &FilterAxis::ALL[..],
+                            Some(headtracker.filter_axis),
+                            Message::FilterAxis,
+                        )
+                        .text_size(14),
+                    ),
+            )
             .push(vertical_space(Length::Fixed(20.)))
             .push(text("Speed").size(14))
             .push(Container::new(min_cutoff_slider).width(Length::FillPortion(2)))
@@ -77,9 +132,17 @@ pub fn run_page(headtracker: &HeadTracker) -> Column<Message> {
             .push(vertical_space(Length::Fixed(30.)))
             .push(text("FPS").size(15))
             .push(Container::new(fps_slider).width(Length::FillPortion(2)))
+            .push(vertical_space(Length::Fixed(10.)))
+            .push(
+                text(match min_quality {
+                    0 => String::from("Minimum Quality (off)"),
+                    _ => format!("Minimum Quality {}%", min_quality),
+                })
+                .size(14),
+            )
+            .push(Container::new(min_quality_slider).width(Length::FillPortion(2)))
             .push(vertical_space(Length::Fixed(30.)))
             .push(text("IP and Port").size(15))
-            // ! IPV4 and V6 support for external devices, having only two inputs, ip and port
             .push(Container::new(
                 Row::new()
                     .spacing(5)
@@ -95,36 +158,102 @@ pub fn run_page(headtracker: &HeadTracker) -> Column<Message> {
                             .width(Length::FillPortion(15)),
                     ),
             ))
+            .push(vertical_space(Length::Fixed(5.)))
+            .push(
+                text_input("Other receivers, eg. 192.168.1.20:4242, [::1]:4242", extra_targets)
+                    .on_input(Message::InputExtraTargets)
+                    .size(14),
+            )
+            .push(input_error(target_error, &theme))
+            .push(vertical_space(Length::Fixed(30.)))
+            .push(
+                toggler("VMC Output".to_string(), vmc_enabled, Message::VmcToggled)
+                    .text_size(15)
+                    .size(20)
+                    .spacing(2),
+            )
+            .push(vertical_space(Length::Fixed(10.)))
+            .push(Container::new(
+                Row::new()
+                    .spacing(5)
+                    .push(
+                        text_input("127.0.0.1", vmc_ip)
+                            .on_input(Message::InputVmcIP)
+                            .width(Length::FillPortion(70)),
+                    )
+                    .push(text("      "))
+                    .push(
+                        text_input("39539", vmc_port)
+                            .on_input(Message::InputVmcPort)
+                            .width(Length::FillPortion(15)),
+                    ),
+            ))
+            .push(input_error(vmc_error, &theme))
             .push(vertical_space(Length::Fixed(30.))),
     )
     .padding(40);
 
     // If camera is set to hidden, show a placeholder image
-    let image = match hide_camera {
-        true => NO_VIDEO_IMG.to_vec(),
-        false => {
-            if headtracker.headtracker_running.load(Ordering::SeqCst) {
-                let frame = headtracker.frame.clone();
-                let mut encoded_image = VectorOfu8::new();
-                let params = VectorOfi32::new();
-                match imgcodecs::imencode(".PNG", &frame, &mut encoded_image, &params) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        tracing::error!("Error encoding image: {}", e);
-                    }
-                }
-                encoded_image.to_vec()
-            } else {
-                NO_VIDEO_IMG.to_vec()
-            }
-        }
+    let preview = match (
+        hide_camera,
+        headtracker.headtracker_running.load(Ordering::SeqCst),
+        &headtracker.preview,
+    ) {
+        (false, true, Some(preview)) => preview.clone(),
+        _ => image::Handle::from_memory(NO_VIDEO_IMG),
+    };
+
+    // Elements of the debug overlay, only shown while it is enabled
+    let overlay_elements = match overlay.enabled {
+        true => OverlayElement::ALL.iter().fold(
+            Row::new().spacing(10).padding([5, 0, 0, 0]),
+            |row, element| {
+                let element = *element;
+                row.push(
+                    checkbox(element.to_string(), overlay.shows(element), move |value| {
+                        Message::OverlayElement(element, value)
+                    })
+                    .size(14)
+                    .text_size(13)
+                    .spacing(4),
+                )
+            },
+        ),
+        false => Row::new(),
+    };
+
+    // Only offered when the tray icon could be created, it is the way back to a hidden window
+    let tray_options = match headtracker.tray {
+        Some(_) => Row::new()
+            .spacing(10)
+            .push(
+                checkbox(
+                    "Minimize to tray on close",
+                    headtracker.config.minimize_to_tray,
+                    Message::MinimizeToTray,
+                )
+                .size(14)
+                .text_size(13)
+                .spacing(4),
+            )
+            .push(
+                checkbox(
+                    "Start minimized",
+                    headtracker.config.start_minimized,
+                    Message::StartMinimized,
+                )
+                .size(14)
+                .text_size(13)
+                .spacing(4),
+            ),
+        None => Row::new(),
     };
 
     // Contains camera placeholder, available cameras list and the toggle button to hide the camera
     let camera_row = Container::new(
         Column::new()
             .push({
-                iced::widget::image::viewer(iced::widget::image::Handle::from_memory(image))
+                image::viewer(preview)
                     .width(Length::Fill)
                     .height(Length::Fixed(200.))
             })
@@ -153,21 +282,243 @@ pub fn run_page(headtracker: &HeadTracker) -> Column<Message> {
                             .width(Length::FillPortion(40)),
                     )
                     .padding(1),
-            )),
+            ))
+            .push(vertical_space(Length::Fixed(10.)))
+            .push(
+                toggler(
+                    "Debug Overlay".to_string(),
+                    overlay.enabled,
+                    Message::DebugOverlay,
+                )
+                .size(24)
+                .spacing(2),
+            )
+            .push(overlay_elements)
+            .push(vertical_space(Length::Fixed(20.)))
+            .push(text("Rotation Order").size(15))
+            .push(vertical_space(Length::Fixed(5.)))
+            .push(
+                pick_list(
+                    &RotationOrder::ALL[..],
+                    Some(headtracker.config.rotation_order),
+                    Message::RotationOrder,
+                )
+                .width(Length::Fill),
+            )
+            .push(vertical_space(Length::Fixed(20.)))
+            .push(text("Theme").size(15))
+            .push(vertical_space(Length::Fixed(5.)))
+            .push(
+                pick_list(
+                    &AppTheme::ALL[..],
+                    Some(headtracker.config.theme),
+                    Message::Theme,
+                )
+                .width(Length::Fill),
+            )
+            .push(vertical_space(Length::Fixed(10.)))
+            .push(tray_options),
     )
     .padding(40)
     .center_x()
     .center_y();
 
-    let start_button_row = Container::new(toggle_start)
-        .width(Length::Fill)
-        .align_x(Horizontal::Center);
+    // Response curve of a single axis, shown in place of the filter settings
+    let curves_row = {
+        let axis = headtracker.curve_axis.indices()[0];
+        let curve = headtracker.config.curves.lock().unwrap()[axis].clone();
+        let input = match headtracker.headtracker_running.load(Ordering::SeqCst) {
+            true => headtracker
+                .pose_history
+                .lock()
+                .unwrap()
+                .latest()
+                .map(|sample| sample.filtered[axis]),
+            false => None,
+        };
+        let max_input = curve.max_input.round() as u32;
+
+        Container::new(
+            Column::new()
+                .push(
+                    Row::new()
+                        .spacing(8)
+                        .align_items(Alignment::Center)
+                        .push(text("Response Curve").size(15).width(Length::Fill))
+                        .push(
+                            pick_list(
+                                &FilterAxis::ALL[2..],
+                                Some(headtracker.curve_axis),
+                                Message::CurveAxis,
+                            )
+                            .text_size(14),
+                        )
+                        .push(
+                            pick_list(&CurvePreset::ALL[..], None, Message::CurvePreset)
+                                .placeholder("Preset")
+                                .text_size(14),
+                        ),
+                )
+                .push(vertical_space(Length::Fixed(10.)))
+                .push(
+                    canvas(CurveEditor { axis, curve, input })
+                        .width(Length::Fill)
+                        .height(Length::Fixed(260.)),
+                )
+                .push(vertical_space(Length::Fixed(10.)))
+                .push(text(format!("Range {}", max_input)).size(14))
+                .push(
+                    slider(
+                        5..=180,
+                        max_input.clamp(5, 180),
+                        Message::CurveMaxInput,
+                    )
+                    .step(5),
+                ),
+        )
+        .padding(40)
+    };
+
+    // Raw and filtered pose of the last seconds, and the head following the filtered rotation
+    let graphs_row = {
+        let history = headtracker.pose_history.lock().unwrap().clone();
+        let rotation = history
+            .latest()
+            .map_or(Quaternion::IDENTITY, |sample| sample.rotation);
+
+        Container::new(
+            Column::new()
+                .push(
+                    canvas(PoseGraphs { history })
+                        .width(Length::Fill)
+                        .height(Length::Fixed(240.)),
+                )
+                .push(vertical_space(Length::Fixed(10.)))
+                .push(
+                    Row::new()
+                        .spacing(20)
+                        .align_items(Alignment::Center)
+                        .push(
+                            canvas(HeadView { rotation })
+                                .width(Length::Fixed(120.))
+                                .height(Length::Fixed(120.)),
+                        )
+                        .push(
+                            Column::new()
+                                .spacing(4)
+                                .push(text("Raw").size(14).style(raw_color(&theme)))
+                                .push(
+                                    text("Filtered").size(14).style(filtered_color(&theme)),
+                                ),
+                        ),
+                ),
+        )
+        .padding(40)
+    };
+
+    // Recentering only makes sense while tracking
+    let recenter = {
+        let recenter = button(
+            text("Recenter")
+                .vertical_alignment(Vertical::Center)
+                .horizontal_alignment(Horizontal::Center),
+        )
+        .height(Length::Fixed(40.))
+        .width(Length::Fixed(120.));
+        match headtracker.headtracker_running.load(Ordering::SeqCst) {
+            true => recenter.on_press(Message::Recenter),
+            false => recenter,
+        }
+    };
 
-    let controls_row = Container::new(
+    let pause = button(
+        text(match headtracker.output_paused.load(Ordering::SeqCst) {
+            true => "Resume Output",
+            false => "Pause Output",
+        })
+        .vertical_alignment(Vertical::Center)
+        .horizontal_alignment(Horizontal::Center),
+    )
+    .height(Length::Fixed(40.))
+    .width(Length::Fixed(140.))
+    .on_press(Message::PauseOutput);
+
+    // Score of the last frame, see quality.rs
+    let quality = text(match headtracker.headtracker_running.load(Ordering::SeqCst) {
+        true => format!(
+            "Quality {:.0}%",
+            headtracker.metrics.quality.load(Ordering::Relaxed) * 100.
+        ),
+        false => String::new(),
+    })
+    .size(15)
+    .width(Length::Fixed(110.));
+
+    let start_button_row = Container::new(
         Row::new()
-            .push(Container::new(camera_row).width(Length::FillPortion(5)))
-            .push(Container::new(sliders_row).width(Length::FillPortion(5))),
-    );
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(toggle_start)
+            .push(recenter)
+            .push(pause)
+            .push(quality),
+    )
+    .width(Length::Fill)
+    .align_x(Horizontal::Center);
+
+    // Picker of the active profile, the name input is used to create, clone or rename profiles
+    let profile_row = Container::new(
+        Row::new()
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .push(text("Profile").size(15))
+            .push(
+                pick_list(
+                    Cow::from(headtracker.profiles.clone()),
+                    Some(headtracker.profile.clone()),
+                    Message::SwitchProfile,
+                )
+                .width(Length::FillPortion(30)),
+            )
+            .push(
+                text_input("Profile name", &headtracker.profile_name)
+                    .on_input(Message::InputProfileName)
+                    .on_submit(Message::NewProfile)
+                    .width(Length::FillPortion(30)),
+            )
+            .push(button(text("New").size(14)).on_press(Message::NewProfile))
+            .push(button(text("Clone").size(14)).on_press(Message::CloneProfile))
+            .push(button(text("Rename").size(14)).on_press(Message::RenameProfile))
+            .push(button(text("Delete").size(14)).on_press(Message::DeleteProfile)),
+    )
+    .padding([0, 40]);
+
+    // Changes of an imported file are reviewed in place of the settings before being applied
+    let controls_row = match &headtracker.pending_import {
+        Some((_, profiles)) => Container::new(import_review(profiles)).padding(40),
+        None => Container::new(
+            Row::new()
+                .push(
+                    match headtracker.show_graphs {
+                        true => graphs_row,
+                        false => camera_row,
+                    }
+                    .width(Length::FillPortion(5)),
+                )
+                .push(
+                    match (&headtracker.tuning, headtracker.show_curves) {
+                        (Some(wizard), _) => Container::new(tuning_wizard(
+                            wizard,
+                            headtracker.headtracker_running.load(Ordering::SeqCst),
+                        ))
+                        .padding(40),
+                        (None, true) => curves_row,
+                        (None, false) => sliders_row,
+                    }
+                    .width(Length::FillPortion(5)),
+                ),
+        ),
+    };
 
     let body = Container::new(
         Column::new()
@@ -188,12 +539,47 @@ pub fn run_page(headtracker: &HeadTracker) -> Column<Message> {
                         None => Container::new(vertical_space(Length::Fixed(40.))),
                     })
                     .push(horizontal_space(Length::Fixed(34.)))
+                    .push(
+                        button(
+                            text(match headtracker.show_graphs {
+                                true => "Camera",
+                                false => "Graphs",
+                            })
+                            .size(15),
+                        )
+                        .on_press(Message::ShowGraphs(!headtracker.show_graphs)),
+                    )
+                    .push(horizontal_space(Length::Fixed(8.)))
+                    .push(
+                        button(
+                            text(match headtracker.show_curves {
+                                true => "Filters",
+                                false => "Curves",
+                            })
+                            .size(15),
+                        )
+                        .on_press(Message::ShowCurves(!headtracker.show_curves)),
+                    )
+                    .push(horizontal_space(Length::Fixed(8.)))
+                    .push(button(text("Import").size(15)).on_press(Message::ImportConfig))
+                    .push(horizontal_space(Length::Fixed(8.)))
+                    .push(button(text("Export").size(15)).on_press(Message::ExportProfile))
+                    .push(horizontal_space(Length::Fixed(8.)))
+                    .push(button(text("Export All").size(15)).on_press(Message::ExportAll))
+                    .push(horizontal_space(Length::Fixed(8.)))
+                    .push(
+                        button(text("Diagnostics").size(15))
+                            .on_press(Message::ExportDiagnostics),
+                    )
+                    .push(horizontal_space(Length::Fixed(8.)))
                     .push(
                         button(text("  Reset to Default  ").size(15))
                             .on_press(Message::DefaultSettings),
                     )
                     .push(horizontal_space(Length::Fixed(40.))),
             ))
+            .push(vertical_space(Length::Fixed(10.)))
+            .push(profile_row)
             .push(controls_row.width(Length::FillPortion(50)))
             .push(start_button_row.width(Length::FillPortion(50)))
             .push(vertical_space(Length::Fixed(20.)))
@@ -220,6 +606,110 @@ pub fn run_page(headtracker: &HeadTracker) -> Column<Message> {
     Column::new().spacing(10).push(body).push(footer)
 }
 
+// Message shown under an invalid address, empty otherwise
+fn input_error(error: Option<anyhow::Error>, theme: &Theme) -> Text<'static> {
+    text(error.map(|e| e.to_string()).unwrap_or_default())
+        .size(13)
+        .style(theme.palette().danger)
+}
+
+// Every setting changed by the import, per profile
+fn import_review(profiles: &[ProfileChanges]) -> Column<Message> {
+    let changes = profiles.iter().fold(Column::new().spacing(4), |column, profile| {
+        let title = match (profile.created, profile.changes.is_empty()) {
+            (true, _) => format!("{} (new profile)", profile.profile),
+            (false, true) => format!("{} - no changes", profile.profile),
+            (false, false) => profile.profile.clone(),
+        };
+        profile.changes.iter().fold(
+            column
+                .push(vertical_space(Length::Fixed(6.)))
+                .push(text(title).size(15)),
+            |column, change| column.push(text(change.to_string()).size(14)),
+        )
+    });
+
+    Column::new()
+        .push(text("Review the imported settings").size(15))
+        .push(vertical_space(Length::Fixed(10.)))
+        .push(scrollable(changes).height(Length::Fixed(300.)))
+        .push(vertical_space(Length::Fixed(20.)))
+        .push(
+            Row::new()
+                .spacing(10)
+                .push(button(text("Apply").size(15)).on_press(Message::ApplyImport))
+                .push(button(text("Cancel").size(15)).on_press(Message::CancelImport)),
+        )
+}
+
+// Steps of the filter tuning, then the recommended parameters of each axis with the jitter and lag they give
+fn tuning_wizard(wizard: &TuningWizard, running: bool) -> Column<Message> {
+    let position = TuningStep::ALL
+        .iter()
+        .position(|step| *step == wizard.step)
+        .unwrap_or(0);
+    let status = match (wizard.step, running) {
+        (TuningStep::Done, _) => String::from("Recording done"),
+        (_, false) => String::from("Start the tracking to record your movements"),
+        (step, true) => format!(
+            "Step {} of {}: {}, {:.0}s left",
+            position + 1,
+            TuningStep::ALL.len() - 1,
+            step,
+            wizard.remaining().ceil()
+        ),
+    };
+
+    let mut column = Column::new()
+        .push(text("Filter Auto-tune").size(15))
+        .push(vertical_space(Length::Fixed(10.)))
+        .push(text(status).size(14))
+        .push(text(wizard.step.instructions()).size(14))
+        .push(vertical_space(Length::Fixed(20.)));
+
+    if let Some(result) = &wizard.result {
+        let cell = |content: String| text(content).size(13).width(Length::FillPortion(1));
+        column = column.push(
+            Row::new()
+                .push(cell(String::from("Axis")))
+                .push(cell(String::from("Unfiltered")))
+                .push(cell(String::from("Jitter now")))
+                .push(cell(String::from("Jitter after")))
+                .push(cell(String::from("Lag now")))
+                .push(cell(String::from("Lag after"))),
+        );
+        for (axis, tuning) in result.iter().enumerate() {
+            column = column.push(
+                Row::new()
+                    .push(cell(FilterAxis::ALL[axis + 2].to_string()))
+                    .push(cell(format!("{:.3}", tuning.raw_jitter)))
+                    .push(cell(format!("{:.3}", tuning.jitter_before)))
+                    .push(cell(format!("{:.3}", tuning.jitter_after)))
+                    .push(cell(format!("{:.0} ms", tuning.lag_before * 1000.)))
+                    .push(cell(format!("{:.0} ms", tuning.lag_after * 1000.))),
+            );
+        }
+        column = column
+            .push(vertical_space(Length::Fixed(10.)))
+            .push(
+                text("Jitter is the change per frame while still, in the units of the axis. The parameters apply to the axes using the One Euro filter")
+                    .size(12),
+            );
+    }
+
+    let apply = button(text("Apply").size(15));
+    column.push(vertical_space(Length::Fixed(20.))).push(
+        Row::new()
+            .spacing(10)
+            .push(match wizard.result {
+                Some(_) => apply.on_press(Message::ApplyTuning),
+                None => apply,
+            })
+            .push(button(text("Restart").size(15)).on_press(Message::StartTuning))
+            .push(button(text("Cancel").size(15)).on_press(Message::CancelTuning)),
+    )
+}
+
 // Shows app version and links to github and logs
 fn footer() -> Container<'static, Message, Renderer> {
     let github_button = button(
@@ -0,0 +1,301 @@
+/// Keyboard shortcuts, handled in the window and, on Linux, globally by reading the evdev input devices
+/// Key names of iced (eg. "Key1", "F5") and evdev (eg. "KEY_1", "KEY_F5") are normalized to the same names
+use crate::{
+    enums::hotkey_action::HotkeyAction,
+    structs::hotkey::{Hotkey, HotkeyBindings},
+};
+use anyhow::{bail, Result};
+
+#[cfg(target_os = "linux")]
+use crate::structs::hotkey::HeldModifiers;
+
+impl Default for HotkeyBindings {
+    fn default() -> Self {
+        Self {
+            recenter: "Ctrl+Shift+R".to_string(),
+            pause_output: "Ctrl+Shift+P".to_string(),
+            toggle_tracking: "Ctrl+Shift+S".to_string(),
+            toggle_preview: "Ctrl+Shift+H".to_string(),
+        }
+    }
+}
+
+// Upper case name without the prefixes of iced and evdev
+pub fn normalize_key_name(name: &str) -> String {
+    let name = name.trim().to_uppercase();
+    let name = name.strip_prefix("KEY_").unwrap_or(&name);
+
+    // iced names the digits Key0 to Key9
+    let name = match name.strip_prefix("KEY") {
+        Some(digit) if digit.len() == 1 && digit.chars().all(|c| c.is_ascii_digit()) => digit,
+        _ => name,
+    };
+
+    match name {
+        "ESC" => "ESCAPE".to_string(),
+        "RETURN" => "ENTER".to_string(),
+        _ => name.to_string(),
+    }
+}
+
+impl Hotkey {
+    pub fn parse(binding: &str) -> Result<Self> {
+        let mut hotkey = Hotkey {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            key: String::new(),
+        };
+
+        for part in binding.split('+').map(str::trim) {
+            match part.to_uppercase().as_str() {
+                "CTRL" | "CONTROL" => hotkey.ctrl = true,
+                "SHIFT" => hotkey.shift = true,
+                "ALT" => hotkey.alt = true,
+                "" => bail!("Empty key in hotkey {:?}", binding),
+                _ if hotkey.key.is_empty() => hotkey.key = normalize_key_name(part),
+                _ => bail!("More than one key in hotkey {:?}", binding),
+            }
+        }
+
+        if hotkey.key.is_empty() {
+            bail!("No key in hotkey {:?}", binding);
+        }
+
+        Ok(hotkey)
+    }
+
+    // Modifiers have to match exactly, so Ctrl+R does not trigger on Ctrl+Shift+R
+    pub fn matches(&self, key: &str, ctrl: bool, shift: bool, alt: bool) -> bool {
+        self.ctrl == ctrl
+            && self.shift == shift
+            && self.alt == alt
+            && self.key == normalize_key_name(key)
+    }
+}
+
+impl HotkeyBindings {
+    // Action bound to the pressed key, invalid bindings are ignored
+    pub fn action(&self, key: &str, ctrl: bool, shift: bool, alt: bool) -> Option<HotkeyAction> {
+        [
+            (&self.recenter, HotkeyAction::Recenter),
+            (&self.pause_output, HotkeyAction::PauseOutput),
+            (&self.toggle_tracking, HotkeyAction::ToggleTracking),
+            (&self.toggle_preview, HotkeyAction::TogglePreview),
+        ]
+        .into_iter()
+        .filter(|(binding, _)| !binding.trim().is_empty())
+        .find(|(binding, _)| match Hotkey::parse(binding) {
+            Ok(hotkey) => hotkey.matches(key, ctrl, shift, alt),
+            Err(_) => false,
+        })
+        .map(|(_, action)| action)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for binding in [
+            &self.recenter,
+            &self.pause_output,
+            &self.toggle_tracking,
+            &self.toggle_preview,
+        ] {
+            if !binding.trim().is_empty() {
+                Hotkey::parse(binding)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl HeldModifiers {
+    // Returns whether the key is a modifier
+    pub fn update(&mut self, keyboard: usize, key: evdev::Key, pressed: bool) -> bool {
+        use evdev::Key;
+
+        match key {
+            Key::KEY_LEFTCTRL
+            | Key::KEY_RIGHTCTRL
+            | Key::KEY_LEFTSHIFT
+            | Key::KEY_RIGHTSHIFT
+            | Key::KEY_LEFTALT
+            | Key::KEY_RIGHTALT => {}
+            _ => return false,
+        }
+        if pressed {
+            self.keys.insert((keyboard, key));
+        } else {
+            self.keys.remove(&(keyboard, key));
+        }
+        true
+    }
+
+    // Ctrl, Shift and Alt
+    pub fn state(&self) -> (bool, bool, bool) {
+        use evdev::Key;
+
+        let held = |left, right| {
+            self.keys
+                .iter()
+                .any(|(_, key)| *key == left || *key == right)
+        };
+        (
+            held(Key::KEY_LEFTCTRL, Key::KEY_RIGHTCTRL),
+            held(Key::KEY_LEFTSHIFT, Key::KEY_RIGHTSHIFT),
+            held(Key::KEY_LEFTALT, Key::KEY_RIGHTALT),
+        )
+    }
+}
+
+// Listens to every keyboard, returning the number of keyboards which could be opened
+// The bindings are shared with the configuration, so the changed ones apply without a restart
+// Reading /dev/input requires the user to be in the input group
+#[cfg(target_os = "linux")]
+pub fn spawn_global_listener(
+    bindings: std::sync::Arc<std::sync::Mutex<HotkeyBindings>>,
+    sender: crossbeam_channel::Sender<HotkeyAction>,
+) -> Result<usize> {
+    use evdev::{InputEventKind, Key};
+
+    let keyboards: Vec<_> = evdev::enumerate()
+        .filter(|(_, device)| {
+            device
+                .supported_keys()
+                .is_some_and(|keys| keys.contains(Key::KEY_A))
+        })
+        .collect();
+
+    if keyboards.is_empty() {
+        bail!("No keyboard could be opened, global hotkeys need read access to /dev/input");
+    }
+
+    let count = keyboards.len();
+    let modifiers = std::sync::Arc::new(std::sync::Mutex::new(HeldModifiers::default()));
+    for (keyboard, (path, mut device)) in keyboards.into_iter().enumerate() {
+        let bindings = bindings.clone();
+        let sender = sender.clone();
+        let modifiers = modifiers.clone();
+
+        std::thread::spawn(move || {
+            loop {
+                let events = match device.fetch_events() {
+                    Ok(events) => events,
+                    Err(e) => {
+                        tracing::error!("Stopped reading hotkeys from {:?}: {}", path, e);
+                        // Its modifiers can not be released anymore
+                        let mut modifiers = modifiers.lock().unwrap();
+                        modifiers.keys.retain(|(held, _)| *held != keyboard);
+                        return;
+                    }
+                };
+
+                for event in events {
+                    let key = match event.kind() {
+                        InputEventKind::Key(key) => key,
+                        _ => continue,
+                    };
+                    // 0 is a release, 1 a press and 2 a repeat
+                    let pressed = event.value() != 0;
+
+                    let (ctrl, shift, alt) = {
+                        let mut modifiers = modifiers.lock().unwrap();
+                        if modifiers.update(keyboard, key, pressed) || event.value() != 1 {
+                            continue;
+                        }
+                        modifiers.state()
+                    };
+                    let name = format!("{:?}", key);
+                    let action = bindings.lock().unwrap().action(&name, ctrl, shift, alt);
+                    if let Some(action) = action {
+                        if sender.send(action).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_key_name() {
+        assert_eq!(normalize_key_name("KEY_R"), "R");
+        assert_eq!(normalize_key_name("r"), "R");
+        assert_eq!(normalize_key_name("Key1"), "1");
+        assert_eq!(normalize_key_name("KEY_1"), "1");
+        assert_eq!(normalize_key_name("KEY_F5"), "F5");
+        assert_eq!(normalize_key_name("F5"), "F5");
+        assert_eq!(normalize_key_name("KEY_ESC"), normalize_key_name("Escape"));
+        assert_eq!(normalize_key_name("KEY_SPACE"), normalize_key_name("Space"));
+    }
+
+    #[test]
+    fn test_parse_hotkey() {
+        let hotkey = Hotkey::parse("Ctrl + Shift + r").unwrap();
+        assert!(hotkey.ctrl && hotkey.shift && !hotkey.alt);
+        assert_eq!(hotkey.key, "R");
+
+        assert_eq!(Hotkey::parse("F9").unwrap().key, "F9");
+        assert!(Hotkey::parse("Ctrl+Shift").is_err());
+        assert!(Hotkey::parse("Ctrl+A+B").is_err());
+        assert!(Hotkey::parse("Ctrl++").is_err());
+    }
+
+    #[test]
+    fn test_hotkey_action() {
+        let bindings = HotkeyBindings {
+            toggle_preview: String::new(),
+            ..HotkeyBindings::default()
+        };
+
+        // iced and evdev names of the same key
+        assert_eq!(
+            bindings.action("R", true, true, false),
+            Some(HotkeyAction::Recenter)
+        );
+        assert_eq!(
+            bindings.action("KEY_P", true, true, false),
+            Some(HotkeyAction::PauseOutput)
+        );
+
+        // Modifiers have to match exactly and empty bindings are disabled
+        assert_eq!(bindings.action("R", true, false, false), None);
+        assert_eq!(bindings.action("R", true, true, true), None);
+        assert_eq!(bindings.action("H", true, true, false), None);
+
+        assert!(bindings.validate().is_ok());
+        let invalid = HotkeyBindings {
+            recenter: "Ctrl+".to_string(),
+            ..HotkeyBindings::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_held_modifiers() {
+        use evdev::Key;
+
+        let mut modifiers = HeldModifiers::default();
+        assert!(!modifiers.update(0, Key::KEY_R, true));
+
+        // Ctrl on one keyboard and Shift on another
+        assert!(modifiers.update(0, Key::KEY_LEFTCTRL, true));
+        assert!(modifiers.update(1, Key::KEY_RIGHTSHIFT, true));
+        assert_eq!(modifiers.state(), (true, true, false));
+
+        // Ctrl stays held while the other keyboard still holds it
+        assert!(modifiers.update(1, Key::KEY_LEFTCTRL, true));
+        assert!(modifiers.update(0, Key::KEY_LEFTCTRL, false));
+        assert_eq!(modifiers.state(), (true, true, false));
+        modifiers.update(1, Key::KEY_LEFTCTRL, false);
+        modifiers.update(1, Key::KEY_RIGHTSHIFT, false);
+        assert_eq!(modifiers.state(), (false, false, false));
+    }
+}
@@ -0,0 +1,513 @@
+/// Log file of the application, rotated daily or by size, with the ID of the session (one run of StableView) in every event
+use crate::{
+    consts::APP_NAME,
+    enums::{log_format::LogFormat, log_level::LogLevel, log_rotation::LogRotation},
+    structs::{
+        logging::{LogSettings, RotatingFile, SessionFormat},
+        profile::ProfileStore,
+    },
+};
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::Lazy;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{
+    fmt::{
+        format::{self, Writer},
+        FmtContext, FormatEvent, FormatFields, MakeWriter,
+    },
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+pub const LOG_FILE: &str = "StableView.log";
+pub const LOG_LEVEL_ENV: &str = "STABLEVIEW_LOG";
+
+// Short random ID, to tell apart the runs in a log file sent with a bug report
+pub static SESSION_ID: Lazy<String> = Lazy::new(|| format!("{:08x}", rand::random::<u32>()));
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::default(),
+            format: LogFormat::default(),
+            rotation: LogRotation::default(),
+            max_size_mb: 10,
+            retention: 7,
+        }
+    }
+}
+
+impl LogSettings {
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=1024).contains(&self.max_size_mb) {
+            bail!("max_size_mb has to be between 1 and 1024");
+        }
+        if !(1..=100).contains(&self.retention) {
+            bail!("retention has to be between 1 and 100 files");
+        }
+        Ok(())
+    }
+}
+
+impl LogLevel {
+    pub fn parse(level: &str) -> Result<Self> {
+        let level = level.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.to_string() == level)
+            .with_context(|| {
+                format!(
+                    "{:?} is not a log level, use error, warn, info, debug or trace",
+                    level
+                )
+            })
+    }
+
+    pub fn to_tracing(self) -> Level {
+        match self {
+            LogLevel::Error => Level::ERROR,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Trace => Level::TRACE,
+        }
+    }
+}
+
+// The command line has the priority over the environment variable, itself over the config file
+pub fn select_level(
+    cli: Option<LogLevel>,
+    env: Option<&str>,
+    config: LogLevel,
+) -> Result<LogLevel> {
+    match (cli, env) {
+        (Some(level), _) => Ok(level),
+        (None, Some(env)) => {
+            LogLevel::parse(env).with_context(|| format!("Invalid {} variable", LOG_LEVEL_ENV))
+        }
+        (None, None) => Ok(config),
+    }
+}
+
+// Settings of the profile given on the command line or of the active one, read before the window is created
+pub fn profile_settings(profile: Option<&str>) -> LogSettings {
+    let store = match ProfileStore::new() {
+        Ok(store) => store,
+        Err(_) => return LogSettings::default(),
+    };
+    let profile = match profile {
+        Some(profile) if store.exists(profile) => profile.to_string(),
+        _ => store.active(),
+    };
+    store
+        .load(&profile)
+        .map(|config| config.logging)
+        .unwrap_or_default()
+}
+
+// * Similar path is also used by confy https://github.com/rust-cli/confy/blob/master/src/lib.rs#L316
+pub fn log_directory() -> Result<PathBuf> {
+    // ? Adding organization name
+    let dirs = directories::ProjectDirs::from("rs", "", APP_NAME)
+        .context("Could not find project directories")?;
+    Ok(dirs.data_dir().to_path_buf())
+}
+
+// UTC date and time from the Unix time, eg. 2023-11-14_22-13-20, used in the names of the rotated files
+pub fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((seconds / SECONDS_PER_DAY) as i64);
+    let seconds = seconds % SECONDS_PER_DAY;
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// Days since 1970-01-01 to (year, month, day), from http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month as u32, day as u32)
+}
+
+fn day(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / SECONDS_PER_DAY
+}
+
+impl RotatingFile {
+    // The log file of the previous runs is continued, it is rotated at the first event if needed
+    pub fn open(directory: &Path, settings: &LogSettings) -> Result<Self> {
+        fs::create_dir_all(directory)
+            .with_context(|| format!("Unable to create the log directory {:?}", directory))?;
+        let path = directory.join(LOG_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Unable to open the log file {:?}", path))?;
+
+        let metadata = file.metadata()?;
+        let opened = match metadata.len() {
+            0 => SystemTime::now(),
+            _ => metadata
+                .created()
+                .or_else(|_| metadata.modified())
+                .unwrap_or_else(|_| SystemTime::now()),
+        };
+
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            rotation: settings.rotation,
+            max_size: u64::from(settings.max_size_mb) * 1024 * 1024,
+            retention: settings.retention as usize,
+            file,
+            size: metadata.len(),
+            opened,
+        })
+    }
+
+    fn should_rotate(&self, now: SystemTime, length: usize) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        let full = self.size + length as u64 > self.max_size;
+        let new_day = self.rotation == LogRotation::Daily && day(self.opened) != day(now);
+        full || new_day
+    }
+
+    fn rotate(&mut self, now: SystemTime) -> io::Result<()> {
+        let timestamp = format_timestamp(self.opened);
+        let mut rotated = self.directory.join(format!("StableView.{}.log", timestamp));
+        let mut index = 1;
+        while rotated.exists() {
+            rotated = self
+                .directory
+                .join(format!("StableView.{}.{}.log", timestamp, index));
+            index += 1;
+        }
+
+        let path = self.directory.join(LOG_FILE);
+        fs::rename(&path, rotated)?;
+        self.file = OpenOptions::new().create(true).append(true).open(&path)?;
+        self.size = 0;
+        self.opened = now;
+
+        self.remove_old()
+    }
+
+    // The names start with the UTC time, so sorting them sorts the files from the oldest
+    fn remove_old(&self) -> io::Result<()> {
+        let mut rotated: Vec<PathBuf> = fs::read_dir(&self.directory)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name != LOG_FILE
+                            && name.starts_with("StableView.")
+                            && name.ends_with(".log")
+                    })
+            })
+            .collect();
+        rotated.sort();
+
+        let excess = rotated.len().saturating_sub(self.retention);
+        for path in &rotated[..excess] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let now = SystemTime::now();
+        if self.should_rotate(now, buf.len()) {
+            // Events are still written to the current file if it could not be rotated
+            if let Err(e) = self.rotate(now) {
+                eprintln!("Unable to rotate the log file: {}", e);
+            }
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for SessionFormat<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        let mut line = String::new();
+        self.inner
+            .format_event(ctx, Writer::new(&mut line), event)?;
+
+        match self.format {
+            LogFormat::Text => write!(writer, "[{}] {}", self.session, line),
+            // First field of the object
+            LogFormat::Json => match line.strip_prefix('{') {
+                Some(fields) => write!(writer, "{{\"session\":\"{}\",{}", self.session, fields),
+                None => writer.write_str(&line),
+            },
+        }
+    }
+}
+
+pub fn subscriber<W>(
+    writer: W,
+    format: LogFormat,
+    level: LogLevel,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_max_level(level.to_tracing());
+    let session = SESSION_ID.clone();
+
+    match format {
+        LogFormat::Text => Box::new(
+            builder
+                .event_format(SessionFormat {
+                    session,
+                    format,
+                    inner: format::format(),
+                })
+                .finish(),
+        ),
+        LogFormat::Json => Box::new(
+            builder
+                .json()
+                .event_format(SessionFormat {
+                    session,
+                    format,
+                    inner: format::format().json(),
+                })
+                .finish(),
+        ),
+    }
+}
+
+// The returned guard has to be kept until the end of the program, the last events are written when it is dropped
+pub fn init(directory: &Path, settings: &LogSettings, level: LogLevel) -> Result<WorkerGuard> {
+    let file = RotatingFile::open(directory, settings)?;
+    let (writer, guard) = tracing_appender::non_blocking(file);
+
+    subscriber(writer, settings.format, level)
+        .try_init()
+        .map_err(|e| anyhow!("Unable to set up logging: {}", e))?;
+
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    fn directory(test: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("stableview-logs-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn log_files(directory: &Path) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(directory)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01_00-00-00");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            "2023-11-14_22-13-20"
+        );
+        // Leap day
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_709_164_800)),
+            "2024-02-29_00-00-00"
+        );
+    }
+
+    #[test]
+    fn test_select_level() -> Result<()> {
+        assert_eq!(LogLevel::parse(" DEBUG ")?, LogLevel::Debug);
+        assert!(LogLevel::parse("verbose").is_err());
+
+        assert_eq!(
+            select_level(Some(LogLevel::Trace), Some("error"), LogLevel::Info)?,
+            LogLevel::Trace
+        );
+        assert_eq!(
+            select_level(None, Some("error"), LogLevel::Info)?,
+            LogLevel::Error
+        );
+        assert_eq!(select_level(None, None, LogLevel::Warn)?, LogLevel::Warn);
+        assert!(select_level(None, Some("loud"), LogLevel::Info).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_rotation_and_retention() -> Result<()> {
+        let directory = directory("size");
+        let settings = LogSettings {
+            rotation: LogRotation::Size,
+            retention: 2,
+            ..LogSettings::default()
+        };
+        let mut file = RotatingFile::open(&directory, &settings)?;
+        file.max_size = 100;
+
+        for i in 0..5 {
+            file.write_all(&[b'a'; 60])?;
+            // Rotated files are named after the second their first event was written
+            file.opened = UNIX_EPOCH + Duration::from_secs(i * 60);
+        }
+
+        let files = log_files(&directory);
+        assert_eq!(
+            files,
+            [
+                "StableView.1970-01-01_00-02-00.log",
+                "StableView.1970-01-01_00-03-00.log",
+                LOG_FILE
+            ]
+        );
+        assert_eq!(fs::read(directory.join(LOG_FILE))?.len(), 60);
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_daily_rotation() -> Result<()> {
+        let directory = directory("daily");
+        let mut file = RotatingFile::open(&directory, &LogSettings::default())?;
+        file.write_all(b"today\n")?;
+        assert_eq!(log_files(&directory), [LOG_FILE]);
+
+        // A file started yesterday is continued after a restart, then rotated at the next event
+        file.opened = SystemTime::now() - Duration::from_secs(SECONDS_PER_DAY);
+        let yesterday = format_timestamp(file.opened);
+        file.write_all(b"tomorrow\n")?;
+
+        assert_eq!(
+            log_files(&directory),
+            [
+                format!("StableView.{}.log", yesterday),
+                LOG_FILE.to_string()
+            ]
+        );
+        assert_eq!(fs::read_to_string(directory.join(LOG_FILE))?, "tomorrow\n");
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn logged(format: LogFormat, level: LogLevel) -> String {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        tracing::subscriber::with_default(
+            subscriber(move || writer.clone(), format, level),
+            || {
+                tracing::info!(fps = 60, "Tracking started");
+                tracing::debug!("Hidden at the info level");
+            },
+        );
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_session_in_events() -> Result<()> {
+        let text = logged(LogFormat::Text, LogLevel::Info);
+        assert_eq!(text.lines().count(), 1);
+        assert!(text.starts_with(&format!("[{}] ", *SESSION_ID)));
+        assert!(text.contains("Tracking started fps=60"));
+
+        let json = logged(LogFormat::Json, LogLevel::Debug);
+        let events: Vec<serde_json::Value> = json
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["session"], SESSION_ID.as_str());
+        assert_eq!(events[0]["level"], "INFO");
+        assert_eq!(events[0]["fields"]["message"], "Tracking started");
+        assert_eq!(events[0]["fields"]["fps"], 60);
+        Ok(())
+    }
+}
@@ -1,21 +1,50 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![allow(non_snake_case)]
 
+mod broadcast;
 mod camera;
+mod cli;
+mod config;
 mod consts;
+mod control;
+mod curve;
+mod diagnostics;
 mod enums;
 mod filter;
+mod graph;
 mod gui;
+mod hotkey;
+mod logging;
+mod metrics;
 mod network;
+mod overlay;
+mod palette;
+mod portable;
+mod pose;
+mod pose_server;
 mod process;
+mod profile;
+mod quality;
 mod structs;
 mod tddfa;
+mod tray;
+mod tuning;
+mod update;
+mod validation;
 mod face;
 mod utils;
+mod vmc;
 
 use crate::{
     consts::{APP_NAME, APP_VERSION, DEFAULT_FONT, ICON},
-    structs::app::HeadTracker,
+    logging::{log_directory, select_level, LOG_LEVEL_ENV, SESSION_ID},
+    structs::{
+        app::HeadTracker,
+        cli::CliArgs,
+        control::ControlServer,
+        metrics::MetricsServer,
+        pose_server::{PoseServer, TrackingStatus},
+    },
 };
 use iced::{
     window::{self, PlatformSpecific},
@@ -23,52 +52,57 @@ use iced::{
 };
 use image::ImageFormat;
 
-use tracing::Level;
+use std::path::Path;
 
-use std::{fs, path::Path};
-
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // ? Adding organization name
-    let log_filepath = match directories::ProjectDirs::from("rs", "", APP_NAME) {
-        Some(dirs) => dirs,
-        None => {
-            tracing::error!("Could not find project directories");
-            std::process::exit(1);
+    let cli_args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(cli_args) => cli_args,
+        Err(e) => {
+            cli::attach_console();
+            eprintln!("{}", e);
+            std::process::exit(2);
         }
     };
-    let log_filename = "StableView.log";
-
-    // println!("{:?}", std::env::current_exe().unwrap());
-
-    match fs::remove_file(log_filepath.data_dir().join(log_filename)) {
-        Ok(_) => tracing::warn!("Removed old log file"),
-        Err(_) => tracing::warn!("No old log file found"),
+    if cli_args.help {
+        cli::attach_console();
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if cli_args.has_command() {
+        cli::attach_console();
+        if let Err(e) = cli_args.run_command() {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
     }
 
-    let file_appender = tracing_appender::rolling::never(
-        // * Similar path is also used by confy https://github.com/rust-cli/confy/blob/master/src/lib.rs#L316
-        match log_filepath.data_dir().to_str() {
-            Some(path) => path,
-            None => {
-                tracing::error!("Could not find project directories");
-                std::process::exit(1);
-            }
-        },
-        log_filename,
-    );
-
-    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-
-    tracing_subscriber::fmt()
-        .with_writer(non_blocking)
-        .with_ansi(false)
-        .with_max_level(Level::WARN)
-        .init(); // ! Need to have only 1 log file which resets daily
+    // Logging is set up before anything else, from the profile used for this run
+    let log_settings = logging::profile_settings(cli_args.profile.as_deref());
+    let env_level = std::env::var(LOG_LEVEL_ENV).ok();
+    let (level, level_error) =
+        match select_level(cli_args.log_level, env_level.as_deref(), log_settings.level) {
+            Ok(level) => (level, None),
+            Err(e) => (log_settings.level, Some(e)),
+        };
+    let _guard = match log_directory()
+        .and_then(|directory| logging::init(&directory, &log_settings, level))
+    {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            eprintln!("Logging is disabled: {:#}", e);
+            None
+        }
+    };
+    if let Some(e) = level_error {
+        tracing::warn!("{:#}, using the {} level", e, level);
+    }
 
-    tracing::warn!("Version {} on {}", APP_VERSION, std::env::consts::OS);
-    tracing::warn!(
+    tracing::info!("Session {} started", *SESSION_ID);
+    tracing::info!("Version {} on {}", APP_VERSION, std::env::consts::OS);
+    tracing::info!(
         "The configuration file path is: {:#?}",
         match confy::get_configuration_file_path(APP_NAME, "config") {
             Ok(path) => path,
@@ -80,16 +114,98 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let mut flags = HeadTracker::default();
+
+    // Profile given on the command line, eg. by a simulator launcher, only for this run
+    // A misspelled name is reported rather than creating a new profile
+    if let Some(profile) = cli_args.profile {
+        let selected = match flags.profiles.contains(&profile) {
+            true => flags.use_profile(&profile),
+            false => Err(anyhow!(
+                "No profile named {:?}, the profiles are {}",
+                profile,
+                flags.profiles.join(", ")
+            )),
+        };
+        if let Err(e) = selected {
+            tracing::error!("Unable to use profile {:?} : {:?}", profile, e);
+            *flags.error_tracker.lock().unwrap() = format!("{:#}", e);
+        }
+    }
+
     flags.config = flags.load_config();
 
-    tracing::warn!("Config : {}", flags);
+    if flags.config.control_enabled {
+        match ControlServer::start(
+            flags.config.control_port,
+            ControlServer::default_socket_path(),
+            flags.config.control_token.clone(),
+            flags.control_sender.clone(),
+            flags.pose_broadcaster.clone(),
+        ) {
+            Ok(server) => flags.control_server = Some(server),
+            Err(e) => tracing::error!("Unable to start the control server: {:?}", e),
+        }
+    }
+
+    if flags.config.pose_server_enabled {
+        match PoseServer::start(
+            flags.config.pose_server_port,
+            flags.config.pose_server_max_rate,
+            flags.pose_broadcaster.clone(),
+            TrackingStatus {
+                running: flags.headtracker_running.clone(),
+                output_paused: flags.output_paused.clone(),
+            },
+        ) {
+            Ok(server) => flags.pose_server = Some(server),
+            Err(e) => tracing::error!("Unable to start the pose server: {:?}", e),
+        }
+    }
+
+    if flags.config.metrics_enabled {
+        match MetricsServer::start(flags.config.metrics_port, flags.metrics.clone()) {
+            Ok(server) => flags.metrics_server = Some(server),
+            Err(e) => tracing::error!("Unable to start the metrics server: {:?}", e),
+        }
+    }
+
+    tracing::info!("Config : {}", flags);
+
+    if let Err(e) = flags.config.hotkeys.lock().unwrap().validate() {
+        tracing::error!("Invalid hotkey, it will be ignored : {}", e);
+    }
+
+    // Window hotkeys are ignored when the global ones work, to not trigger actions twice
+    #[cfg(target_os = "linux")]
+    if flags.config.global_hotkeys {
+        match hotkey::spawn_global_listener(
+            flags.config.hotkeys.clone(),
+            flags.hotkey_sender.clone(),
+        ) {
+            Ok(keyboards) => {
+                tracing::info!("Listening to global hotkeys on {} keyboards", keyboards);
+                flags.global_hotkeys_active = true;
+            }
+            Err(e) => tracing::error!("Unable to listen to global hotkeys: {:?}", e),
+        }
+    }
+
+    // The window is only started hidden when the tray icon can show it again
+    match tray::spawn_tray(flags.tray_state()) {
+        Ok(tray) => {
+            flags.tray = Some(tray);
+            flags.window_hidden = flags.config.start_minimized;
+        }
+        Err(e) => tracing::error!("Unable to create the tray icon: {:?}", e),
+    }
+    let visible = !flags.window_hidden;
 
     let settings = Settings {
         id: None,
         window: window::Settings {
-            size: (750, 620), // start size
+            size: (750, 750), // start size
             position: window::Position::Centered,
-            min_size: Some((750, 620)), // min size allowed
+            min_size: Some((750, 750)), // min size allowed
             max_size: None,
             resizable: true,
             decorations: true,
@@ -99,7 +215,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Ok(icon) => Some(icon),
                 Err(_) => None,
             },
-            visible: true,
+            visible,
             platform_specific: PlatformSpecific::default(),
         },
         flags,
@@ -0,0 +1,271 @@
+/// Metrics of the tracking in the Prometheus text format, served on http://127.0.0.1:<port>/metrics
+use crate::structs::metrics::{Histogram, Metrics, MetricsServer, LATENCY_BUCKETS};
+use anyhow::{Context, Result};
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{atomic::Ordering, Arc},
+    thread,
+    time::Duration,
+};
+
+// Slow or silent clients are not allowed to hold the server
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl Histogram {
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    // Buckets are cumulative in the exposition format
+    fn render(&self, output: &mut String, name: &str, stage: &str) {
+        let mut count = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            count += bucket.load(Ordering::Relaxed);
+            let bound = match LATENCY_BUCKETS.get(i) {
+                Some(bound) => bound.to_string(),
+                None => "+Inf".to_string(),
+            };
+            let _ = writeln!(
+                output,
+                "{}_bucket{{stage=\"{}\",le=\"{}\"}} {}",
+                name, stage, bound, count
+            );
+        }
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(output, "{}_sum{{stage=\"{}\"}} {}", name, stage, sum);
+        let _ = writeln!(output, "{}_count{{stage=\"{}\"}} {}", name, stage, count);
+    }
+}
+
+impl Metrics {
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        let counters = [
+            (
+                "frames_captured",
+                "Frames received from the camera",
+                &self.frames_captured,
+            ),
+            (
+                "frames_processed",
+                "Frames the head pose was estimated from",
+                &self.frames_processed,
+            ),
+            (
+                "frames_dropped",
+                "Frames skipped to process a newer one",
+                &self.frames_dropped,
+            ),
+            (
+                "processing_errors",
+                "Frames the head pose estimation failed on",
+                &self.processing_errors,
+            ),
+            (
+                "face_detect_runs",
+                "Runs of the face detector",
+                &self.face_detect_runs,
+            ),
+            (
+                "tracking_lost",
+                "Times the face was lost",
+                &self.tracking_lost,
+            ),
+            (
+                "frames_rejected",
+                "Frames replaced by the last good pose for their low quality",
+                &self.frames_rejected,
+            ),
+            (
+                "glitches",
+                "Poses rejected or clamped before the filter as implausible",
+                &self.glitches,
+            ),
+            (
+                "packets_sent",
+                "UDP packets sent to opentrack receivers",
+                &self.packets_sent,
+            ),
+            (
+                "send_errors",
+                "UDP packets which could not be sent",
+                &self.send_errors,
+            ),
+        ];
+        for (name, help, counter) in counters {
+            let _ = writeln!(output, "# HELP stableview_{}_total {}", name, help);
+            let _ = writeln!(output, "# TYPE stableview_{}_total counter", name);
+            let _ = writeln!(
+                output,
+                "stableview_{}_total {}",
+                name,
+                counter.load(Ordering::Relaxed)
+            );
+        }
+
+        let _ = writeln!(output, "# HELP stableview_fps Poses computed per second");
+        let _ = writeln!(output, "# TYPE stableview_fps gauge");
+        let _ = writeln!(
+            output,
+            "stableview_fps {}",
+            self.fps.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            output,
+            "# HELP stableview_tracking_quality Quality of the last pose, from 0 to 1"
+        );
+        let _ = writeln!(output, "# TYPE stableview_tracking_quality gauge");
+        let _ = writeln!(
+            output,
+            "stableview_tracking_quality {}",
+            self.quality.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            output,
+            "# HELP stableview_receivers Opentrack receivers of the running tracking"
+        );
+        let _ = writeln!(output, "# TYPE stableview_receivers gauge");
+        let _ = writeln!(
+            output,
+            "stableview_receivers {}",
+            self.receivers.load(Ordering::Relaxed)
+        );
+
+        let name = "stableview_latency_seconds";
+        let _ = writeln!(
+            output,
+            "# HELP {} Latency of each stage of the tracking",
+            name
+        );
+        let _ = writeln!(output, "# TYPE {} histogram", name);
+        self.capture_latency.render(&mut output, name, "capture");
+        self.inference_latency
+            .render(&mut output, name, "inference");
+        self.filter_latency.render(&mut output, name, "filter");
+        self.send_latency.render(&mut output, name, "send");
+        self.total_latency.render(&mut output, name, "total");
+
+        output
+    }
+}
+
+impl MetricsServer {
+    pub fn start(port: u16, metrics: Arc<Metrics>) -> Result<Self> {
+        // Only reachable from this computer
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("Unable to bind metrics server on port {}", port))?;
+        let address = listener.local_addr()?;
+        tracing::info!("Metrics server listening on {}", address);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(e) = handle_client(stream, &metrics) {
+                    tracing::info!("Metrics request failed: {}", e);
+                }
+            }
+        });
+
+        Ok(Self { address })
+    }
+}
+
+// Scrapes are small and rare, they are answered one at a time
+fn handle_client(mut stream: TcpStream, metrics: &Metrics) -> Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not needed, only read until the end of the request
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            ("200 OK", "text/plain; version=0.0.4", metrics.render())
+        }
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n".to_string(),
+        ),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn get(server: &MetricsServer, path: &str) -> Result<String> {
+        let mut stream = TcpStream::connect(server.address)?;
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path)?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_micros(500));
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(3));
+
+        let mut output = String::new();
+        histogram.render(&mut output, "latency", "total");
+        assert!(output.contains("latency_bucket{stage=\"total\",le=\"0.001\"} 1\n"));
+        assert!(output.contains("latency_bucket{stage=\"total\",le=\"0.025\"} 2\n"));
+        assert!(output.contains("latency_bucket{stage=\"total\",le=\"1\"} 2\n"));
+        assert!(output.contains("latency_bucket{stage=\"total\",le=\"+Inf\"} 3\n"));
+        assert!(output.contains("latency_sum{stage=\"total\"} 3.0205\n"));
+        assert!(output.contains("latency_count{stage=\"total\"} 3\n"));
+    }
+
+    #[test]
+    fn test_metrics_server() -> Result<()> {
+        let metrics = Arc::new(Metrics::default());
+        metrics.frames_processed.fetch_add(42, Ordering::Relaxed);
+        metrics.fps.store(59.5, Ordering::Relaxed);
+        metrics.quality.store(0.75, Ordering::Relaxed);
+        let server = MetricsServer::start(0, metrics)?;
+
+        let response = get(&server, "/metrics")?;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE stableview_frames_processed_total counter"));
+        assert!(response.contains("\nstableview_frames_processed_total 42\n"));
+        assert!(response.contains("\nstableview_fps 59.5\n"));
+        assert!(response.contains("\nstableview_tracking_quality 0.75\n"));
+        assert!(response.contains("stableview_latency_seconds_count{stage=\"inference\"} 0"));
+
+        assert!(get(&server, "/")?.starts_with("HTTP/1.1 404"));
+        Ok(())
+    }
+}
@@ -1,49 +1,351 @@
 /// Deals with sending the data (x,y,depth,yaw,pitch,roll) to opentrack (https://github.com/opentrack/opentrack) using UDP socket
-use crate::structs::network::SocketNetwork;
-use anyhow::{Context, Result};
-use std::net::UdpSocket;
+use crate::enums::rotation_order::RotationOrder;
+use crate::structs::{
+    metrics::Metrics,
+    network::{NetworkTarget, SocketNetwork},
+    pose::Pose,
+};
+use anyhow::{bail, Context, Result};
+use std::{
+    net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
 
-impl SocketNetwork {
-    pub fn new(ip: String, port: String) -> Result<Self> {
-        tracing::info!("Sending data to {} on port {}", ip, port);
+// Time every receiver can keep failing before the tracking is stopped, eg. the network went down
+const SEND_FAILURE_TIMEOUT: Duration = Duration::from_secs(3);
+
+impl NetworkTarget {
+    // IPv6 addresses may be given with or without brackets, eg. ::1 or [::1]
+    pub fn validate_host(host: &str) -> Result<String> {
+        let host = host.trim();
+        let unbracketed = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+        if unbracketed.parse::<IpAddr>().is_ok() {
+            return Ok(unbracketed.to_string());
+        }
 
-        let address = format!("{}:{}", ip, port);
+        // Hostname made of dot separated labels of letters, digits and hyphens
+        let valid_hostname = !host.is_empty()
+            && host.len() <= 253
+            && host.split('.').all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        if !valid_hostname {
+            bail!("{:?} is not a valid IP address or hostname", host);
+        }
+        Ok(host.to_string())
+    }
 
-        let socket_network = UdpSocket::bind("0.0.0.0:0")
-            .with_context(|| format!("Unable to bind socket : {:?}:{:?}", ip, port))?;
+    pub fn validate_port(port: &str) -> Result<u16> {
+        match port.trim().parse::<u16>() {
+            Ok(port) if port > 0 => Ok(port),
+            _ => bail!("{:?} is not a port between 1 and 65535", port),
+        }
+    }
 
+    pub fn parse(host: &str, port: &str) -> Result<Self> {
         Ok(Self {
-            address,
-            socket_network,
+            host: Self::validate_host(host)?,
+            port: Self::validate_port(port)?,
         })
     }
 
-    // TODO : Cleaning and possibly removing unsafe code
-    pub fn send(&mut self, data: [f32; 6]) -> Result<()> {
-        let data: [f64; 6] = [
-            data[0] as f64,
-            data[1] as f64,
-            data[2] as f64,
-            data[3] as f64,
-            data[4] as f64,
-            data[5] as f64,
-        ];
+    // host:port, with IPv6 addresses in brackets, eg. 192.168.1.20:4242 or [::1]:4242
+    pub fn parse_address(address: &str) -> Result<Self> {
+        let address = address.trim();
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, port),
+            None => bail!("{:?} has no port, use host:port", address),
+        };
+        if host.contains(':') && !host.starts_with('[') {
+            bail!(
+                "IPv6 address {:?} has to be in brackets, eg. [::1]:4242",
+                address
+            );
+        }
+        Self::parse(host, port)
+    }
 
-        // Convert an array to f64 to array of u8
-        let out =
-            unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * 10) };
+    // Comma separated list of addresses, as typed in the GUI
+    pub fn parse_list(addresses: &str) -> Result<Vec<Self>> {
+        addresses
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(Self::parse_address)
+            .collect()
+    }
 
-        // Send data
-        self.socket_network.send_to(out, &self.address)?;
+    // IPv4 is preferred when a hostname has both, as opentrack only listens on IPv4
+    pub fn resolve(&self) -> Result<SocketAddr> {
+        let addresses: Vec<SocketAddr> = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .with_context(|| format!("Unable to resolve {}", self))?
+            .collect();
 
-        Ok(())
+        addresses
+            .iter()
+            .find(|address| address.is_ipv4())
+            .or_else(|| addresses.first())
+            .copied()
+            .with_context(|| format!("No address found for {}", self))
+    }
+}
+
+impl std::fmt::Display for NetworkTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.host.contains(':') {
+            true => write!(f, "[{}]:{}", self.host, self.port),
+            false => write!(f, "{}:{}", self.host, self.port),
+        }
+    }
+}
+
+// Socket of the same address family as the receiver, on any local port
+pub fn bind_for(address: &SocketAddr) -> Result<UdpSocket> {
+    let local = match address {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    UdpSocket::bind(local).with_context(|| format!("Unable to bind socket for {}", address))
+}
+
+impl SocketNetwork {
+    pub fn new(
+        targets: &[NetworkTarget],
+        rotation_order: RotationOrder,
+        metrics: Arc<Metrics>,
+    ) -> Result<Self> {
+        let addresses = targets
+            .iter()
+            .map(NetworkTarget::resolve)
+            .collect::<Result<Vec<_>>>()?;
+        tracing::info!("Sending data to {:?}", addresses);
+        metrics.receivers.store(addresses.len(), Ordering::Relaxed);
+
+        let socket_v4 = match addresses.iter().find(|address| address.is_ipv4()) {
+            Some(address) => Some(bind_for(address)?),
+            None => None,
+        };
+        let socket_v6 = match addresses.iter().find(|address| address.is_ipv6()) {
+            Some(address) => Some(bind_for(address)?),
+            None => None,
+        };
+
+        Ok(Self {
+            failing_since: vec![None; addresses.len()],
+            addresses,
+            socket_v4,
+            socket_v6,
+            rotation_order,
+            metrics,
+        })
+    }
+
+    // Opentrack expects the six values as f64 in the byte order of the machine, 48 bytes in total
+    pub fn send(&mut self, pose: Pose) -> Result<()> {
+        let data = pose.to_euler_data(self.rotation_order);
+        let out: Vec<u8> = data
+            .iter()
+            .flat_map(|value| (*value as f64).to_ne_bytes())
+            .collect();
+
+        // Send data to every receiver, one failing does not keep the others from getting the pose
+        let mut last_error = None;
+        for (address, failing_since) in self.addresses.iter().zip(&mut self.failing_since) {
+            let socket = match address {
+                SocketAddr::V4(_) => self.socket_v4.as_ref(),
+                SocketAddr::V6(_) => self.socket_v6.as_ref(),
+            };
+            let sent = socket
+                .context("No socket bound")
+                .and_then(|socket| Ok(socket.send_to(&out, address)?))
+                .with_context(|| format!("Unable to send data to {}", address));
+            match sent {
+                Ok(_) => {
+                    self.metrics.packets_sent.fetch_add(1, Ordering::Relaxed);
+                    if failing_since.take().is_some() {
+                        tracing::info!("Sending data to {} again", address);
+                    }
+                }
+                Err(e) => {
+                    self.metrics.send_errors.fetch_add(1, Ordering::Relaxed);
+                    if failing_since.is_none() {
+                        tracing::warn!("{:#}", e);
+                        *failing_since = Some(Instant::now());
+                    }
+                    last_error = Some(e);
+                }
+            };
+        }
+
+        // Only given up when no receiver got anything for a while
+        let given_up = self.failing_since.iter().all(|failing_since| {
+            failing_since.is_some_and(|since| since.elapsed() >= SEND_FAILURE_TIMEOUT)
+        });
+        match last_error {
+            Some(e) if given_up => Err(e.context(format!(
+                "No receiver could be reached for {}s",
+                SEND_FAILURE_TIMEOUT.as_secs()
+            ))),
+            _ => Ok(()),
+        }
     }
 }
 
 #[test]
 pub fn test_socket_network() -> Result<()> {
-    let mut socket_network = SocketNetwork::new("127.0.0.1".to_owned(), "4242".to_owned())?;
-    socket_network.send([1., 2., 3., 4., 5., 6.])?;
+    let mut socket_network = SocketNetwork::new(
+        &[NetworkTarget::parse("127.0.0.1", "4242")?],
+        RotationOrder::default(),
+        Arc::default(),
+    )?;
+    socket_network.send(Pose::default())?;
+
+    Ok(())
+}
+
+#[test]
+pub fn test_network_target_parse() -> Result<()> {
+    let target = NetworkTarget::parse(" 192.168.1.20 ", "4242")?;
+    assert_eq!(target.to_string(), "192.168.1.20:4242");
+    assert_eq!(
+        NetworkTarget::parse("[::1]", "4242")?.to_string(),
+        "[::1]:4242"
+    );
+    assert_eq!(NetworkTarget::parse("fe80::1", "1")?.host, "fe80::1");
+    assert_eq!(
+        NetworkTarget::parse("my-pc.local", "4242")?.host,
+        "my-pc.local"
+    );
+
+    assert!(NetworkTarget::parse("", "4242").is_err());
+    assert!(NetworkTarget::parse("localhost:4242", "4242").is_err());
+    assert!(NetworkTarget::parse("-pc", "4242").is_err());
+    assert!(NetworkTarget::parse("127.0.0.1", "0").is_err());
+    assert!(NetworkTarget::parse("127.0.0.1", "70000").is_err());
+
+    let targets = NetworkTarget::parse_list("10.0.0.2:4242, [::1]:5555,,")?;
+    assert_eq!(
+        targets,
+        [
+            NetworkTarget::parse("10.0.0.2", "4242")?,
+            NetworkTarget::parse("::1", "5555")?
+        ]
+    );
+    assert!(NetworkTarget::parse_address("::1:4242").is_err());
+    assert!(NetworkTarget::parse_address("10.0.0.2").is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn test_socket_network_multiple_targets() -> Result<()> {
+    let receivers = [
+        UdpSocket::bind("127.0.0.1:0")?,
+        UdpSocket::bind("127.0.0.1:0")?,
+    ];
+    let mut targets = receivers
+        .iter()
+        .map(|receiver| {
+            NetworkTarget::parse("127.0.0.1", &receiver.local_addr()?.port().to_string())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // IPv6 loopback may be disabled on the machine running the tests
+    let receiver_v6 = UdpSocket::bind("[::1]:0").ok();
+    if let Some(receiver) = &receiver_v6 {
+        targets.push(NetworkTarget::parse(
+            "::1",
+            &receiver.local_addr()?.port().to_string(),
+        )?);
+    }
+
+    let metrics = Arc::new(Metrics::default());
+    let mut socket_network =
+        SocketNetwork::new(&targets, RotationOrder::default(), metrics.clone())?;
+    socket_network.send(Pose::default())?;
+    assert_eq!(
+        metrics.packets_sent.load(Ordering::Relaxed),
+        targets.len() as u64
+    );
+
+    for receiver in receivers.iter().chain(receiver_v6.iter()) {
+        receiver.set_read_timeout(Some(std::time::Duration::from_secs(2)))?;
+        let mut buffer = [0; 128];
+        assert_eq!(receiver.recv(&mut buffer)?, 48);
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn test_socket_network_failing_target() -> Result<()> {
+    let receiver = UdpSocket::bind("127.0.0.1:0")?;
+    receiver.set_read_timeout(Some(std::time::Duration::from_secs(2)))?;
+    // Sending to the broadcast address is refused without SO_BROADCAST
+    let unreachable = NetworkTarget::parse("255.255.255.255", "4242")?;
+    let targets = [
+        unreachable.clone(),
+        NetworkTarget::parse("127.0.0.1", &receiver.local_addr()?.port().to_string())?,
+    ];
+
+    // The other receiver still gets the pose
+    let metrics = Arc::new(Metrics::default());
+    let mut socket_network =
+        SocketNetwork::new(&targets, RotationOrder::default(), metrics.clone())?;
+    socket_network.send(Pose::default())?;
+    assert_eq!(receiver.recv(&mut [0; 128])?, 48);
+    assert_eq!(metrics.packets_sent.load(Ordering::Relaxed), 1);
+    assert_eq!(metrics.send_errors.load(Ordering::Relaxed), 1);
+
+    // Only failing receivers stop the tracking, once they kept failing
+    let mut socket_network =
+        SocketNetwork::new(&[unreachable], RotationOrder::default(), Arc::default())?;
+    socket_network.send(Pose::default())?;
+    socket_network.failing_since[0] = Instant::now().checked_sub(SEND_FAILURE_TIMEOUT);
+    assert!(socket_network.send(Pose::default()).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn test_socket_network_packet() -> Result<()> {
+    let receiver = UdpSocket::bind("127.0.0.1:0")?;
+    receiver.set_read_timeout(Some(std::time::Duration::from_secs(2)))?;
+    let target = NetworkTarget::parse("127.0.0.1", &receiver.local_addr()?.port().to_string())?;
+
+    let pose = Pose::from_euler_data([1.5, -2., 30., 20., -10., 5.], RotationOrder::default());
+    let mut socket_network =
+        SocketNetwork::new(&[target], RotationOrder::default(), Arc::default())?;
+    socket_network.send(pose)?;
+
+    let mut buffer = [0; 128];
+    let received = receiver.recv(&mut buffer)?;
+    assert_eq!(received, 48);
+
+    let values: Vec<f64> = buffer[..received]
+        .chunks_exact(8)
+        .map(|bytes| f64::from_ne_bytes(bytes.try_into().unwrap()))
+        .collect();
+    for (value, expected) in values
+        .iter()
+        .zip(pose.to_euler_data(RotationOrder::default()))
+    {
+        assert!(
+            (value - expected as f64).abs() < 1e-4,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
 
     Ok(())
 }
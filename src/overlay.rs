@@ -0,0 +1,98 @@
+/// Debug overlay of the camera preview, the geometry of the tracking drawn over the frame by utils/visualize.rs
+use crate::{
+    enums::overlay_element::OverlayElement,
+    structs::overlay::{OverlayData, OverlaySettings},
+};
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            landmarks: true,
+            face_box: true,
+            roi_box: true,
+            camera_box: true,
+            axes: true,
+        }
+    }
+}
+
+impl OverlaySettings {
+    pub fn shows(&self, element: OverlayElement) -> bool {
+        match element {
+            OverlayElement::Landmarks => self.landmarks,
+            OverlayElement::FaceBox => self.face_box,
+            OverlayElement::RoiBox => self.roi_box,
+            OverlayElement::CameraBox => self.camera_box,
+            OverlayElement::Axes => self.axes,
+        }
+    }
+
+    pub fn set(&mut self, element: OverlayElement, value: bool) {
+        match element {
+            OverlayElement::Landmarks => self.landmarks = value,
+            OverlayElement::FaceBox => self.face_box = value,
+            OverlayElement::RoiBox => self.roi_box = value,
+            OverlayElement::CameraBox => self.camera_box = value,
+            OverlayElement::Axes => self.axes = value,
+        }
+    }
+}
+
+impl OverlayData {
+    // Ends of the x, y and z axes of the head, the y of the image goes down while the one of the model goes up
+    pub fn axes(&self, length: f32) -> [[f32; 2]; 3] {
+        let r = self.rotation;
+        [0, 1, 2].map(|i| {
+            [
+                self.origin[0] + length * r[0][i],
+                self.origin[1] - length * r[1][i],
+            ]
+        })
+    }
+
+    // Length of the axes, a third of the size of the face so they scale with the distance to the camera
+    pub fn axis_length(&self) -> f32 {
+        ((self.roi_box[2] - self.roi_box[0]).abs() / 3.).max(1.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_settings() {
+        let mut settings = OverlaySettings::default();
+        assert!(!settings.enabled);
+        assert!(OverlayElement::ALL
+            .iter()
+            .all(|element| settings.shows(*element)));
+
+        settings.set(OverlayElement::RoiBox, false);
+        assert!(!settings.roi_box);
+        assert!(!settings.shows(OverlayElement::RoiBox));
+        assert!(settings.shows(OverlayElement::FaceBox));
+    }
+
+    #[test]
+    fn test_overlay_axes() {
+        let data = OverlayData {
+            roi_box: [100., 100., 400., 400.],
+            rotation: [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+            origin: [250., 250.],
+            ..OverlayData::default()
+        };
+        assert_eq!(data.axis_length(), 100.);
+        // Looking at the camera, x goes right, y goes up and z points to the camera
+        assert_eq!(data.axes(100.), [[350., 250.], [250., 150.], [250., 250.]]);
+
+        // Turned a quarter around y, the x axis now points away from the camera
+        let data = OverlayData {
+            rotation: [[0., 0., 1.], [0., 1., 0.], [-1., 0., 0.]],
+            ..data
+        };
+        assert_eq!(data.axes(100.)[0], [250., 250.]);
+        assert_eq!(data.axes(100.)[2], [350., 250.]);
+    }
+}
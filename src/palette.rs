@@ -0,0 +1,75 @@
+/// Colors of the Custom theme, read from the config file and turned into the palette of the GUI by gui/style.rs
+use anyhow::{bail, Context, Result};
+
+use crate::structs::palette::ThemePalette;
+
+// Starts from the dark theme, so a custom palette only needs the colors to change
+impl Default for ThemePalette {
+    fn default() -> Self {
+        Self {
+            background: "#1a1c1f".to_string(),
+            text: "#d9d9d9".to_string(),
+            primary: "#4d73cc".to_string(),
+            success: "#409966".to_string(),
+            danger: "#d95959".to_string(),
+        }
+    }
+}
+
+// Red, green and blue from 0 to 1
+pub fn parse_color(color: &str) -> Result<[f32; 3]> {
+    let hex = color
+        .trim()
+        .strip_prefix('#')
+        .with_context(|| format!("{:?} has to start with #", color))?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("{:?} is not a #rrggbb color", color);
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32 / 255.;
+    Ok([channel(0), channel(2), channel(4)])
+}
+
+impl ThemePalette {
+    // Background, text, primary, success and danger
+    pub fn colors(&self) -> Result<[[f32; 3]; 5]> {
+        Ok([
+            parse_color(&self.background).context("background")?,
+            parse_color(&self.text).context("text")?,
+            parse_color(&self.primary).context("primary")?,
+            parse_color(&self.success).context("success")?,
+            parse_color(&self.danger).context("danger")?,
+        ])
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.colors().map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() -> Result<()> {
+        assert_eq!(parse_color("#ff0080")?, [1., 0., 128. / 255.]);
+        assert_eq!(parse_color(" #FFFFFF ")?, [1.; 3]);
+        for invalid in ["ff0080", "#ff008", "#ff00800", "#gg0080", "#"] {
+            assert!(parse_color(invalid).is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ThemePalette::default().validate().is_ok());
+
+        let palette = ThemePalette {
+            danger: "red".to_string(),
+            ..ThemePalette::default()
+        };
+        let error = palette.validate().unwrap_err();
+        assert_eq!(error.to_string(), "danger");
+    }
+}
@@ -0,0 +1,397 @@
+/// Import and export of the settings as a portable file, either a single profile or every profile at once
+/// Imported files go through the same migrations and validation as the config file itself
+use crate::{
+    config::parse_table,
+    enums::config_format::ConfigFormat,
+    structs::{
+        portable::{ConfigBundle, FieldChange, ImportedConfig, ProfileChanges},
+        profile::ProfileStore,
+        state::AppConfig,
+    },
+};
+use anyhow::{bail, Context, Result};
+use std::{collections::BTreeMap, fs, path::Path};
+use toml::{value::Table, Value};
+
+impl ConfigFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Json => "json",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+
+        match extension.as_deref() {
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some("json") => Ok(ConfigFormat::Json),
+            _ => bail!("{:?} has to be a .toml or .json file", path),
+        }
+    }
+}
+
+fn serialize<T: serde::Serialize>(value: &T, format: ConfigFormat) -> Result<String> {
+    Ok(match format {
+        ConfigFormat::Toml => toml::to_string_pretty(value)?,
+        ConfigFormat::Json => serde_json::to_string_pretty(value)?,
+    })
+}
+
+pub fn export_profile(config: &AppConfig, format: ConfigFormat) -> Result<String> {
+    serialize(config, format)
+}
+
+pub fn export_bundle(bundle: &ConfigBundle, format: ConfigFormat) -> Result<String> {
+    serialize(bundle, format)
+}
+
+// Format taken from the extension of the file
+pub fn export_file(
+    path: &Path,
+    contents: impl FnOnce(ConfigFormat) -> Result<String>,
+) -> Result<()> {
+    let contents = contents(ConfigFormat::from_path(path)?)?;
+    fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))
+}
+
+// Migrated like a config file and rejected as a whole if any value is invalid
+fn read_profile(table: Table) -> Result<AppConfig> {
+    let (config, _) = parse_table(table)?;
+
+    let errors = config.validate();
+    if !errors.is_empty() {
+        let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        bail!("Invalid settings - {}", errors.join(", "));
+    }
+    Ok(config)
+}
+
+pub fn read_import(contents: &str, format: ConfigFormat) -> Result<ImportedConfig> {
+    let value: Value = match format {
+        ConfigFormat::Toml => toml::from_str(contents).context("The file is not valid TOML")?,
+        ConfigFormat::Json => {
+            let json: serde_json::Value =
+                serde_json::from_str(contents).context("The file is not valid JSON")?;
+            Value::try_from(json).context("The file can not be converted to a config")?
+        }
+    };
+    let mut table = match value {
+        Value::Table(table) => table,
+        _ => bail!("The file does not contain a config"),
+    };
+
+    // A bundle has every profile in its profiles table, a profile is a config file on its own
+    let profiles = match table.remove("profiles") {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => bail!("profiles has to be a table of profiles"),
+        None => return Ok(ImportedConfig::Profile(Box::new(read_profile(table)?))),
+    };
+
+    let mut bundle = ConfigBundle {
+        active: match table.remove("active") {
+            Some(Value::String(active)) => active,
+            _ => bail!("The bundle has no active profile"),
+        },
+        profiles: BTreeMap::new(),
+    };
+    for (name, profile) in profiles {
+        ProfileStore::validate_name(&name)?;
+        let profile = match profile {
+            Value::Table(profile) => profile,
+            _ => bail!("Profile {:?} is not a table", name),
+        };
+        let profile =
+            read_profile(profile).with_context(|| format!("Invalid profile {:?}", name))?;
+        bundle.profiles.insert(name, profile);
+    }
+    if !bundle.profiles.contains_key(&bundle.active) {
+        bail!(
+            "The active profile {:?} is not in the bundle",
+            bundle.active
+        );
+    }
+
+    Ok(ImportedConfig::Bundle(bundle))
+}
+
+pub fn import_file(path: &Path) -> Result<ImportedConfig> {
+    let format = ConfigFormat::from_path(path)?;
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+    read_import(&contents, format).with_context(|| format!("Unable to import {:?}", path))
+}
+
+// Every leaf value under a dotted path, eg. hotkeys.recenter or axis_filters[3].beta
+fn flatten(prefix: String, value: Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                let path = match prefix.is_empty() {
+                    true => key,
+                    false => format!("{}.{}", prefix, key),
+                };
+                flatten(path, value, fields);
+            }
+        }
+        Value::Array(array) => {
+            for (i, value) in array.into_iter().enumerate() {
+                flatten(format!("{}[{}]", prefix, i), value, fields);
+            }
+        }
+        value => {
+            fields.insert(prefix, value.to_string());
+        }
+    }
+}
+
+pub fn diff(current: &AppConfig, imported: &AppConfig) -> Result<Vec<FieldChange>> {
+    let mut current_fields = BTreeMap::new();
+    flatten(
+        String::new(),
+        Value::try_from(current)?,
+        &mut current_fields,
+    );
+    let mut imported_fields = BTreeMap::new();
+    flatten(
+        String::new(),
+        Value::try_from(imported)?,
+        &mut imported_fields,
+    );
+
+    Ok(imported_fields
+        .into_iter()
+        .filter_map(|(field, imported)| {
+            let current = current_fields.remove(&field).unwrap_or_default();
+            (current != imported).then_some(FieldChange {
+                field,
+                current,
+                imported,
+            })
+        })
+        .collect())
+}
+
+impl std::fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.current, self.imported)
+    }
+}
+
+impl ProfileStore {
+    pub fn bundle(&self, active: &str) -> Result<ConfigBundle> {
+        let profiles = self
+            .list()
+            .into_iter()
+            .map(|name| self.load(&name).map(|config| (name, config)))
+            .collect::<Result<_>>()?;
+
+        Ok(ConfigBundle {
+            active: active.to_string(),
+            profiles,
+        })
+    }
+
+    // What an import would change, a single profile replaces the target profile
+    pub fn import_changes(
+        &self,
+        imported: &ImportedConfig,
+        target: &str,
+    ) -> Result<Vec<ProfileChanges>> {
+        let profiles: Vec<(&str, &AppConfig)> = match imported {
+            ImportedConfig::Profile(config) => vec![(target, config.as_ref())],
+            ImportedConfig::Bundle(bundle) => bundle
+                .profiles
+                .iter()
+                .map(|(name, config)| (name.as_str(), config))
+                .collect(),
+        };
+
+        profiles
+            .into_iter()
+            .map(|(name, config)| {
+                let (current, created) = match self.exists(name) {
+                    true => (self.load(name)?, false),
+                    false => (AppConfig::default(), true),
+                };
+                Ok(ProfileChanges {
+                    profile: name.to_string(),
+                    created,
+                    changes: diff(&current, config)?,
+                })
+            })
+            .collect()
+    }
+
+    // Profiles of a bundle are created or replaced, the other profiles are kept
+    // Once every profile is saved, the active profile of the bundle becomes the active one
+    pub fn import(&self, imported: &ImportedConfig, target: &str) -> Result<()> {
+        match imported {
+            ImportedConfig::Profile(config) => {
+                Self::validate_name(target)?;
+                self.save(target, config)
+            }
+            ImportedConfig::Bundle(bundle) => {
+                for (name, config) in &bundle.profiles {
+                    self.save(name, config)?;
+                }
+                self.set_active(&bundle.active)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ProfileChanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self.created, self.changes.is_empty()) {
+            (true, _) => writeln!(f, "Profile {:?} (new)", self.profile)?,
+            (false, true) => return writeln!(f, "Profile {:?}: no changes", self.profile),
+            (false, false) => writeln!(f, "Profile {:?}", self.profile)?,
+        }
+        for change in &self.changes {
+            writeln!(f, "  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::{CONFIG_VERSION, DEFAULT_PROFILE};
+
+    fn custom_config() -> AppConfig {
+        let mut config = AppConfig {
+            fps: 90,
+            ip: "192.168.1.20".to_string(),
+            ..AppConfig::default()
+        };
+        config.axis_filters[3].beta = 0.5;
+        config.hotkeys.recenter = "Ctrl+F12".to_string();
+        config
+    }
+
+    fn temp_store(name: &str) -> ProfileStore {
+        let config_dir = std::env::temp_dir().join(format!(
+            "stableview-portable-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&config_dir);
+        ProfileStore { config_dir }
+    }
+
+    #[test]
+    fn test_profile_round_trip() -> Result<()> {
+        let config = custom_config();
+        for format in ConfigFormat::ALL {
+            let exported = export_profile(&config, format)?;
+            match read_import(&exported, format)? {
+                ImportedConfig::Profile(imported) => assert_eq!(*imported, config),
+                ImportedConfig::Bundle(_) => panic!("Imported a profile as a bundle"),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_round_trip() -> Result<()> {
+        let bundle = ConfigBundle {
+            active: "DCS World".to_string(),
+            profiles: BTreeMap::from([
+                (DEFAULT_PROFILE.to_string(), AppConfig::default()),
+                ("DCS World".to_string(), custom_config()),
+            ]),
+        };
+        for format in ConfigFormat::ALL {
+            let exported = export_bundle(&bundle, format)?;
+            match read_import(&exported, format)? {
+                ImportedConfig::Bundle(imported) => {
+                    assert_eq!(imported.active, bundle.active);
+                    assert_eq!(imported.profiles, bundle.profiles);
+                }
+                ImportedConfig::Profile(_) => panic!("Imported a bundle as a profile"),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_migrates_and_validates() -> Result<()> {
+        // Unversioned file of StableView 1.0 is migrated
+        match read_import(
+            r#"{"ip": "10.0.0.2", "min_cutoff": 0.0025}"#,
+            ConfigFormat::Json,
+        )? {
+            ImportedConfig::Profile(config) => {
+                assert_eq!(config.version, CONFIG_VERSION);
+                assert_eq!(config.ip, "10.0.0.2");
+                assert!((config.axis_filters[0].min_cutoff - 0.15).abs() < 1e-6);
+            }
+            ImportedConfig::Bundle(_) => panic!("Imported a profile as a bundle"),
+        }
+
+        // Invalid values are rejected instead of being reset
+        assert!(read_import("fps = 500", ConfigFormat::Toml).is_err());
+        assert!(read_import("[1, 2]", ConfigFormat::Json).is_err());
+        assert!(read_import(
+            "active = \"Missing\"\n[profiles.Default]\nfps = 30",
+            ConfigFormat::Toml
+        )
+        .is_err());
+        assert!(read_import("active = \"../x\"\n[profiles.\"../x\"]", ConfigFormat::Toml).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff() -> Result<()> {
+        let fields: Vec<String> = diff(&AppConfig::default(), &custom_config())?
+            .into_iter()
+            .map(|change| change.field)
+            .collect();
+        assert_eq!(
+            fields,
+            ["axis_filters[3].beta", "fps", "hotkeys.recenter", "ip"]
+        );
+        assert!(diff(&custom_config(), &custom_config())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_into_store() -> Result<()> {
+        let store = temp_store("import");
+        store.save(DEFAULT_PROFILE, &AppConfig::default())?;
+
+        let imported = ImportedConfig::Bundle(ConfigBundle {
+            active: "Xplane".to_string(),
+            profiles: BTreeMap::from([
+                (DEFAULT_PROFILE.to_string(), custom_config()),
+                ("Xplane".to_string(), AppConfig::default()),
+            ]),
+        });
+        let changes = store.import_changes(&imported, DEFAULT_PROFILE)?;
+        assert_eq!(changes.len(), 2);
+        assert!(!changes[0].created && changes[0].changes.len() == 4);
+        assert!(changes[1].created && changes[1].changes.is_empty());
+
+        store.import(&imported, DEFAULT_PROFILE)?;
+        assert_eq!(store.load(DEFAULT_PROFILE)?, custom_config());
+        assert!(store.exists("Xplane"));
+        assert_eq!(store.active(), "Xplane");
+
+        // A single profile does not switch the profile in use
+        store.import(
+            &ImportedConfig::Profile(Box::new(custom_config())),
+            "DCS World",
+        )?;
+        assert_eq!(store.load("DCS World")?, custom_config());
+        assert_eq!(store.active(), "Xplane");
+
+        fs::remove_dir_all(&store.config_dir)?;
+        Ok(())
+    }
+}
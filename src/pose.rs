@@ -0,0 +1,431 @@
+/// Quaternion based representation of the head pose, euler angles are only computed by the sinks
+/// Euler angle extraction - https://www.geometrictools.com/Documentation/EulerAngles.pdf
+use crate::{
+    enums::rotation_order::RotationOrder,
+    structs::pose::{Pose, Quaternion},
+    utils::headpose::matrix2quaternion,
+};
+
+// Below this angle (in radians), the small angle approximation is used for log/exp
+const SMALL_ANGLE: f32 = 1e-6;
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl std::ops::Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        Quaternion {
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        }
+    }
+}
+
+impl Quaternion {
+    pub const IDENTITY: Quaternion = Quaternion {
+        x: 0.,
+        y: 0.,
+        z: 0.,
+        w: 1.,
+    };
+
+    pub fn from_rotation_matrix(r: &[[f32; 3]; 3]) -> Self {
+        let [x, y, z, w] = matrix2quaternion(r);
+        Self { x, y, z, w }
+    }
+
+    pub fn to_rotation_matrix(self) -> [[f32; 3]; 3] {
+        let Quaternion { x, y, z, w } = self;
+        [
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - z * w),
+                2. * (x * z + y * w),
+            ],
+            [
+                2. * (x * y + z * w),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - x * w),
+            ],
+            [
+                2. * (x * z - y * w),
+                2. * (y * z + x * w),
+                1. - 2. * (x * x + y * y),
+            ],
+        ]
+    }
+
+    pub fn conjugate(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: self.w,
+        }
+    }
+
+    pub fn dot(self, other: Quaternion) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn normalize(self) -> Self {
+        let norm = self.dot(self).sqrt();
+        if norm <= f32::EPSILON {
+            return Self::IDENTITY;
+        }
+        Self {
+            x: self.x / norm,
+            y: self.y / norm,
+            z: self.z / norm,
+            w: self.w / norm,
+        }
+    }
+
+    // Rotation vector (axis * angle in radians) of the quaternion
+    pub fn log(self) -> [f32; 3] {
+        // q and -q are the same rotation, using the one with positive w gives the shortest rotation
+        let q = if self.w < 0. {
+            Quaternion {
+                x: -self.x,
+                y: -self.y,
+                z: -self.z,
+                w: -self.w,
+            }
+        } else {
+            self
+        };
+
+        let sin_half = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        let scale = if sin_half < SMALL_ANGLE {
+            2. / q.w
+        } else {
+            2. * sin_half.atan2(q.w) / sin_half
+        };
+
+        [q.x * scale, q.y * scale, q.z * scale]
+    }
+
+    // Inverse of log, building the quaternion from a rotation vector
+    pub fn exp(v: [f32; 3]) -> Self {
+        let angle = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        if angle < SMALL_ANGLE {
+            return Quaternion {
+                x: v[0] / 2.,
+                y: v[1] / 2.,
+                z: v[2] / 2.,
+                w: 1.,
+            }
+            .normalize();
+        }
+
+        let (sin_half, cos_half) = (angle / 2.).sin_cos();
+        Quaternion {
+            x: v[0] / angle * sin_half,
+            y: v[1] / angle * sin_half,
+            z: v[2] / angle * sin_half,
+            w: cos_half,
+        }
+    }
+
+    // Spherical linear interpolation, t = 0 returns self and t = 1 returns other
+    pub fn slerp(self, other: Quaternion, t: f32) -> Self {
+        let delta = (self.conjugate() * other).log();
+        (self * Quaternion::exp([delta[0] * t, delta[1] * t, delta[2] * t])).normalize()
+    }
+
+    // Inverse of to_euler, composing the yaw, pitch and roll given in degrees in the given order
+    pub fn from_euler(angles: [f32; 3], order: RotationOrder) -> Self {
+        let rotation = |axis: usize, degrees: f32| {
+            let mut v = [0.; 3];
+            v[axis] = degrees.to_radians();
+            Quaternion::exp(v)
+        };
+        let yaw = rotation(1, angles[0]);
+        let pitch = rotation(0, angles[1]);
+        let roll = rotation(2, angles[2]);
+
+        match order {
+            RotationOrder::YawPitchRoll => yaw * pitch * roll,
+            RotationOrder::YawRollPitch => yaw * roll * pitch,
+            RotationOrder::PitchYawRoll => pitch * yaw * roll,
+            RotationOrder::PitchRollYaw => pitch * roll * yaw,
+            RotationOrder::RollYawPitch => roll * yaw * pitch,
+            RotationOrder::RollPitchYaw => roll * pitch * yaw,
+        }
+        .normalize()
+    }
+
+    // Returns yaw, pitch and roll in degrees, decomposing the rotation in the given order
+    pub fn to_euler(self, order: RotationOrder) -> [f32; 3] {
+        const YAW: usize = 1;
+        const PITCH: usize = 0;
+        const ROLL: usize = 2;
+
+        let (a, b, c) = match order {
+            RotationOrder::YawPitchRoll => (YAW, PITCH, ROLL),
+            RotationOrder::YawRollPitch => (YAW, ROLL, PITCH),
+            RotationOrder::PitchYawRoll => (PITCH, YAW, ROLL),
+            RotationOrder::PitchRollYaw => (PITCH, ROLL, YAW),
+            RotationOrder::RollYawPitch => (ROLL, YAW, PITCH),
+            RotationOrder::RollPitchYaw => (ROLL, PITCH, YAW),
+        };
+
+        // Sign of the permutation of the axes, cyclic orders (XYZ, YZX, ZXY) are positive
+        let sign = if (b + 3 - a) % 3 == 1 { 1. } else { -1. };
+
+        let r = self.to_rotation_matrix();
+        let mut angles = [0.; 3];
+
+        let sin_b = (sign * r[a][c]).clamp(-1., 1.);
+        angles[b] = sin_b.asin();
+        if sin_b.abs() < 0.999_999 {
+            angles[a] = (-sign * r[b][c]).atan2(r[c][c]);
+            angles[c] = (-sign * r[a][b]).atan2(r[a][a]);
+        } else {
+            // Gimbal lock, only the sum/difference of the first and last angle is defined
+            angles[a] = (sign * r[c][b]).atan2(r[b][b]);
+            angles[c] = 0.;
+        }
+
+        [
+            angles[YAW].to_degrees(),
+            angles[PITCH].to_degrees(),
+            angles[ROLL].to_degrees(),
+        ]
+    }
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self {
+            translation: [0.; 3],
+            rotation: Quaternion::IDENTITY,
+        }
+    }
+}
+
+impl Pose {
+    // Converts the pose into x, y, depth, yaw, pitch, roll as expected by the sinks
+    pub fn to_euler_data(self, order: RotationOrder) -> [f32; 6] {
+        let [yaw, pitch, roll] = self.rotation.to_euler(order);
+        [
+            self.translation[0],
+            self.translation[1],
+            self.translation[2],
+            yaw,
+            pitch,
+            roll,
+        ]
+    }
+
+    // Inverse of to_euler_data
+    pub fn from_euler_data(data: [f32; 6], order: RotationOrder) -> Pose {
+        Pose {
+            translation: [data[0], data[1], data[2]],
+            rotation: Quaternion::from_euler([data[3], data[4], data[5]], order),
+        }
+    }
+
+    // Pose relative to the center set when recentering
+    pub fn relative_to(self, center: Pose) -> Pose {
+        Pose {
+            translation: [
+                self.translation[0] - center.translation[0],
+                self.translation[1] - center.translation[1],
+                self.translation[2] - center.translation[2],
+            ],
+            rotation: (center.rotation.conjugate() * self.rotation).normalize(),
+        }
+    }
+
+    // Angle in degrees of the rotation from one pose to the other
+    pub fn angle_to(self, other: Pose) -> f32 {
+        let [x, y, z] = (self.rotation.conjugate() * other.rotation).log();
+        (x * x + y * y + z * z).sqrt().to_degrees()
+    }
+
+    // Distance between the positions of the two poses
+    pub fn distance_to(self, other: Pose) -> f32 {
+        self.translation
+            .iter()
+            .zip(other.translation)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    // A broken fit gives NaN or infinite values
+    pub fn is_finite(self) -> bool {
+        let q = self.rotation;
+        self.translation
+            .iter()
+            .chain(&[q.x, q.y, q.z, q.w])
+            .all(|value| value.is_finite())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotation(axis: usize, degrees: f32) -> Quaternion {
+        let mut v = [0.; 3];
+        v[axis] = degrees.to_radians();
+        Quaternion::exp(v)
+    }
+
+    fn assert_close(a: &[f32], b: &[f32], tolerance: f32) {
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < tolerance, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_log_exp() {
+        let v = [0.3, -0.2, 0.1];
+        assert_close(&Quaternion::exp(v).log(), &v, 1e-6);
+        assert_eq!(Quaternion::IDENTITY.log(), [0.; 3]);
+
+        // q and -q represent the same rotation
+        let q = Quaternion::exp(v);
+        let negated = Quaternion {
+            x: -q.x,
+            y: -q.y,
+            z: -q.z,
+            w: -q.w,
+        };
+        assert_close(&negated.log(), &v, 1e-6);
+    }
+
+    #[test]
+    fn test_rotation_matrix() {
+        let q = Quaternion::exp([0.3, -0.2, 0.1]);
+        let back = Quaternion::from_rotation_matrix(&q.to_rotation_matrix());
+        assert_close(
+            &[back.x, back.y, back.z, back.w],
+            &[q.x, q.y, q.z, q.w],
+            1e-6,
+        );
+    }
+
+    #[test]
+    fn test_slerp() {
+        let a = rotation(1, 10.);
+        let b = rotation(1, 50.);
+        assert_close(&a.slerp(b, 0.5).log(), &rotation(1, 30.).log(), 1e-6);
+        assert_close(&a.slerp(b, 0.).log(), &a.log(), 1e-6);
+        assert_close(&a.slerp(b, 1.).log(), &b.log(), 1e-6);
+    }
+
+    #[test]
+    fn test_to_euler() {
+        let (yaw, pitch, roll) = (rotation(1, 30.), rotation(0, -20.), rotation(2, 10.));
+
+        let orders = [
+            (RotationOrder::YawPitchRoll, yaw * pitch * roll),
+            (RotationOrder::YawRollPitch, yaw * roll * pitch),
+            (RotationOrder::PitchYawRoll, pitch * yaw * roll),
+            (RotationOrder::PitchRollYaw, pitch * roll * yaw),
+            (RotationOrder::RollYawPitch, roll * yaw * pitch),
+            (RotationOrder::RollPitchYaw, roll * pitch * yaw),
+        ];
+
+        for (order, q) in orders {
+            assert_close(&q.to_euler(order), &[30., -20., 10.], 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_from_euler() {
+        for order in RotationOrder::ALL {
+            let data = [1., -2., 3., 30., -20., 10.];
+            let pose = Pose::from_euler_data(data, order);
+            assert_close(&pose.to_euler_data(order), &data, 1e-3);
+        }
+        assert_close(
+            &Quaternion::from_euler([0.; 3], RotationOrder::default()).log(),
+            &[0.; 3],
+            1e-6,
+        );
+    }
+
+    #[test]
+    fn test_to_euler_gimbal_lock() {
+        let q = rotation(1, 25.) * rotation(0, 90.);
+        let [yaw, pitch, roll] = q.to_euler(RotationOrder::YawPitchRoll);
+
+        assert!((yaw - 25.).abs() < 1e-2);
+        assert!((pitch - 90.).abs() < 1e-2);
+        assert_eq!(roll, 0.);
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let center = Pose {
+            translation: [1., 2., 3.],
+            rotation: rotation(1, 20.),
+        };
+        let pose = Pose {
+            translation: [2., 2., 5.],
+            rotation: rotation(1, 50.),
+        };
+
+        let relative = pose.relative_to(center);
+        assert_close(&relative.translation, &[1., 0., 2.], 1e-6);
+        assert_close(&relative.rotation.log(), &rotation(1, 30.).log(), 1e-6);
+
+        // Recentering on the pose itself gives the neutral pose
+        let neutral = pose.relative_to(pose);
+        assert_close(&neutral.translation, &[0.; 3], 1e-6);
+        assert_close(&neutral.rotation.log(), &[0.; 3], 1e-6);
+
+        assert!((pose.angle_to(center) - 30.).abs() < 1e-3);
+        assert!((pose.distance_to(center) - 5_f32.sqrt()).abs() < 1e-6);
+
+        assert!(pose.is_finite());
+        let mut broken = pose;
+        broken.rotation.w = f32::NAN;
+        assert!(!broken.is_finite());
+    }
+
+    #[test]
+    fn test_matches_legacy_euler_angles() {
+        use crate::utils::headpose::calc_pose;
+
+        let mut param = [0.; 62];
+        let r = rotation(1, 30.) * rotation(0, 15.) * rotation(2, -5.);
+        let r = r.to_rotation_matrix();
+        for i in 0..3 {
+            param[i * 4..i * 4 + 3].copy_from_slice(&r[i]);
+        }
+
+        let (p, pose) = calc_pose(&param);
+        let r = [
+            [p[0][0], p[0][1], p[0][2]],
+            [p[1][0], p[1][1], p[1][2]],
+            [p[2][0], p[2][1], p[2][2]],
+        ];
+        let q = Quaternion::from_rotation_matrix(&r);
+
+        // Same change of frame as done in ProcessHeadPose::single_iter
+        let q = Quaternion {
+            x: -q.x,
+            y: -q.y,
+            z: q.z,
+            w: q.w,
+        };
+
+        assert_close(
+            &q.to_euler(RotationOrder::default()),
+            &[pose[0], -pose[1], pose[2]],
+            1e-3,
+        );
+    }
+}
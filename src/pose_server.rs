@@ -0,0 +1,586 @@
+/// Pose server, so local applications (overlays, loggers, avatar apps) can subscribe to the filtered poses
+/// TCP and WebSocket clients connect to the same localhost port, UDP clients send their subscription as a datagram to it
+use crate::{
+    enums::pose_format::PoseFormat,
+    structs::{
+        broadcast::{PoseBroadcaster, PoseSample},
+        pose_server::{
+            PoseFrame, PoseServer, PoseSubscription, RateLimiter, TrackingStatus, UdpClient,
+        },
+    },
+};
+use anyhow::{bail, Context, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::{atomic::Ordering, Arc, Mutex},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+    Message,
+};
+
+// Status frame sent when no pose came for this long, eg. while the tracking is stopped
+const HEARTBEAT: Duration = Duration::from_secs(1);
+
+// UDP clients have to send their subscription again before it expires
+const UDP_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Magic, sequence, unix_time_ms, flags, timestamp, x, y, z, yaw, pitch, roll, the rotation quaternion and the
+// tracking quality
+pub const BINARY_FRAME_SIZE: usize = 72;
+const BINARY_MAGIC: [u8; 4] = *b"SVP2";
+const FLAG_RUNNING: u32 = 1;
+const FLAG_OUTPUT_PAUSED: u32 = 1 << 1;
+const FLAG_POSE: u32 = 1 << 2;
+
+impl RateLimiter {
+    // Rate asked by the client, never above the maximum of the server
+    pub fn new(rate: Option<f32>, max_rate: u32) -> Self {
+        let max_rate = max_rate.max(1) as f32;
+        let rate = rate
+            .filter(|rate| rate.is_finite() && *rate > 0.)
+            .map_or(max_rate, |rate| rate.min(max_rate));
+
+        Self {
+            interval: Duration::from_secs_f32(1. / rate),
+            last_sent: None,
+        }
+    }
+
+    // A tenth of tolerance, so a client asking for the tracking rate gets every pose despite the jitter
+    pub fn ready(&mut self, now: Instant) -> bool {
+        match self.last_sent {
+            Some(last_sent) if now.duration_since(last_sent) < self.interval.mul_f32(0.9) => false,
+            _ => {
+                self.last_sent = Some(now);
+                true
+            }
+        }
+    }
+}
+
+impl PoseSubscription {
+    // Empty line for the defaults, or a JSON object
+    pub fn parse(line: &str) -> Result<Self> {
+        match line.trim() {
+            "" => Ok(Self::default()),
+            line => serde_json::from_str(line).context("Invalid subscription"),
+        }
+    }
+
+    // WebSocket clients give it in the URL, eg. ws://127.0.0.1:4244/?format=binary&rate=30
+    pub fn from_query(query: &str) -> Result<Self> {
+        let mut subscription = Self::default();
+        for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+            match parameter.split_once('=') {
+                Some(("format", "json")) => subscription.format = PoseFormat::Json,
+                Some(("format", "binary")) => subscription.format = PoseFormat::Binary,
+                Some(("rate", rate)) => {
+                    subscription.rate = Some(
+                        rate.parse()
+                            .with_context(|| format!("Invalid rate {:?}", rate))?,
+                    )
+                }
+                _ => bail!("Unknown parameter {:?}", parameter),
+            }
+        }
+        Ok(subscription)
+    }
+}
+
+impl PoseFrame {
+    pub fn new(sequence: u64, status: &TrackingStatus, pose: Option<PoseSample>) -> Self {
+        Self {
+            sequence,
+            unix_time_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis() as u64),
+            running: status.running.load(Ordering::SeqCst),
+            output_paused: status.output_paused.load(Ordering::SeqCst),
+            pose,
+        }
+    }
+
+    // Little endian, the pose values are zeros when the frame has no pose
+    pub fn to_bytes(self) -> [u8; BINARY_FRAME_SIZE] {
+        let mut flags = 0;
+        if self.running {
+            flags |= FLAG_RUNNING;
+        }
+        if self.output_paused {
+            flags |= FLAG_OUTPUT_PAUSED;
+        }
+        if self.pose.is_some() {
+            flags |= FLAG_POSE;
+        }
+
+        let pose = self.pose.unwrap_or(PoseSample {
+            timestamp: 0.,
+            x: 0.,
+            y: 0.,
+            z: 0.,
+            yaw: 0.,
+            pitch: 0.,
+            roll: 0.,
+            rotation: [0.; 4],
+            quality: 0.,
+        });
+        let values = [
+            pose.timestamp,
+            pose.x,
+            pose.y,
+            pose.z,
+            pose.yaw,
+            pose.pitch,
+            pose.roll,
+        ]
+        .into_iter()
+        .chain(pose.rotation)
+        .chain([pose.quality]);
+
+        let mut bytes = [0; BINARY_FRAME_SIZE];
+        bytes[..4].copy_from_slice(&BINARY_MAGIC);
+        bytes[4..12].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[12..20].copy_from_slice(&self.unix_time_ms.to_le_bytes());
+        bytes[20..24].copy_from_slice(&flags.to_le_bytes());
+        for (i, value) in values.enumerate() {
+            bytes[24 + i * 4..28 + i * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != BINARY_FRAME_SIZE || bytes[..4] != BINARY_MAGIC {
+            bail!("Not a pose frame");
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        let f32_at =
+            |i: usize| f32::from_le_bytes(bytes[24 + i * 4..28 + i * 4].try_into().unwrap());
+        let flags = u32::from_le_bytes(bytes[20..24].try_into().unwrap());
+
+        Ok(Self {
+            sequence: u64_at(4),
+            unix_time_ms: u64_at(12),
+            running: flags & FLAG_RUNNING != 0,
+            output_paused: flags & FLAG_OUTPUT_PAUSED != 0,
+            pose: (flags & FLAG_POSE != 0).then(|| PoseSample {
+                timestamp: f32_at(0),
+                x: f32_at(1),
+                y: f32_at(2),
+                z: f32_at(3),
+                yaw: f32_at(4),
+                pitch: f32_at(5),
+                roll: f32_at(6),
+                rotation: [f32_at(7), f32_at(8), f32_at(9), f32_at(10)],
+                quality: f32_at(11),
+            }),
+        })
+    }
+
+    pub fn encode(&self, format: PoseFormat) -> Result<Vec<u8>> {
+        Ok(match format {
+            PoseFormat::Json => serde_json::to_vec(self)?,
+            PoseFormat::Binary => self.to_bytes().to_vec(),
+        })
+    }
+}
+
+impl PoseServer {
+    pub fn start(
+        port: u16,
+        max_rate: u32,
+        poses: PoseBroadcaster,
+        status: TrackingStatus,
+    ) -> Result<Self> {
+        // Only reachable from this computer
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("Unable to bind pose server on TCP port {}", port))?;
+        let udp_socket = UdpSocket::bind(("127.0.0.1", port))
+            .with_context(|| format!("Unable to bind pose server on UDP port {}", port))?;
+        let tcp_address = listener.local_addr()?;
+        let udp_address = udp_socket.local_addr()?;
+        tracing::info!(
+            "Pose server listening on {} and {}",
+            tcp_address,
+            udp_address
+        );
+
+        {
+            let poses = poses.clone();
+            let status = status.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let poses = poses.clone();
+                    let status = status.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = handle_tcp(stream, max_rate, &poses, &status) {
+                            tracing::info!("Pose connection closed: {}", e);
+                        }
+                    });
+                }
+            });
+        }
+
+        serve_udp(udp_socket, max_rate, poses.subscribe(), status)?;
+
+        Ok(Self {
+            tcp_address,
+            udp_address,
+        })
+    }
+}
+
+// Sends the poses at the rate of the client and a heartbeat without pose when there are none, until send fails
+fn stream_frames(
+    poses: &Receiver<PoseSample>,
+    status: &TrackingStatus,
+    mut limiter: RateLimiter,
+    mut send: impl FnMut(&PoseFrame) -> Result<()>,
+) -> Result<()> {
+    let mut sequence = 0;
+    loop {
+        let pose = match poses.recv_timeout(HEARTBEAT) {
+            // Only the latest pose matters when the client is slower than the tracking
+            Ok(sample) => Some(poses.try_iter().last().unwrap_or(sample)),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        if pose.is_some() && !limiter.ready(Instant::now()) {
+            continue;
+        }
+
+        send(&PoseFrame::new(sequence, status, pose))?;
+        sequence += 1;
+    }
+}
+
+// The first line is the subscription, then the frames are streamed as JSON lines or binary frames
+fn handle_tcp(
+    mut stream: TcpStream,
+    max_rate: u32,
+    poses: &PoseBroadcaster,
+    status: &TrackingStatus,
+) -> Result<()> {
+    let mut start = [0; 4];
+    let peeked = stream.peek(&mut start)?;
+    if start[..peeked] == *b"GET " {
+        return handle_websocket(stream, max_rate, poses, status);
+    }
+
+    let mut line = String::new();
+    BufReader::new(stream.try_clone()?).read_line(&mut line)?;
+    let subscription = match PoseSubscription::parse(&line) {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            writeln!(
+                stream,
+                "{}",
+                serde_json::json!({ "error": format!("{:#}", e) })
+            )?;
+            return Err(e);
+        }
+    };
+
+    let limiter = RateLimiter::new(subscription.rate, max_rate);
+    stream_frames(&poses.subscribe(), status, limiter, |frame| {
+        let mut bytes = frame.encode(subscription.format)?;
+        if subscription.format == PoseFormat::Json {
+            bytes.push(b'\n');
+        }
+        stream.write_all(&bytes)?;
+        Ok(())
+    })
+}
+
+// JSON frames as text messages, binary frames as binary messages
+// The error response type is imposed by tungstenite
+#[allow(clippy::result_large_err)]
+fn handle_websocket(
+    stream: TcpStream,
+    max_rate: u32,
+    poses: &PoseBroadcaster,
+    status: &TrackingStatus,
+) -> Result<()> {
+    let mut subscription = PoseSubscription::default();
+    let mut websocket = tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+        match PoseSubscription::from_query(request.uri().query().unwrap_or_default()) {
+            Ok(parsed) => {
+                subscription = parsed;
+                Ok(response)
+            }
+            Err(e) => {
+                let mut error = ErrorResponse::new(Some(format!("{:#}", e)));
+                *error.status_mut() = StatusCode::BAD_REQUEST;
+                Err(error)
+            }
+        }
+    })
+    .map_err(|e| anyhow::anyhow!("WebSocket handshake failed: {}", e))?;
+
+    let limiter = RateLimiter::new(subscription.rate, max_rate);
+    stream_frames(&poses.subscribe(), status, limiter, |frame| {
+        let message = match subscription.format {
+            PoseFormat::Json => Message::Text(serde_json::to_string(frame)?),
+            PoseFormat::Binary => Message::Binary(frame.to_bytes().to_vec()),
+        };
+        websocket.send(message)?;
+        Ok(())
+    })
+}
+
+// One thread registers the clients from their datagrams, another sends them the frames
+fn serve_udp(
+    socket: UdpSocket,
+    max_rate: u32,
+    poses: Receiver<PoseSample>,
+    status: TrackingStatus,
+) -> Result<()> {
+    let clients: Arc<Mutex<HashMap<SocketAddr, UdpClient>>> = Arc::default();
+
+    {
+        let socket = socket.try_clone()?;
+        let clients = clients.clone();
+        thread::spawn(move || {
+            let mut buffer = [0; 1024];
+            while let Ok((size, address)) = socket.recv_from(&mut buffer) {
+                let line = String::from_utf8_lossy(&buffer[..size]);
+                let subscription = match PoseSubscription::parse(&line) {
+                    Ok(subscription) => subscription,
+                    Err(e) => {
+                        let error = serde_json::json!({ "error": format!("{:#}", e) });
+                        let _ = socket.send_to(error.to_string().as_bytes(), address);
+                        continue;
+                    }
+                };
+
+                let mut clients = clients.lock().unwrap();
+                let client = clients.entry(address).or_insert_with(|| {
+                    tracing::info!("Pose subscriber {} over UDP", address);
+                    UdpClient {
+                        subscription,
+                        limiter: RateLimiter::new(subscription.rate, max_rate),
+                        last_seen: Instant::now(),
+                        sequence: 0,
+                    }
+                });
+                if client.subscription != subscription {
+                    client.subscription = subscription;
+                    client.limiter = RateLimiter::new(subscription.rate, max_rate);
+                }
+                client.last_seen = Instant::now();
+            }
+        });
+    }
+
+    thread::spawn(move || loop {
+        let pose = match poses.recv_timeout(HEARTBEAT) {
+            Ok(sample) => Some(poses.try_iter().last().unwrap_or(sample)),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let now = Instant::now();
+
+        let mut clients = clients.lock().unwrap();
+        clients.retain(|_, client| now.duration_since(client.last_seen) < UDP_CLIENT_TIMEOUT);
+        for (address, client) in clients.iter_mut() {
+            if pose.is_some() && !client.limiter.ready(now) {
+                continue;
+            }
+
+            let frame = PoseFrame::new(client.sequence, &status, pose);
+            match frame.encode(client.subscription.format) {
+                // Lost datagrams are not retried, the client sees the gap in the sequence
+                Ok(bytes) => {
+                    let _ = socket.send_to(&bytes, address);
+                }
+                Err(e) => tracing::error!("Unable to encode pose frame: {}", e),
+            }
+            client.sequence += 1;
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{enums::rotation_order::RotationOrder, structs::pose::Pose};
+    use std::{io::Read, sync::atomic::AtomicBool};
+
+    fn start_server() -> Result<(PoseServer, PoseBroadcaster, TrackingStatus)> {
+        let poses = PoseBroadcaster::default();
+        let status = TrackingStatus::default();
+        let server = PoseServer::start(0, 120, poses.clone(), status.clone())?;
+        Ok((server, poses, status))
+    }
+
+    // Publishes poses like the tracking thread, until the receiving test is over
+    fn publish_poses(poses: PoseBroadcaster, status: TrackingStatus) -> Arc<AtomicBool> {
+        let done = Arc::new(AtomicBool::new(false));
+        status.running.store(true, Ordering::SeqCst);
+        {
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut timestamp = 0.;
+                while !done.load(Ordering::SeqCst) {
+                    poses.publish(PoseSample::new(
+                        timestamp,
+                        Pose::default(),
+                        RotationOrder::default(),
+                        1.,
+                    ));
+                    timestamp += 0.01;
+                    thread::sleep(Duration::from_millis(10));
+                }
+            });
+        }
+        done
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(Some(10.), 60);
+        assert!(limiter.ready(start));
+        assert!(!limiter.ready(start + Duration::from_millis(50)));
+        assert!(limiter.ready(start + Duration::from_millis(95)));
+
+        // Limited by the server
+        assert_eq!(
+            RateLimiter::new(Some(1000.), 60).interval,
+            RateLimiter::new(None, 60).interval
+        );
+        assert_eq!(
+            RateLimiter::new(Some(-1.), 60).interval,
+            RateLimiter::new(None, 60).interval
+        );
+    }
+
+    #[test]
+    fn test_subscription() -> Result<()> {
+        assert_eq!(PoseSubscription::parse("\n")?, PoseSubscription::default());
+        assert_eq!(
+            PoseSubscription::parse(r#"{"format": "binary", "rate": 30}"#)?,
+            PoseSubscription {
+                format: PoseFormat::Binary,
+                rate: Some(30.)
+            }
+        );
+        assert_eq!(
+            PoseSubscription::from_query("format=binary&rate=30")?,
+            PoseSubscription::parse(r#"{"format": "binary", "rate": 30}"#)?
+        );
+        assert!(PoseSubscription::parse(r#"{"format": "xml"}"#).is_err());
+        assert!(PoseSubscription::from_query("rate=fast").is_err());
+        assert!(PoseSubscription::from_query("colour=red").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_frame() -> Result<()> {
+        let status = TrackingStatus::default();
+        status.output_paused.store(true, Ordering::SeqCst);
+        let mut sample = PoseSample::new(1.5, Pose::default(), RotationOrder::default(), 0.8);
+        sample.yaw = 12.5;
+
+        for pose in [Some(sample), None] {
+            let frame = PoseFrame::new(7, &status, pose);
+            let bytes = frame.to_bytes();
+            assert_eq!(bytes.len(), BINARY_FRAME_SIZE);
+            assert_eq!(PoseFrame::from_bytes(&bytes)?, frame);
+        }
+        assert!(PoseFrame::from_bytes(&[0; BINARY_FRAME_SIZE]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcp_json_stream() -> Result<()> {
+        let (server, poses, status) = start_server()?;
+        let mut stream = TcpStream::connect(server.tcp_address)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        stream.write_all(b"{\"rate\": 1000}\n")?;
+        let mut lines = BufReader::new(stream.try_clone()?).lines();
+
+        // Heartbeat while nothing is tracked
+        let frame: PoseFrame = serde_json::from_str(&lines.next().context("Closed")??)?;
+        assert!(frame.pose.is_none() && !frame.running);
+
+        let done = publish_poses(poses, status);
+        let frame: PoseFrame = serde_json::from_str(&lines.next().context("Closed")??)?;
+        done.store(true, Ordering::SeqCst);
+        assert!(frame.pose.is_some() && frame.running);
+        assert_eq!(frame.sequence, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tcp_invalid_subscription() -> Result<()> {
+        let (server, _, _) = start_server()?;
+        let mut stream = TcpStream::connect(server.tcp_address)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        stream.write_all(b"{\"format\": \"xml\"}\n")?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        assert!(response.contains("error"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_udp_binary_stream() -> Result<()> {
+        let (server, poses, status) = start_server()?;
+        let socket = UdpSocket::bind("127.0.0.1:0")?;
+        socket.set_read_timeout(Some(Duration::from_secs(5)))?;
+        socket.send_to(br#"{"format": "binary"}"#, server.udp_address)?;
+
+        let done = publish_poses(poses, status);
+        let mut buffer = [0; 256];
+        let frame = loop {
+            let size = socket.recv(&mut buffer)?;
+            let frame = PoseFrame::from_bytes(&buffer[..size])?;
+            if frame.pose.is_some() {
+                break frame;
+            }
+        };
+        done.store(true, Ordering::SeqCst);
+        assert!(frame.running);
+        Ok(())
+    }
+
+    #[test]
+    fn test_websocket_stream() -> Result<()> {
+        let (server, poses, status) = start_server()?;
+        let stream = TcpStream::connect(server.tcp_address)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let url = format!("ws://{}/?format=binary&rate=30", server.tcp_address);
+        let (mut websocket, _) = tungstenite::client(url, stream)?;
+
+        let done = publish_poses(poses, status);
+        let frame = loop {
+            match websocket.read()? {
+                Message::Binary(bytes) => {
+                    let frame = PoseFrame::from_bytes(&bytes)?;
+                    if frame.pose.is_some() {
+                        break frame;
+                    }
+                }
+                message => bail!("Unexpected message {:?}", message),
+            }
+        };
+        done.store(true, Ordering::SeqCst);
+        assert!(frame.running);
+
+        // Rejected during the handshake
+        let stream = TcpStream::connect(server.tcp_address)?;
+        let url = format!("ws://{}/?format=xml", server.tcp_address);
+        assert!(tungstenite::client(url, stream).is_err());
+        Ok(())
+    }
+}
@@ -1,9 +1,16 @@
 use std::ops::Deref;
 
 /// Processing the head pose (filters, etc.) and generating the x,y,z of the head.
+use crate::consts::MIN_ROI_AREA;
 use crate::enums::crop_policy::CropPolicy;
 use crate::structs::face::FaceDetect;
-use crate::structs::{pose::ProcessHeadPose, tddfa::Tddfa};
+use crate::structs::{
+    overlay::OverlayData,
+    pose::{Pose, ProcessHeadPose, Quaternion},
+    quality::QualityInput,
+    tddfa::Tddfa,
+};
+use crate::utils::blendshape::calc_blendshapes;
 use crate::utils::headpose::{calc_pose, gen_point2d};
 use crate::utils::image::crop_img;
 use anyhow::{Context, Result};
@@ -26,6 +33,12 @@ impl ProcessHeadPose {
             first_iteration: true,
             param: [0.; 62],
             roi_box: [150., 150., 400., 400.],
+            face_found: false,
+            confidence: None,
+            detection_box: [0.; 4],
+            frame_size: [640., 480.],
+            camera_box: vec![],
+            rotation: [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
         })
     }
 
@@ -61,10 +74,74 @@ impl ProcessHeadPose {
         (centroid, distance)
     }
 
-    pub fn single_iter(&mut self, frame: &Mat) -> Result<[f32; 6]> {
+    // Get the mouth open and eye blink values from the latest landmarks
+    pub fn get_blendshapes(&self) -> [f32; 3] {
+        calc_blendshapes(&self.pts_3d)
+    }
+
+    // Geometry of the last frame for the debug overlay
+    pub fn overlay(&self) -> OverlayData {
+        let landmarks = match self.pts_3d.as_slice() {
+            [x, y, ..] => x.iter().zip(y).map(|(x, y)| [*x, *y]).collect(),
+            _ => vec![],
+        };
+        // Axes start from the tip of the nose
+        let origin = landmarks.get(30).copied().unwrap_or([
+            (self.roi_box[0] + self.roi_box[2]) / 2.,
+            (self.roi_box[1] + self.roi_box[3]) / 2.,
+        ]);
+
+        OverlayData {
+            landmarks,
+            face_box: self.face_box,
+            roi_box: self.roi_box,
+            camera_box: self
+                .camera_box
+                .iter()
+                .filter(|point| point.len() >= 2)
+                .map(|point| [point[0], point[1]])
+                .collect(),
+            rotation: self.rotation,
+            origin,
+        }
+    }
+
+    // What the last frame gave, for the quality of its pose
+    pub fn quality_input(&self, timestamp: f32, pose: Pose) -> QualityInput {
+        let landmarks_box = match self.pts_3d.as_slice() {
+            [x, y, ..] if !x.is_empty() && !y.is_empty() => [
+                x.iter().copied().fold(f32::INFINITY, f32::min),
+                y.iter().copied().fold(f32::INFINITY, f32::min),
+                x.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                y.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            ],
+            _ => [0.; 4],
+        };
+
+        QualityInput {
+            timestamp,
+            confidence: self.confidence,
+            detection_box: self.detection_box,
+            landmarks_box,
+            roi_box: self.roi_box,
+            frame_size: self.frame_size,
+            pose,
+        }
+    }
+
+    // Models and execution providers of the sessions, one per line
+    pub fn inference(&self) -> String {
+        format!(
+            "{}\n{}\n",
+            self.face_detector.inference, self.tddfa.inference
+        )
+    }
+
+    pub fn single_iter(&mut self, frame: &Mat) -> Result<Pose> {
         // ! A very tuff bug laying around somewhere here, resulting in out of ordinary roi box values when moving to camera border
+        // The resulting poses are caught by validation.rs before reaching the filter
 
-        let mut return_data = [0.; 6];
+        let mut return_data = Pose::default();
 
         if self.first_iteration {
             (self.param, self.roi_box) =
@@ -84,7 +161,7 @@ impl ProcessHeadPose {
                     .run(frame, self.face_box, &self.pts_3d, CropPolicy::Landmark)?;
 
             if (self.roi_box[2] - self.roi_box[0]).abs() * (self.roi_box[3] - self.roi_box[1]).abs()
-                < 2020.
+                < MIN_ROI_AREA
             {
                 (self.param, self.roi_box) =
                     self.tddfa
@@ -118,16 +195,34 @@ impl ProcessHeadPose {
             ],
         );
 
+        self.camera_box = point2d.clone();
+        self.rotation = [
+            [p[0][0], p[0][1], p[0][2]],
+            [p[1][0], p[1][1], p[1][2]],
+            [p[2][0], p[2][1], p[2][2]],
+        ];
+
         let (centroid, distance) =
             self.get_coordintes_and_depth(pose, distance, point2d, &self.roi_box);
 
         // detect any faces, if there are no faces, return the previous values
         let faces = self.face_detector.detect(frame.clone());
+        self.face_found = !faces.is_empty();
+        self.confidence = faces.first().map(|face| face.confidence);
+        let size = frame.size()?;
+        self.frame_size = [size.width as f32, size.height as f32];
 
         if faces.is_empty() {
             return Ok(return_data);
         }
 
+        self.detection_box = [
+            faces[0].rect.x as f32,
+            faces[0].rect.y as f32,
+            faces[0].rect.x as f32 + faces[0].rect.width as f32,
+            faces[0].rect.y as f32 + faces[0].rect.height as f32,
+        ];
+
         // update the face box with a little bit of bigger box
         self.face_box = [
             faces[0].rect.x as f32 - 50.,
@@ -136,14 +231,19 @@ impl ProcessHeadPose {
             faces[0].rect.y as f32 + faces[0].rect.height as f32 + 50.,
         ];
 
-        return_data = [
-            centroid[0],
-            -centroid[1],
-            distance,
-            pose[0],
-            -pose[1],
-            pose[2],
-        ];
+        // Changing the frame of the rotation (a half turn around z) so that yaw is around +Y and pitch around +X,
+        // matching the signs of the euler angles previously sent to opentrack
+        let rotation = Quaternion::from_rotation_matrix(&self.rotation);
+
+        return_data = Pose {
+            translation: [centroid[0], -centroid[1], distance],
+            rotation: Quaternion {
+                x: -rotation.x,
+                y: -rotation.y,
+                z: rotation.z,
+                w: rotation.w,
+            },
+        };
 
         Ok(return_data)
     }
@@ -155,7 +255,8 @@ impl ProcessHeadPose {
 pub fn test_process_head_pose() -> Result<()> {
     use crate::structs::camera::ThreadedCamera;
     // use crate::utils::image::crop_img;
-    use crate::utils::visualize::draw_landmark;
+    use crate::structs::overlay::OverlaySettings;
+    use crate::utils::visualize::draw_overlay;
     use opencv::highgui;
 
     use rust_faces::{
@@ -176,7 +277,7 @@ pub fn test_process_head_pose() -> Result<()> {
     .build()
     .expect("Fail to load the face detector.");
 
-    let (tx, rx) = crossbeam_channel::unbounded::<Mat>();
+    let (tx, rx) = crossbeam_channel::unbounded::<crate::structs::camera::Frame>();
 
     let mut thr_cam = ThreadedCamera::start_camera_thread(tx, 0, "Test Camera".to_owned())?;
 
@@ -185,13 +286,13 @@ pub fn test_process_head_pose() -> Result<()> {
     let window = "video capture";
     highgui::named_window(window, highgui::WINDOW_AUTOSIZE)?;
 
-    let mut frame = rx.recv()?;
-    let mut _data: [f32; 6];
+    let mut frame = rx.recv()?.image;
+    let mut _data: Pose;
 
     let mut frame_no = 0;
     loop {
         frame = match rx.try_recv() {
-            Ok(result) => result,
+            Ok(result) => result.image,
             Err(_) => frame.clone(),
         };
 
@@ -230,16 +331,13 @@ pub fn test_process_head_pose() -> Result<()> {
 
         _data = head_pose.single_iter(&frame)?;
 
-        frame = draw_landmark(
-            frame,
-            vec![
-                head_pose.pts_3d[0][28..48].to_vec(),
-                head_pose.pts_3d[1][28..48].to_vec(),
-                head_pose.pts_3d[2][28..48].to_vec(),
-            ],
-            head_pose.roi_box,
-            (0., 255., 0.),
-            1,
+        frame = draw_overlay(
+            &frame,
+            &head_pose.overlay(),
+            &OverlaySettings {
+                enabled: true,
+                ..OverlaySettings::default()
+            },
         )?;
 
         // add bbox of face detection
@@ -0,0 +1,238 @@
+/// Named configuration sets (eg. one per simulator), each profile being a full AppConfig saved in its own file
+use crate::{
+    config::load_file,
+    consts::{APP_NAME, DEFAULT_PROFILE},
+    structs::{
+        config::ConfigError,
+        profile::{ProfileIndex, ProfileStore},
+        state::AppConfig,
+    },
+};
+use anyhow::{bail, Context, Result};
+use std::{fs, path::PathBuf};
+
+impl Default for ProfileIndex {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+        }
+    }
+}
+
+impl ProfileStore {
+    // Same directory as used by confy for config.toml
+    pub fn new() -> Result<Self> {
+        let config_path = confy::get_configuration_file_path(APP_NAME, "config")?;
+        let config_dir = config_path
+            .parent()
+            .context("Config file has no parent directory")?
+            .to_path_buf();
+
+        Ok(Self { config_dir })
+    }
+
+    fn profile_path(&self, name: &str) -> PathBuf {
+        if name == DEFAULT_PROFILE {
+            self.config_dir.join("config.toml")
+        } else {
+            self.config_dir
+                .join("profiles")
+                .join(format!("{}.toml", name))
+        }
+    }
+
+    // Names end up as file names, so only a safe subset of characters is allowed
+    pub fn validate_name(name: &str) -> Result<()> {
+        if name.trim().is_empty() {
+            bail!("Profile name can not be empty");
+        }
+        if name.len() > 64 {
+            bail!("Profile name can not be longer than 64 characters");
+        }
+        if name != name.trim()
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        {
+            bail!("Profile name can only contain letters, digits, spaces, - and _");
+        }
+        Ok(())
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        name == DEFAULT_PROFILE || self.profile_path(name).exists()
+    }
+
+    // The default profile first, then the others sorted by name
+    pub fn list(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = match fs::read_dir(self.config_dir.join("profiles")) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "toml")
+                })
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+                .filter(|name| name != DEFAULT_PROFILE && Self::validate_name(name).is_ok())
+                .collect(),
+            Err(_) => vec![],
+        };
+        profiles.sort();
+        profiles.insert(0, DEFAULT_PROFILE.to_string());
+        profiles
+    }
+
+    pub fn load(&self, name: &str) -> Result<AppConfig> {
+        Ok(self.load_checked(name)?.0)
+    }
+
+    // Also returns the invalid values which were replaced by their default
+    pub fn load_checked(&self, name: &str) -> Result<(AppConfig, Vec<ConfigError>)> {
+        load_file(&self.profile_path(name))
+            .with_context(|| format!("Unable to load profile {:?}", name))
+    }
+
+    pub fn save(&self, name: &str, config: &AppConfig) -> Result<()> {
+        let path = self.profile_path(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        confy::store_path(path, config)
+            .with_context(|| format!("Unable to save profile {:?}", name))
+    }
+
+    pub fn active(&self) -> String {
+        let index: ProfileIndex =
+            confy::load_path(self.config_dir.join("profiles.toml")).unwrap_or_default();
+
+        // The profile may have been deleted by hand
+        match self.exists(&index.active) {
+            true => index.active,
+            false => DEFAULT_PROFILE.to_string(),
+        }
+    }
+
+    pub fn set_active(&self, name: &str) -> Result<()> {
+        if !self.exists(name) {
+            bail!("Profile {:?} does not exist", name);
+        }
+        confy::store_path(
+            self.config_dir.join("profiles.toml"),
+            ProfileIndex {
+                active: name.to_string(),
+            },
+        )?;
+        Ok(())
+    }
+
+    pub fn create(&self, name: &str, config: &AppConfig) -> Result<()> {
+        Self::validate_name(name)?;
+        if self.exists(name) {
+            bail!("Profile {:?} already exists", name);
+        }
+        self.save(name, config)
+    }
+
+    pub fn clone_profile(&self, from: &str, to: &str) -> Result<()> {
+        let config = self.load(from)?;
+        self.create(to, &config)
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<()> {
+        if from == DEFAULT_PROFILE {
+            bail!("The default profile can not be renamed");
+        }
+        Self::validate_name(to)?;
+        if self.exists(to) {
+            bail!("Profile {:?} already exists", to);
+        }
+
+        let was_active = self.active() == from;
+
+        fs::rename(self.profile_path(from), self.profile_path(to))
+            .with_context(|| format!("Unable to rename profile {:?}", from))?;
+        if was_active {
+            self.set_active(to)?;
+        }
+        Ok(())
+    }
+
+    // Deleting the active profile switches back to the default one
+    pub fn delete(&self, name: &str) -> Result<()> {
+        if name == DEFAULT_PROFILE {
+            bail!("The default profile can not be deleted");
+        }
+        let was_active = self.active() == name;
+
+        fs::remove_file(self.profile_path(name))
+            .with_context(|| format!("Unable to delete profile {:?}", name))?;
+        if was_active {
+            self.set_active(DEFAULT_PROFILE)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(test: &str) -> ProfileStore {
+        let config_dir = std::env::temp_dir().join(format!(
+            "stableview-profiles-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&config_dir);
+        ProfileStore { config_dir }
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(ProfileStore::validate_name("DCS World").is_ok());
+        assert!(ProfileStore::validate_name("msfs_2020-vr").is_ok());
+        assert!(ProfileStore::validate_name("").is_err());
+        assert!(ProfileStore::validate_name(" padded").is_err());
+        assert!(ProfileStore::validate_name("../config").is_err());
+        assert!(ProfileStore::validate_name("a/b").is_err());
+    }
+
+    #[test]
+    fn test_profiles() -> Result<()> {
+        let store = store("lifecycle");
+        let mut config = AppConfig {
+            fps: 30,
+            ..AppConfig::default()
+        };
+        store.save(DEFAULT_PROFILE, &config)?;
+        assert_eq!(store.list(), [DEFAULT_PROFILE]);
+        assert_eq!(store.active(), DEFAULT_PROFILE);
+
+        // Clone keeps the values, create starts from the given config
+        store.clone_profile(DEFAULT_PROFILE, "DCS")?;
+        config.fps = 90;
+        store.create("Xplane", &config)?;
+        assert_eq!(store.load("DCS")?.fps, 30);
+        assert_eq!(store.load("Xplane")?.fps, 90);
+        assert_eq!(store.list(), [DEFAULT_PROFILE, "DCS", "Xplane"]);
+        assert!(store.create("DCS", &config).is_err());
+
+        store.set_active("DCS")?;
+        store.rename("DCS", "DCS World")?;
+        assert_eq!(store.active(), "DCS World");
+        assert_eq!(store.load("DCS World")?.fps, 30);
+        assert!(!store.exists("DCS"));
+
+        store.delete("DCS World")?;
+        assert_eq!(store.active(), DEFAULT_PROFILE);
+        assert_eq!(store.list(), [DEFAULT_PROFILE, "Xplane"]);
+
+        assert!(store.delete(DEFAULT_PROFILE).is_err());
+        assert!(store.rename(DEFAULT_PROFILE, "Other").is_err());
+        assert!(store.set_active("Missing").is_err());
+
+        fs::remove_dir_all(&store.config_dir)?;
+        Ok(())
+    }
+}
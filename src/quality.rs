@@ -0,0 +1,225 @@
+/// Quality of the head pose of each frame, combining the confidence of the face detector, the agreement of the
+/// landmarks with the detected face, the size and stability of the roi box and the speed of the head
+use crate::{
+    consts::{MAX_ANGULAR_SPEED, MAX_LINEAR_SPEED, MIN_ROI_AREA},
+    structs::quality::{QualityEstimator, QualityInput, TrackingQuality},
+};
+
+// Share of each component in the score
+const DETECTOR_WEIGHT: f32 = 0.35;
+const CONSISTENCY_WEIGHT: f32 = 0.25;
+const ROI_WEIGHT: f32 = 0.2;
+const MOTION_WEIGHT: f32 = 0.2;
+
+// Overlap (intersection over union) of the landmarks with the detected face considered as a full agreement,
+// the landmarks only cover the face from the eyebrows to the chin
+const CONSISTENT_IOU: f32 = 0.5;
+
+// Growing or shrinking of the roi box between two frames scoring 0
+const MAX_ROI_CHANGE: f32 = 2.;
+
+fn area(b: &[f32; 4]) -> f32 {
+    (b[2] - b[0]).max(0.) * (b[3] - b[1]).max(0.)
+}
+
+fn iou(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    let intersection = area(&[
+        a[0].max(b[0]),
+        a[1].max(b[1]),
+        a[2].min(b[2]),
+        a[3].min(b[3]),
+    ]);
+    let union = area(a) + area(b) - intersection;
+    match union > 0. {
+        true => intersection / union,
+        false => 0.,
+    }
+}
+
+impl TrackingQuality {
+    // Quality of a frame without any face
+    pub const NONE: TrackingQuality = TrackingQuality {
+        score: 0.,
+        detector: 0.,
+        consistency: 0.,
+        roi: 0.,
+        motion: 0.,
+    };
+}
+
+impl QualityEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, input: &QualityInput) -> TrackingQuality {
+        let roi = self.roi(input);
+        let confidence = match input.confidence {
+            Some(confidence) => confidence,
+            None => return TrackingQuality::NONE,
+        };
+        let motion = self.motion(input);
+
+        let detector = confidence.clamp(0., 1.);
+        let consistency =
+            (iou(&input.landmarks_box, &input.detection_box) / CONSISTENT_IOU).clamp(0., 1.);
+
+        TrackingQuality {
+            score: DETECTOR_WEIGHT * detector
+                + CONSISTENCY_WEIGHT * consistency
+                + ROI_WEIGHT * roi
+                + MOTION_WEIGHT * motion,
+            detector,
+            consistency,
+            roi,
+            motion,
+        }
+    }
+
+    // Boxes too small for the landmark crop, or out of the frame, are the sign of a lost fit
+    fn roi(&mut self, input: &QualityInput) -> f32 {
+        let roi_area = area(&input.roi_box);
+        let previous = self.previous_roi.replace(input.roi_box);
+
+        if !roi_area.is_finite()
+            || roi_area < MIN_ROI_AREA
+            || roi_area > input.frame_size[0] * input.frame_size[1]
+        {
+            return 0.;
+        }
+
+        match previous.map(|previous| area(&previous)) {
+            Some(previous) if previous > 0. => {
+                1. - ((roi_area / previous).ln().abs() / MAX_ROI_CHANGE.ln()).min(1.)
+            }
+            _ => 1.,
+        }
+    }
+
+    fn motion(&mut self, input: &QualityInput) -> f32 {
+        if !input.pose.is_finite() {
+            return 0.;
+        }
+
+        let motion = match self.steady {
+            Some((timestamp, pose)) if input.timestamp > timestamp => {
+                let elapsed = input.timestamp - timestamp;
+                let angular = pose.angle_to(input.pose) / elapsed / MAX_ANGULAR_SPEED;
+                let linear = pose.distance_to(input.pose) / elapsed / MAX_LINEAR_SPEED;
+                1. - angular.max(linear).min(1.)
+            }
+            // First frame
+            _ => 1.,
+        };
+
+        if motion > 0. {
+            self.steady = Some((input.timestamp, input.pose));
+        }
+        motion
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::pose::{Pose, Quaternion};
+
+    fn yawed(degrees: f32) -> Pose {
+        Pose {
+            translation: [0., 0., 30.],
+            rotation: Quaternion::exp([0., degrees.to_radians(), 0.]),
+        }
+    }
+
+    fn input(timestamp: f32, pose: Pose) -> QualityInput {
+        QualityInput {
+            timestamp,
+            confidence: Some(0.9),
+            detection_box: [200., 120., 400., 360.],
+            landmarks_box: [210., 150., 390., 360.],
+            roi_box: [180., 100., 420., 380.],
+            frame_size: [640., 480.],
+            pose,
+        }
+    }
+
+    #[test]
+    fn test_good_frame() {
+        let mut estimator = QualityEstimator::new();
+        let quality = estimator.update(&input(0., yawed(0.)));
+
+        assert_eq!(quality.detector, 0.9);
+        assert_eq!(quality.consistency, 1.);
+        assert_eq!(quality.roi, 1.);
+        assert_eq!(quality.motion, 1.);
+        assert!((quality.score - 0.965).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_no_face() {
+        let mut estimator = QualityEstimator::new();
+        let quality = estimator.update(&QualityInput {
+            confidence: None,
+            ..input(0., Pose::default())
+        });
+        assert_eq!(quality, TrackingQuality::NONE);
+
+        let quality = estimator.update(&QualityInput {
+            pose: Pose {
+                translation: [f32::NAN; 3],
+                ..Pose::default()
+            },
+            ..input(0.1, yawed(0.))
+        });
+        assert_eq!(quality.motion, 0.);
+    }
+
+    #[test]
+    fn test_landmarks_away_from_face() {
+        let mut estimator = QualityEstimator::new();
+        let quality = estimator.update(&QualityInput {
+            landmarks_box: [450., 50., 600., 200.],
+            ..input(0., yawed(0.))
+        });
+        assert_eq!(quality.consistency, 0.);
+    }
+
+    #[test]
+    fn test_roi_box() {
+        let mut estimator = QualityEstimator::new();
+        let quality = estimator.update(&QualityInput {
+            roi_box: [300., 200., 340., 240.],
+            ..input(0., yawed(0.))
+        });
+        assert_eq!(quality.roi, 0.);
+
+        // Doubling in size between two frames
+        let mut estimator = QualityEstimator::new();
+        estimator.update(&input(0., yawed(0.)));
+        let quality = estimator.update(&QualityInput {
+            roi_box: [180., 100., 660., 380.],
+            frame_size: [1280., 720.],
+            ..input(1. / 60., yawed(0.))
+        });
+        assert!(quality.roi.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_glitch() {
+        let mut estimator = QualityEstimator::new();
+        let frame = 1. / 60.;
+
+        // Turning at 60 degrees per second
+        for i in 0..30 {
+            let quality = estimator.update(&input(i as f32 * frame, yawed(i as f32)));
+            assert!(quality.motion > 0.9);
+        }
+
+        // A single frame 40 degrees off, the next one is measured from the last steady frame
+        let glitch = estimator.update(&input(30. * frame, yawed(70.)));
+        assert_eq!(glitch.motion, 0.);
+        let quality = estimator.update(&input(31. * frame, yawed(31.)));
+        assert!(quality.motion > 0.9);
+        assert!(glitch.score < quality.score);
+    }
+}
@@ -9,46 +9,92 @@ use std::{
     thread,
 };
 
+use anyhow::Result;
 use crossbeam_channel::{unbounded, Receiver, Sender};
+use iced::widget::image;
 use opencv::{core::MatTraitConst, imgcodecs, prelude::Mat};
 
-use super::{camera::ThreadedCamera, release::Release, state::AppConfig};
-use crate::consts::{APP_GITHUB_API, APP_VERSION, NO_VIDEO_IMG};
-use version_compare::{compare_to, Cmp};
-
-// * Adding this to another struct file
-pub struct AtomicF32 {
-    storage: AtomicU32,
-}
-impl AtomicF32 {
-    pub fn new(value: f32) -> Self {
-        let as_u64 = value.to_bits();
-        Self {
-            storage: AtomicU32::new(as_u64),
-        }
-    }
-    pub fn store(&self, value: f32, ordering: Ordering) {
-        let as_u64 = value.to_bits();
-        self.storage.store(as_u64, ordering)
-    }
-    pub fn load(&self, ordering: Ordering) -> f32 {
-        let as_u64 = self.storage.load(ordering);
-        f32::from_bits(as_u64)
-    }
-}
+use super::{
+    atomic::AtomicF32,
+    broadcast::PoseBroadcaster,
+    camera::{Frame, ThreadedCamera},
+    control::{ControlCommand, ControlServer},
+    curve::ResponseCurve,
+    graph::PoseHistory,
+    hotkey::HotkeyBindings,
+    logging::LogSettings,
+    overlay::{OverlayData, OverlaySettings},
+    palette::ThemePalette,
+    metrics::{Metrics, MetricsServer},
+    network::NetworkTarget,
+    portable::{ImportedConfig, ProfileChanges},
+    pose_server::{PoseServer, TrackingStatus},
+    profile::ProfileStore,
+    release::Release,
+    state::{AppConfig, AxisFilterSettings},
+    tray::Tray,
+    tuning::TuningWizard,
+    validation::ValidationSettings,
+};
+use crate::enums::{app_theme::AppTheme, filter_axis::FilterAxis, hotkey_action::HotkeyAction, rotation_order::RotationOrder, update_channel::UpdateChannel};
+use crate::consts::{APP_VERSION, DEFAULT_PROFILE, NO_VIDEO_IMG};
 
 #[derive(Clone)]
 pub struct Config {
-    pub min_cutoff: Arc<AtomicF32>,
-    pub beta: Arc<AtomicF32>,
+    // One Euro parameters of each axis, in the x, y, z, yaw, pitch, roll order
+    pub min_cutoff: [Arc<AtomicF32>; 6],
+    pub beta: [Arc<AtomicF32>; 6],
 
     pub ip: String,
     pub port: String,
+    // Comma separated host:port list, kept as typed while it is edited
+    pub extra_targets: String,
+    pub rotation_order: RotationOrder,
 
     pub fps: Arc<AtomicU32>,
+    // Read by the tracking thread on each frame
+    pub min_quality: Arc<AtomicF32>,
 
     pub selected_camera: String,
     pub hide_camera: bool,
+    pub preview_fps: u32,
+    pub theme: AppTheme,
+    pub minimize_to_tray: bool,
+    pub start_minimized: bool,
+
+    pub vmc_enabled: bool,
+    pub vmc_ip: String,
+    pub vmc_port: String,
+
+    pub control_enabled: bool,
+    pub control_port: u16,
+    pub control_token: String,
+
+    pub pose_server_enabled: bool,
+    pub pose_server_port: u16,
+    pub pose_server_max_rate: u32,
+
+    pub metrics_enabled: bool,
+    pub metrics_port: u16,
+
+    pub update_check: bool,
+    pub update_channel: UpdateChannel,
+
+    pub global_hotkeys: bool,
+    // Read by the global hotkey listener on each key press
+    pub hotkeys: Arc<Mutex<HotkeyBindings>>,
+
+    pub logging: LogSettings,
+
+    pub palette: ThemePalette,
+
+    pub overlay: OverlaySettings,
+
+    pub validation: ValidationSettings,
+
+    pub axis_filters: [AxisFilterSettings; 6],
+    // Edited while tracking, read by the tracking thread for each pose
+    pub curves: Arc<Mutex<[ResponseCurve; 6]>>,
 }
 
 // Contains configuration and state of the application and other data
@@ -57,16 +103,73 @@ pub struct HeadTracker {
 
     pub camera_list: HashMap<String, i32>,
 
+    // Name of the profile the config is loaded from and saved to
+    pub profile: String,
+    pub profiles: Vec<String>,
+    pub profile_store: Option<ProfileStore>,
+    // Name typed for creating, cloning or renaming a profile
+    pub profile_name: String,
+    // Imported file waiting for the user to review its changes
+    pub pending_import: Option<(ImportedConfig, Vec<ProfileChanges>)>,
+
     pub headtracker_thread: Option<thread::JoinHandle<()>>,
     pub headtracker_running: sync::Arc<AtomicBool>,
 
+    // Set to make the tracking thread use the next pose as the new center
+    pub recenter: Arc<AtomicBool>,
+    // Tracking keeps running but nothing is sent to opentrack and VMC
+    pub output_paused: Arc<AtomicBool>,
+    pub pose_broadcaster: PoseBroadcaster,
+
+    // Requests of the local control server, answered in the GUI update loop
+    pub control_sender: Sender<ControlCommand>,
+    pub control_receiver: Receiver<ControlCommand>,
+    pub control_server: Option<ControlServer>,
+    pub pose_server: Option<PoseServer>,
+
+    // Counters of the tracking thread, exposed by the metrics server
+    pub metrics: Arc<Metrics>,
+    pub metrics_server: Option<MetricsServer>,
+    // Models and execution providers, set by the tracking thread once the sessions are created
+    pub inference: Arc<Mutex<Option<String>>>,
+
+    // Actions of the global hotkeys, read from another thread
+    pub hotkey_sender: Sender<HotkeyAction>,
+    pub hotkey_receiver: Receiver<HotkeyAction>,
+    pub global_hotkeys_active: bool,
+
+    // Axes currently edited by the filter sliders
+    pub filter_axis: FilterAxis,
+    // Axis shown by the curve editor, in place of the filter sliders
+    pub curve_axis: FilterAxis,
+    pub show_curves: bool,
+    // Filter tuning wizard shown in place of the filter sliders while it is open
+    pub tuning: Option<TuningWizard>,
+    // Live graphs of the pose shown in place of the camera preview
+    pub show_graphs: bool,
+    pub pose_history: Arc<Mutex<PoseHistory>>,
+
+    // Dark mode of the operating system, used by the System theme
+    pub system_dark: bool,
+    // Tray icon, None when it could not be created
+    pub tray: Option<Tray>,
+    // Window closed to the tray, or started minimized
+    pub window_hidden: bool,
+
     pub should_exit: bool,
     pub error_tracker: Arc<Mutex<String>>,
 
-    pub sender: Sender<Mat>,
-    pub receiver: Receiver<Mat>,
+    pub sender: Sender<Frame>,
+    pub receiver: Receiver<Frame>,
+    // Last frame processed by the tracking thread, taken by the preview, the camera channel is only read for tracking
+    pub preview_frame: Arc<Mutex<Option<Frame>>>,
     pub frame: Mat,
+    // Downscaled RGBA pixels of the frame shown in the preview, made at the preview rate instead of each redraw
+    pub preview: Option<image::Handle>,
+    // Geometry of the last processed frame, drawn over the preview when the debug overlay is enabled
+    pub overlay_data: Arc<Mutex<Option<OverlayData>>>,
 
+    // Newer release found by the update check run at startup
     pub release_info: Option<Release>,
     pub version: String,
 }
@@ -75,24 +178,92 @@ impl Default for Config {
     fn default() -> Self {
         Config {
             // ? Adding log directory path might lead to un-anonymous logs
-            min_cutoff: Arc::new(AtomicF32::new(AppConfig::default().min_cutoff)),
-            beta: Arc::new(AtomicF32::new(AppConfig::default().beta)),
+            min_cutoff: AppConfig::default()
+                .axis_filters
+                .map(|axis| Arc::new(AtomicF32::new(axis.min_cutoff))),
+            beta: AppConfig::default()
+                .axis_filters
+                .map(|axis| Arc::new(AtomicF32::new(axis.beta))),
 
             ip: AppConfig::default().ip,
             port: AppConfig::default().port,
+            extra_targets: String::new(),
+            rotation_order: AppConfig::default().rotation_order,
 
             fps: Arc::new(AtomicU32::new(AppConfig::default().fps)),
+            min_quality: Arc::new(AtomicF32::new(AppConfig::default().min_quality)),
 
             selected_camera: AppConfig::default().selected_camera, // ? Maybe checking for new cameras in main.rs
             hide_camera: AppConfig::default().hide_camera,
+            preview_fps: AppConfig::default().preview_fps,
+            theme: AppConfig::default().theme,
+            minimize_to_tray: AppConfig::default().minimize_to_tray,
+            start_minimized: AppConfig::default().start_minimized,
+
+            vmc_enabled: AppConfig::default().vmc_enabled,
+            vmc_ip: AppConfig::default().vmc_ip,
+            vmc_port: AppConfig::default().vmc_port,
+
+            control_enabled: AppConfig::default().control_enabled,
+            control_port: AppConfig::default().control_port,
+            control_token: AppConfig::default().control_token,
+
+            pose_server_enabled: AppConfig::default().pose_server_enabled,
+            pose_server_port: AppConfig::default().pose_server_port,
+            pose_server_max_rate: AppConfig::default().pose_server_max_rate,
+
+            metrics_enabled: AppConfig::default().metrics_enabled,
+            metrics_port: AppConfig::default().metrics_port,
+
+            update_check: AppConfig::default().update_check,
+            update_channel: AppConfig::default().update_channel,
+
+            global_hotkeys: AppConfig::default().global_hotkeys,
+            hotkeys: Arc::new(Mutex::new(AppConfig::default().hotkeys)),
+
+            logging: AppConfig::default().logging,
+
+            palette: AppConfig::default().palette,
+
+            overlay: AppConfig::default().overlay,
+
+            validation: AppConfig::default().validation,
+
+            axis_filters: AppConfig::default().axis_filters,
+            curves: Arc::new(Mutex::new(AppConfig::default().curves)),
         }
     }
 }
 
+impl Config {
+    // Filter settings of each axis with the current values of the sliders
+    pub fn filter_settings(&self) -> [AxisFilterSettings; 6] {
+        let mut settings = self.axis_filters;
+        for (i, axis) in settings.iter_mut().enumerate() {
+            axis.min_cutoff = self.min_cutoff[i].load(Ordering::SeqCst);
+            axis.beta = self.beta[i].load(Ordering::SeqCst);
+        }
+        settings
+    }
+
+    // Main opentrack receiver followed by the extra ones
+    pub fn targets(&self) -> Result<Vec<NetworkTarget>> {
+        let mut targets = vec![NetworkTarget::parse(&self.ip, &self.port)?];
+        targets.extend(NetworkTarget::parse_list(&self.extra_targets)?);
+        Ok(targets)
+    }
+
+    pub fn vmc_target(&self) -> Result<NetworkTarget> {
+        NetworkTarget::parse(&self.vmc_ip, &self.vmc_port)
+    }
+}
+
 impl Default for HeadTracker {
     fn default() -> Self {
         // Setup channels for camera thread to headtracker thread
-        let (sender, receiver) = unbounded::<Mat>(); // ! bounded causes unwanted crashes bounded::<Mat>(1);
+        let (sender, receiver) = unbounded::<Frame>(); // ! bounded causes unwanted crashes bounded::<Mat>(1);
+        let (control_sender, control_receiver) = unbounded::<ControlCommand>();
+        let (hotkey_sender, hotkey_receiver) = unbounded::<HotkeyAction>();
 
         let frame = match Mat::from_slice(NO_VIDEO_IMG) {
             Ok(frame) => frame.try_clone().unwrap(),
@@ -109,30 +280,10 @@ impl Default for HeadTracker {
             }
         };
 
-        // Check for new version on GitHub
-        let client = reqwest::blocking::Client::new();
-
-        let response_json = match client
-            .get(APP_GITHUB_API)
-            .header("User-Agent", "rust-app")
-            .send()
-            .and_then(|response| response.json::<Release>())
-        {
-            Ok(release_info) => {
-                if compare_to(&release_info.tag_name[1..], APP_VERSION, Cmp::Gt) == Ok(true) {
-                    tracing::info!(
-                        "New version available: {} (current: {})",
-                        release_info.tag_name,
-                        APP_VERSION
-                    );
-                    Some(release_info)
-                } else {
-                    None
-                }
-            }
-
+        let profile_store = match ProfileStore::new() {
+            Ok(store) => Some(store),
             Err(e) => {
-                tracing::info!("Unable to check for new version: {}", e);
+                tracing::error!("Unable to find the profiles: {}", e);
                 None
             }
         };
@@ -140,6 +291,18 @@ impl Default for HeadTracker {
         HeadTracker {
             config: Config::default(),
 
+            profile: match &profile_store {
+                Some(store) => store.active(),
+                None => DEFAULT_PROFILE.to_string(),
+            },
+            profiles: match &profile_store {
+                Some(store) => store.list(),
+                None => vec![DEFAULT_PROFILE.to_string()],
+            },
+            profile_store,
+            profile_name: String::new(),
+            pending_import: None,
+
             camera_list: match ThreadedCamera::get_available_cameras() {
                 Ok(camera_list) => camera_list,
                 Err(e) => {
@@ -151,28 +314,59 @@ impl Default for HeadTracker {
             headtracker_thread: None,
             headtracker_running: Arc::new(AtomicBool::new(false)),
 
+            recenter: Arc::new(AtomicBool::new(false)),
+            output_paused: Arc::new(AtomicBool::new(false)),
+            pose_broadcaster: PoseBroadcaster::default(),
+
+            control_sender,
+            control_receiver,
+            control_server: None,
+            pose_server: None,
+
+            metrics: Arc::default(),
+            metrics_server: None,
+            inference: Arc::default(),
+
+            hotkey_sender,
+            hotkey_receiver,
+            global_hotkeys_active: false,
+
+            filter_axis: FilterAxis::default(),
+            curve_axis: FilterAxis::Yaw,
+            show_curves: false,
+            tuning: None,
+            show_graphs: false,
+            pose_history: Arc::default(),
+
+            system_dark: false,
+            tray: None,
+            window_hidden: false,
+
             should_exit: false,
             error_tracker: Arc::new(Mutex::new(String::new())),
 
             version: APP_VERSION.to_string(),
-            release_info: response_json,
+            release_info: None,
 
             sender,
             receiver,
             frame,
+            preview: None,
+            overlay_data: Arc::default(),
+            preview_frame: Arc::default(),
         }
     }
 }
 
 impl std::fmt::Display for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "(min_cutoff : {}, beta: {}, ip: {}, port: {}, fps: {}, selected_camera: {}, hide_camera: {})", 
-        self.min_cutoff.load(Ordering::SeqCst), self.beta.load(Ordering::SeqCst), self.ip,self.port, self.fps.load(Ordering::SeqCst), self.selected_camera.clone(), self.hide_camera)
+        write!(f, "(ip: {}, port: {}, extra_targets: {}, rotation_order: {}, fps: {}, min_quality: {}, selected_camera: {}, hide_camera: {}, preview_fps: {}, theme: {}, minimize_to_tray: {}, start_minimized: {}, vmc_enabled: {}, vmc_ip: {}, vmc_port: {}, control_enabled: {}, control_port: {}, pose_server_enabled: {}, pose_server_port: {}, pose_server_max_rate: {}, metrics_enabled: {}, metrics_port: {}, update_check: {}, update_channel: {}, global_hotkeys: {}, hotkeys: {:?}, logging: {:?}, palette: {:?}, overlay: {:?}, validation: {:?}, axis_filters: {:?}, curves: {:?})", 
+        self.ip,self.port, self.extra_targets, self.rotation_order, self.fps.load(Ordering::SeqCst), self.min_quality.load(Ordering::SeqCst), self.selected_camera.clone(), self.hide_camera, self.preview_fps, self.theme, self.minimize_to_tray, self.start_minimized, self.vmc_enabled, self.vmc_ip, self.vmc_port, self.control_enabled, self.control_port, self.pose_server_enabled, self.pose_server_port, self.pose_server_max_rate, self.metrics_enabled, self.metrics_port, self.update_check, self.update_channel, self.global_hotkeys, self.hotkeys.lock().unwrap(), self.logging, self.palette, self.overlay, self.validation, self.filter_settings(), self.curves.lock().unwrap())
     }
 }
 
 impl std::fmt::Display for HeadTracker {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "(config: {}, camera_list: {:?}, headtracker_running: {}, should_exit: {}, version: {})", self.config, self.camera_list, self.headtracker_running.load(Ordering::SeqCst), self.should_exit, self.version)
+        write!(f, "(profile: {}, config: {}, camera_list: {:?}, headtracker_running: {}, should_exit: {}, version: {})", self.profile, self.config, self.camera_list, self.headtracker_running.load(Ordering::SeqCst), self.should_exit, self.version)
     }
 }
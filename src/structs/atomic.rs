@@ -0,0 +1,28 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// f32 stored as its bits in an AtomicU32, used for values updated from the GUI while tracking
+pub struct AtomicF32 {
+    storage: AtomicU32,
+}
+impl AtomicF32 {
+    pub fn new(value: f32) -> Self {
+        let as_u64 = value.to_bits();
+        Self {
+            storage: AtomicU32::new(as_u64),
+        }
+    }
+    pub fn store(&self, value: f32, ordering: Ordering) {
+        let as_u64 = value.to_bits();
+        self.storage.store(as_u64, ordering)
+    }
+    pub fn load(&self, ordering: Ordering) -> f32 {
+        let as_u64 = self.storage.load(ordering);
+        f32::from_bits(as_u64)
+    }
+}
+
+impl Default for AtomicF32 {
+    fn default() -> Self {
+        Self::new(0.)
+    }
+}
@@ -0,0 +1,33 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize};
+
+// Pose sent to the subscribers, with the euler angles in degrees as sent to opentrack
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct PoseSample {
+    // Seconds since the tracking started
+    pub timestamp: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    // x, y, z, w
+    pub rotation: [f32; 4],
+    // Confidence in the pose from 0 to 1, see quality.rs
+    #[serde(default)]
+    pub quality: f32,
+}
+
+// Shares the poses of the tracking thread with any number of subscribers
+#[derive(Clone, Default)]
+pub struct PoseBroadcaster {
+    pub subscribers: Arc<Mutex<Vec<Sender<PoseSample>>>>,
+    // Last poses published, added to the diagnostics bundle
+    pub recent: Arc<Mutex<VecDeque<PoseSample>>>,
+}
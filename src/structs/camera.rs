@@ -1,9 +1,18 @@
+use opencv::prelude::Mat;
 use std::{
     sync::{self, atomic::AtomicBool},
     thread,
+    time::Instant,
 };
 
 pub struct ThreadedCamera {
     pub cam_thread: Option<thread::JoinHandle<()>>, // Storing the thread
     pub keep_running: sync::Arc<AtomicBool>,        // Signal to stop the thread
 }
+
+// Frame read from the camera along with the time it was captured
+#[derive(Clone)]
+pub struct Frame {
+    pub image: Mat,
+    pub timestamp: Instant,
+}
@@ -0,0 +1,16 @@
+use std::path::PathBuf;
+
+use crate::enums::log_level::LogLevel;
+
+// Command line arguments
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    pub profile: Option<String>,
+    pub export: Option<PathBuf>,
+    pub export_all: Option<PathBuf>,
+    pub import: Option<PathBuf>,
+    pub dry_run: bool,
+    pub diagnostics: Option<PathBuf>,
+    pub log_level: Option<LogLevel>,
+    pub help: bool,
+}
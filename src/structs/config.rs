@@ -0,0 +1,6 @@
+// Invalid value found in the config, shown to the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub field: String,
+    pub message: String,
+}
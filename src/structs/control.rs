@@ -0,0 +1,37 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize};
+
+use crate::enums::control_request::ControlRequest;
+
+// Request received by the control server, answered by the GUI which owns the application state
+pub struct ControlCommand {
+    pub request: ControlRequest,
+    pub reply: Sender<ControlResponse>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ControlStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ControlStatus {
+    pub running: bool,
+    pub output_paused: bool,
+    pub error: String,
+    pub camera: String,
+    pub fps: u32,
+}
+
+pub struct ControlServer {
+    pub tcp_address: SocketAddr,
+    pub socket_path: Option<PathBuf>,
+}
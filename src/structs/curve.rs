@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+// Output of an axis for its input between 0 and max_input, mirrored for negative inputs
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ResponseCurve {
+    // Input at the end of the curve, in the units of the axis (degrees for the rotation)
+    pub max_input: f32,
+    // Control points as [input, output] fractions of max_input, sorted by input, from [0, 0] to an input of 1
+    pub points: Vec<[f32; 2]>,
+}
@@ -0,0 +1,17 @@
+use std::path::PathBuf;
+
+use super::{broadcast::PoseSample, state::AppConfig};
+
+// Everything attached to a bug report, written to a single zip archive
+pub struct Diagnostics {
+    pub system: String,
+    pub profile: String,
+    pub config: AppConfig,
+    // Cameras found, or the error of the enumeration
+    pub cameras: String,
+    pub inference: String,
+    // Metrics of the running application, not available from the command line
+    pub metrics: Option<String>,
+    pub poses: Vec<PoseSample>,
+    pub log_directory: Option<PathBuf>,
+}
@@ -2,4 +2,6 @@ use rust_faces::FaceDetector;
 
 pub struct FaceDetect {
     pub face_detector: Box<dyn FaceDetector>,
+    // Model and execution provider of the session, for the diagnostics
+    pub inference: String,
 }
@@ -0,0 +1,20 @@
+use std::collections::VecDeque;
+
+use super::pose::Quaternion;
+
+// Pose of a processed frame before and after the filter, in x, y, z, yaw, pitch, roll order as sent to opentrack
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphSample {
+    // Capture time of the frame, in seconds since the start of the tracking
+    pub timestamp: f32,
+    pub raw: [f32; 6],
+    pub filtered: [f32; 6],
+    // Filtered head rotation, shown by the 3D head
+    pub rotation: Quaternion,
+}
+
+// Last seconds of poses plotted by the graphs, written by the tracking thread
+#[derive(Debug, Clone, Default)]
+pub struct PoseHistory {
+    pub samples: VecDeque<GraphSample>,
+}
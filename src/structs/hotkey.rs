@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+// Bindings as written in the config file, eg. "Ctrl+Shift+R", an empty string disables the hotkey
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HotkeyBindings {
+    pub recenter: String,
+    pub pause_output: String,
+    pub toggle_tracking: String,
+    pub toggle_preview: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    // Normalized key name, eg. "R", "F5" or "SPACE"
+    pub key: String,
+}
+
+// Modifiers held on any keyboard, shared by the threads reading them, eg. Ctrl on a keypad and R on the keyboard
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default)]
+pub struct HeldModifiers {
+    // Index of the keyboard and modifier key, releasing the Ctrl of one keyboard keeps the Ctrl held on another
+    pub keys: std::collections::HashSet<(usize, evdev::Key)>,
+}
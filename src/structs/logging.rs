@@ -0,0 +1,36 @@
+use std::{fs::File, path::PathBuf, time::SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::enums::{log_format::LogFormat, log_level::LogLevel, log_rotation::LogRotation};
+
+// Log file settings, the level can also be given with --log-level or the STABLEVIEW_LOG variable
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LogSettings {
+    pub level: LogLevel,
+    pub format: LogFormat,
+    pub rotation: LogRotation,
+    // Size at which the file is rotated whatever the rotation, in megabytes
+    pub max_size_mb: u32,
+    // Number of rotated files kept, the oldest are deleted
+    pub retention: u32,
+}
+
+// StableView.log being written, moved to StableView.<start time>.log when rotated
+pub struct RotatingFile {
+    pub directory: PathBuf,
+    pub rotation: LogRotation,
+    pub max_size: u64,
+    pub retention: usize,
+    pub file: File,
+    pub size: u64,
+    // Time of the first event in the file
+    pub opened: SystemTime,
+}
+
+// Adds the session ID to each event formatted by the inner formatter
+pub struct SessionFormat<F> {
+    pub session: String,
+    pub format: LogFormat,
+    pub inner: F,
+}
@@ -0,0 +1,52 @@
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, AtomicUsize},
+};
+
+use super::atomic::AtomicF32;
+
+// Upper bounds of the latency buckets, in seconds
+pub const LATENCY_BUCKETS: [f64; 10] =
+    [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.];
+
+// Count of the observations per bucket, the last one being above every bound
+#[derive(Default)]
+pub struct Histogram {
+    pub buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    pub sum_micros: AtomicU64,
+}
+
+// Health of the tracking, updated by the tracking thread and read by the metrics server
+#[derive(Default)]
+pub struct Metrics {
+    pub frames_captured: AtomicU64,
+    pub frames_processed: AtomicU64,
+    // Older frames skipped to process the newest one
+    pub frames_dropped: AtomicU64,
+    pub processing_errors: AtomicU64,
+    pub face_detect_runs: AtomicU64,
+    pub tracking_lost: AtomicU64,
+    // Frames whose pose was replaced by the last good one, being under min_quality
+    pub frames_rejected: AtomicU64,
+    // Poses rejected or clamped by the validation before the filter
+    pub glitches: AtomicU64,
+    pub packets_sent: AtomicU64,
+    pub send_errors: AtomicU64,
+
+    pub fps: AtomicF32,
+    // Score of the last frame, from 0 to 1
+    pub quality: AtomicF32,
+    pub receivers: AtomicUsize,
+
+    // Age of the frame when picked, head pose estimation, filtering and sending
+    pub capture_latency: Histogram,
+    pub inference_latency: Histogram,
+    pub filter_latency: Histogram,
+    pub send_latency: Histogram,
+    // From the capture of the frame to the pose being sent
+    pub total_latency: Histogram,
+}
+
+pub struct MetricsServer {
+    pub address: SocketAddr,
+}
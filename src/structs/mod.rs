@@ -1,9 +1,30 @@
 pub mod app;
+pub mod atomic;
+pub mod broadcast;
 pub mod camera;
+pub mod cli;
+pub mod config;
+pub mod control;
+pub mod curve;
 pub mod data;
+pub mod diagnostics;
+pub mod graph;
+pub mod hotkey;
+pub mod logging;
+pub mod metrics;
 pub mod network;
+pub mod overlay;
+pub mod palette;
+pub mod portable;
 pub mod pose;
+pub mod pose_server;
+pub mod profile;
+pub mod quality;
 pub mod release;
 pub mod state;
 pub mod tddfa;
-pub mod face;
\ No newline at end of file
+pub mod tray;
+pub mod tuning;
+pub mod validation;
+pub mod face;
+pub mod vmc;
\ No newline at end of file
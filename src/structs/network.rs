@@ -1,6 +1,26 @@
-use std::net::UdpSocket;
+use std::{
+    net::{SocketAddr, UdpSocket},
+    sync::Arc,
+    time::Instant,
+};
 
+use super::metrics::Metrics;
+use crate::enums::rotation_order::RotationOrder;
+
+// Host (IPv4, IPv6 or hostname) and port of a receiver, resolved when the tracking starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkTarget {
+    pub host: String,
+    pub port: u16,
+}
+
+// Sockets are bound per address family, so IPv4 and IPv6 receivers can be mixed
 pub struct SocketNetwork {
-    pub address: String,
-    pub socket_network: UdpSocket,
+    pub addresses: Vec<SocketAddr>,
+    // Time each receiver started failing, None while the packets are sent
+    pub failing_since: Vec<Option<Instant>>,
+    pub socket_v4: Option<UdpSocket>,
+    pub socket_v6: Option<UdpSocket>,
+    pub rotation_order: RotationOrder,
+    pub metrics: Arc<Metrics>,
 }
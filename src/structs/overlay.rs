@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+// Elements of the debug overlay to draw, saved in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OverlaySettings {
+    pub enabled: bool,
+    pub landmarks: bool,
+    pub face_box: bool,
+    pub roi_box: bool,
+    pub camera_box: bool,
+    pub axes: bool,
+}
+
+// Geometry of the last processed frame, in pixels of the camera frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OverlayData {
+    // 68 facial landmarks
+    pub landmarks: Vec<[f32; 2]>,
+    // Box around the face found by the face detector, [left, top, right, bottom]
+    pub face_box: [f32; 4],
+    // Crop given to the landmark model
+    pub roi_box: [f32; 4],
+    // Rear then front square of the 3D box around the head, 5 points each (the first repeated)
+    pub camera_box: Vec<[f32; 2]>,
+    // Rotation of the head and the point the axes start from (nose tip)
+    pub rotation: [[f32; 3]; 3],
+    pub origin: [f32; 2],
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+// Colors of the Custom theme as #rrggbb, in the [palette] section of the config file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ThemePalette {
+    pub background: String,
+    pub text: String,
+    pub primary: String,
+    pub success: String,
+    pub danger: String,
+}
@@ -0,0 +1,35 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::state::AppConfig;
+
+// Every profile in a single file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConfigBundle {
+    pub active: String,
+    pub profiles: BTreeMap<String, AppConfig>,
+}
+
+// Content of an imported file, already migrated and validated
+#[derive(Debug, Clone)]
+pub enum ImportedConfig {
+    Profile(Box<AppConfig>),
+    Bundle(ConfigBundle),
+}
+
+// Setting which differs between the current and the imported config, eg. "axis_filters[3].beta"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub current: String,
+    pub imported: String,
+}
+
+// Changes an import makes to one profile, a new profile is compared to the default settings
+#[derive(Debug, Clone)]
+pub struct ProfileChanges {
+    pub profile: String,
+    pub created: bool,
+    pub changes: Vec<FieldChange>,
+}
@@ -8,4 +8,28 @@ pub struct ProcessHeadPose {
     pub first_iteration: bool,
     pub param: [f32; 62],
     pub roi_box: [f32; 4],
+    // Whether the face detector found a face in the last frame, its score and box (without the margin of face_box)
+    pub face_found: bool,
+    pub confidence: Option<f32>,
+    pub detection_box: [f32; 4],
+    pub frame_size: [f32; 2],
+    // 3D box around the head projected on the frame, and the head rotation, drawn by the debug overlay
+    pub camera_box: Vec<Vec<f32>>,
+    pub rotation: [[f32; 3]; 3],
+}
+
+// Unit quaternion representing the head rotation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+// Head pose, rotation uses yaw around +Y, pitch around +X and roll around +Z
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub translation: [f32; 3],
+    pub rotation: Quaternion,
 }
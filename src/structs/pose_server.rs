@@ -0,0 +1,58 @@
+use std::{
+    net::SocketAddr,
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::broadcast::PoseSample;
+use crate::enums::pose_format::PoseFormat;
+
+// First message of a client, eg. {"format": "binary", "rate": 30}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PoseSubscription {
+    pub format: PoseFormat,
+    // Frames per second, limited by the server maximum
+    pub rate: Option<f32>,
+}
+
+// Sent for every pose, and as a heartbeat without pose while the tracking is stopped
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct PoseFrame {
+    // Counter of the frames sent to this client, gaps mean lost UDP datagrams
+    pub sequence: u64,
+    // Wall clock time the frame was sent, milliseconds since the Unix epoch
+    pub unix_time_ms: u64,
+    pub running: bool,
+    pub output_paused: bool,
+    pub pose: Option<PoseSample>,
+}
+
+// Drops the frames coming faster than the rate asked by the client
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimiter {
+    pub interval: Duration,
+    pub last_sent: Option<Instant>,
+}
+
+pub struct PoseServer {
+    pub tcp_address: SocketAddr,
+    pub udp_address: SocketAddr,
+}
+
+// Shared with the GUI, read when building each frame
+#[derive(Clone, Default)]
+pub struct TrackingStatus {
+    pub running: Arc<AtomicBool>,
+    pub output_paused: Arc<AtomicBool>,
+}
+
+// UDP client, forgotten when it stops renewing its subscription
+pub struct UdpClient {
+    pub subscription: PoseSubscription,
+    pub limiter: RateLimiter,
+    pub last_seen: Instant,
+    pub sequence: u64,
+}
@@ -0,0 +1,15 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+// Saved in profiles.toml, next to the config files
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProfileIndex {
+    pub active: String,
+}
+
+// The default profile is the historical config.toml, the others are in the profiles directory
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    pub config_dir: PathBuf,
+}
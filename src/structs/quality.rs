@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use super::pose::Pose;
+
+// What the head pose estimation saw on a frame, boxes are [left, top, right, bottom] in pixels of the camera frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityInput {
+    // Capture time of the frame in seconds
+    pub timestamp: f32,
+    // Score of the face detector, None when it found no face
+    pub confidence: Option<f32>,
+    // Face found by the detector, and the box around the 68 landmarks fitted by 3DDFA
+    pub detection_box: [f32; 4],
+    pub landmarks_box: [f32; 4],
+    // Crop given to the landmark model
+    pub roi_box: [f32; 4],
+    pub frame_size: [f32; 2],
+    // Unfiltered pose of the frame
+    pub pose: Pose,
+}
+
+// Confidence in the pose of a frame, each component from 0 (unusable) to 1
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+pub struct TrackingQuality {
+    // Weighted mean of the components, 0 when no face is found
+    pub score: f32,
+    pub detector: f32,
+    // Agreement of the landmarks with the face found by the detector
+    pub consistency: f32,
+    // Size of the roi box and its change since the previous frame
+    pub roi: f32,
+    // Speed of the head since the last steady frame
+    pub motion: f32,
+}
+
+// Scores the frames one after the other
+#[derive(Debug, Clone, Default)]
+pub struct QualityEstimator {
+    pub previous_roi: Option<[f32; 4]>,
+    // Last frame which did not jump, the speed of the head is measured from it so a glitch is not a reference
+    pub steady: Option<(f32, Pose)>,
+}
@@ -1,10 +1,51 @@
 // Contains new release information
 
+use std::{path::PathBuf, time::Duration};
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
+use crate::enums::update_channel::UpdateChannel;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Release {
-    pub name: String,
+    // Null for the releases made from a tag without a title
+    #[serde(default)]
+    pub name: Option<String>,
     pub tag_name: String,
     pub html_url: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+// Version found in a release tag, eg. v1.2.0 or 1.3.0-beta.2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    // Dot separated identifiers after the -, a version without them is newer
+    pub pre: Vec<String>,
+}
+
+// Result of the last check, saved next to the config files so GitHub is asked at most once a day
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UpdateCache {
+    // Unix time in seconds
+    pub checked_at: u64,
+    pub channel: UpdateChannel,
+    // Newest release of the channel
+    pub release: Option<Release>,
+}
+
+// Looks for a newer release than the running version, from a thread as the request is blocking
+#[derive(Debug, Clone)]
+pub struct UpdateChecker {
+    pub url: String,
+    pub channel: UpdateChannel,
+    pub current: String,
+    pub cache_path: Option<PathBuf>,
+    pub cache_max_age: Duration,
+    pub timeout: Duration,
 }
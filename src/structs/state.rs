@@ -1,40 +1,179 @@
 /// Saving state of the application
-use std::sync::{
-    atomic::{AtomicU32, Ordering},
-    Arc,
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
 };
 
 use crate::{
-    consts::APP_NAME,
-    structs::app::{AtomicF32, Config, HeadTracker},
+    consts::{APP_NAME, CONFIG_VERSION},
+    enums::{
+        app_theme::AppTheme, filter_kind::FilterKind, rotation_order::RotationOrder,
+        update_channel::UpdateChannel,
+    },
+    portable::{export_bundle, export_file, export_profile, import_file},
+    structs::{
+        app::{Config, HeadTracker},
+        atomic::AtomicF32,
+        curve::ResponseCurve,
+        diagnostics::Diagnostics,
+        hotkey::HotkeyBindings,
+        logging::LogSettings,
+        overlay::OverlaySettings,
+        palette::ThemePalette,
+        portable::ImportedConfig,
+        profile::ProfileStore,
+        validation::ValidationSettings,
+    },
 };
 
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 use super::camera::ThreadedCamera;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct AppConfig {
+    // Format of the file, see config.rs for the migrations between versions
+    pub version: u32,
+
     pub ip: String,
     pub port: String,
-    pub min_cutoff: f32,
-    pub beta: f32,
+    // Other receivers of the opentrack data, as host:port
+    pub extra_targets: Vec<String>,
+    pub rotation_order: RotationOrder,
     pub fps: u32,
+    // Frames scoring under it (0 to 1) are replaced by the last good pose before filtering, 0 keeps every frame
+    pub min_quality: f32,
     pub selected_camera: String,
     pub hide_camera: bool,
+    // Rate at which the camera preview is redrawn, lower than the tracking to save CPU
+    pub preview_fps: u32,
+    pub theme: AppTheme,
+    // Closing the window hides it and keeps the tracking running, reachable from the tray icon
+    pub minimize_to_tray: bool,
+    // Only the tray icon is shown at startup
+    pub start_minimized: bool,
+    pub vmc_enabled: bool,
+    pub vmc_ip: String,
+    pub vmc_port: String,
+
+    // Local control server (localhost TCP port and Unix socket)
+    pub control_enabled: bool,
+    pub control_port: u16,
+    // Sent with each request, the server does not start without it
+    pub control_token: String,
+
+    // Pose server for local subscribers over TCP, UDP and WebSocket, on the same port
+    pub pose_server_enabled: bool,
+    pub pose_server_port: u16,
+    // Highest rate a subscriber can ask for, in frames per second
+    pub pose_server_max_rate: u32,
+
+    // Prometheus metrics on http://127.0.0.1:<metrics_port>/metrics
+    pub metrics_enabled: bool,
+    pub metrics_port: u16,
+
+    // Checking GitHub for a new version at startup, at most once a day
+    pub update_check: bool,
+    pub update_channel: UpdateChannel,
+
+    // Reading the keyboards directly so hotkeys work while a game has the focus, only on Linux
+    pub global_hotkeys: bool,
+    pub hotkeys: HotkeyBindings,
+
+    // Level, format and rotation of the log file, read at startup
+    pub logging: LogSettings,
+
+    // Colors of the Custom theme
+    pub palette: ThemePalette,
+
+    // Landmarks, boxes and axes drawn over the camera preview
+    pub overlay: OverlaySettings,
+
+    // Glitches of the pose (NaN, implausible roi box, jumps) caught before the filter
+    pub validation: ValidationSettings,
+
+    // x, y, z, yaw, pitch, roll; tables have to stay after the plain values for the TOML config file
+    pub axis_filters: [AxisFilterSettings; 6],
+    // Response curve of each axis, applied after the filter
+    pub curves: [ResponseCurve; 6],
+}
+
+// Filter used for an axis and its parameters, translation is in the units sent to opentrack and rotation in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct AxisFilterSettings {
+    pub kind: FilterKind,
+
+    // One Euro parameters, updated live from the sliders while tracking
+    pub min_cutoff: f32,
+    pub beta: f32,
+
+    // Time constant in seconds
+    pub ema_time_constant: f32,
+
+    // Standard deviation of the acceleration (per second squared) and of the measurement noise
+    pub kalman_process_noise: f32,
+    pub kalman_measurement_noise: f32,
+
+    pub accela_smoothing: f32,
+    pub accela_deadzone: f32,
+}
+
+impl AxisFilterSettings {
+    pub fn translation() -> Self {
+        Self {
+            kind: FilterKind::OneEuro,
+            min_cutoff: 0.15,
+            beta: 0.01,
+            ema_time_constant: 0.1,
+            kalman_process_noise: 50.,
+            kalman_measurement_noise: 0.5,
+            accela_smoothing: 1.,
+            accela_deadzone: 0.1,
+        }
+    }
+
+    pub fn rotation() -> Self {
+        Self {
+            kind: FilterKind::OneEuro,
+            min_cutoff: 0.15,
+            beta: 0.01,
+            ema_time_constant: 0.1,
+            kalman_process_noise: 200.,
+            kalman_measurement_noise: 1.,
+            accela_smoothing: 1.5,
+            accela_deadzone: 0.1,
+        }
+    }
+
+    pub fn default_axes() -> [Self; 6] {
+        [
+            Self::translation(),
+            Self::translation(),
+            Self::translation(),
+            Self::rotation(),
+            Self::rotation(),
+            Self::rotation(),
+        ]
+    }
 }
 
 // Default values are used when the config file is not found or when there is an error loading the config file
 impl Default for AppConfig {
     fn default() -> Self {
         AppConfig {
-            min_cutoff: 0.0025,
-            beta: 0.01,
+            version: CONFIG_VERSION,
 
             ip: "127.0.0.1".to_string(),
             port: "4242".to_string(),
+            extra_targets: vec![],
+            rotation_order: RotationOrder::default(),
 
             fps: 60,
+            min_quality: 0.,
 
             selected_camera: match ThreadedCamera::get_available_cameras() {
                 Ok(cameras) => match cameras.keys().next() {
@@ -48,21 +187,76 @@ impl Default for AppConfig {
             },
 
             hide_camera: true,
+            preview_fps: 30,
+            theme: AppTheme::default(),
+            minimize_to_tray: false,
+            start_minimized: false,
+
+            vmc_enabled: false,
+            vmc_ip: "127.0.0.1".to_string(),
+            vmc_port: "39539".to_string(),
+
+            control_enabled: false,
+            control_port: 4243,
+            control_token: String::new(),
+
+            pose_server_enabled: false,
+            pose_server_port: 4244,
+            pose_server_max_rate: 60,
+
+            metrics_enabled: false,
+            metrics_port: 4245,
+
+            update_check: true,
+            update_channel: UpdateChannel::default(),
+
+            global_hotkeys: false,
+            hotkeys: HotkeyBindings::default(),
+
+            logging: LogSettings::default(),
+
+            palette: ThemePalette::default(),
+
+            overlay: OverlaySettings::default(),
+
+            validation: ValidationSettings::default(),
+
+            axis_filters: AxisFilterSettings::default_axes(),
+            curves: ResponseCurve::default_axes(),
         }
     }
 }
 
 impl HeadTracker {
     pub fn load_config(&mut self) -> Config {
-        // ! Error occurs when config data types in file does match config data types in code
-        let cfg: AppConfig = match confy::load(APP_NAME, "config") {
-            Ok(cfg) => cfg,
+        // Older files are migrated, invalid values are reset to their default and reported to the user
+        let cfg = match &self.profile_store {
+            Some(store) => store.load_checked(&self.profile),
+            None => confy::load(APP_NAME, "config")
+                .map(|cfg| (cfg, vec![]))
+                .map_err(anyhow::Error::from),
+        };
+        let cfg: AppConfig = match cfg {
+            Ok((cfg, errors)) => {
+                if !errors.is_empty() {
+                    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                    *self.error_tracker.lock().unwrap() =
+                        format!("Invalid settings reset to default - {}", errors.join(", "));
+                }
+                cfg
+            }
             Err(e) => {
-                tracing::error!("Error loading config: {}", e);
+                tracing::error!("Error loading config: {:?}", e);
+                *self.error_tracker.lock().unwrap() = format!("{:#}", e);
                 AppConfig::default()
             }
         };
 
+        self.config_from(cfg)
+    }
+
+    // Builds the runtime configuration, falling back to an available camera if the saved one is missing
+    pub fn config_from(&self, cfg: AppConfig) -> Config {
         let selected_camera = match self.camera_list.get(&cfg.selected_camera) {
             Some(_) => cfg.selected_camera,
             None => match self.camera_list.keys().next() {
@@ -72,32 +266,243 @@ impl HeadTracker {
         };
 
         Config {
-            min_cutoff: Arc::new(AtomicF32::new(cfg.min_cutoff)),
-            beta: Arc::new(AtomicF32::new(cfg.beta)),
+            min_cutoff: cfg
+                .axis_filters
+                .map(|axis| Arc::new(AtomicF32::new(axis.min_cutoff))),
+            beta: cfg
+                .axis_filters
+                .map(|axis| Arc::new(AtomicF32::new(axis.beta))),
 
             ip: cfg.ip.to_string(),
             port: cfg.port.to_string(),
+            extra_targets: cfg.extra_targets.join(", "),
+            rotation_order: cfg.rotation_order,
 
             fps: Arc::new(AtomicU32::new(cfg.fps)),
+            min_quality: Arc::new(AtomicF32::new(cfg.min_quality)),
 
             selected_camera,
             hide_camera: cfg.hide_camera,
+            preview_fps: cfg.preview_fps,
+            theme: cfg.theme,
+            minimize_to_tray: cfg.minimize_to_tray,
+            start_minimized: cfg.start_minimized,
+
+            vmc_enabled: cfg.vmc_enabled,
+            vmc_ip: cfg.vmc_ip,
+            vmc_port: cfg.vmc_port,
+
+            control_enabled: cfg.control_enabled,
+            control_port: cfg.control_port,
+            control_token: cfg.control_token,
+
+            pose_server_enabled: cfg.pose_server_enabled,
+            pose_server_port: cfg.pose_server_port,
+            pose_server_max_rate: cfg.pose_server_max_rate,
+
+            metrics_enabled: cfg.metrics_enabled,
+            metrics_port: cfg.metrics_port,
+
+            update_check: cfg.update_check,
+            update_channel: cfg.update_channel,
+
+            global_hotkeys: cfg.global_hotkeys,
+            hotkeys: Arc::new(Mutex::new(cfg.hotkeys)),
+
+            logging: cfg.logging,
+
+            palette: cfg.palette,
+
+            overlay: cfg.overlay,
+
+            validation: cfg.validation,
+
+            axis_filters: cfg.axis_filters,
+            curves: Arc::new(Mutex::new(cfg.curves)),
         }
     }
-    pub fn save_config(&self) {
-        let config = AppConfig {
+
+    pub fn app_config(&self) -> AppConfig {
+        AppConfig {
+            version: CONFIG_VERSION,
             ip: self.config.ip.clone(),
             port: self.config.port.clone(),
-            min_cutoff: self.config.min_cutoff.load(Ordering::SeqCst),
-            beta: self.config.beta.load(Ordering::SeqCst),
+            extra_targets: self
+                .config
+                .extra_targets
+                .split(',')
+                .map(str::trim)
+                .filter(|address| !address.is_empty())
+                .map(str::to_string)
+                .collect(),
+            rotation_order: self.config.rotation_order,
             fps: self.config.fps.load(Ordering::SeqCst),
+            min_quality: self.config.min_quality.load(Ordering::SeqCst),
             selected_camera: self.config.selected_camera.clone(),
             hide_camera: self.config.hide_camera,
+            preview_fps: self.config.preview_fps,
+            theme: self.config.theme,
+            minimize_to_tray: self.config.minimize_to_tray,
+            start_minimized: self.config.start_minimized,
+            vmc_enabled: self.config.vmc_enabled,
+            vmc_ip: self.config.vmc_ip.clone(),
+            vmc_port: self.config.vmc_port.clone(),
+            control_enabled: self.config.control_enabled,
+            control_port: self.config.control_port,
+            control_token: self.config.control_token.clone(),
+            pose_server_enabled: self.config.pose_server_enabled,
+            pose_server_port: self.config.pose_server_port,
+            pose_server_max_rate: self.config.pose_server_max_rate,
+            metrics_enabled: self.config.metrics_enabled,
+            metrics_port: self.config.metrics_port,
+            update_check: self.config.update_check,
+            update_channel: self.config.update_channel,
+            global_hotkeys: self.config.global_hotkeys,
+            hotkeys: self.config.hotkeys.lock().unwrap().clone(),
+            logging: self.config.logging.clone(),
+            palette: self.config.palette.clone(),
+            overlay: self.config.overlay,
+            validation: self.config.validation,
+            axis_filters: self.config.filter_settings(),
+            curves: self.config.curves.lock().unwrap().clone(),
+        }
+    }
+
+    // Replaces the configuration while keeping the values shared with the tracking thread
+    pub fn apply_config(&mut self, cfg: AppConfig) {
+        for (i, axis) in cfg.axis_filters.iter().enumerate() {
+            self.config.min_cutoff[i].store(axis.min_cutoff, Ordering::SeqCst);
+            self.config.beta[i].store(axis.beta, Ordering::SeqCst);
+        }
+        self.config.fps.store(cfg.fps, Ordering::SeqCst);
+        self.config.min_quality.store(cfg.min_quality, Ordering::SeqCst);
+        *self.config.curves.lock().unwrap() = cfg.curves.clone();
+        *self.config.hotkeys.lock().unwrap() = cfg.hotkeys.clone();
+
+        self.config = Config {
+            min_cutoff: self.config.min_cutoff.clone(),
+            beta: self.config.beta.clone(),
+            fps: self.config.fps.clone(),
+            min_quality: self.config.min_quality.clone(),
+            curves: self.config.curves.clone(),
+            hotkeys: self.config.hotkeys.clone(),
+            ..self.config_from(cfg)
         };
+    }
 
-        match confy::store(APP_NAME, "config", config) {
-            Ok(_) => tracing::info!("Config saved"),
+    pub fn save_config(&self) {
+        let saved = match &self.profile_store {
+            Some(store) => store.save(&self.profile, &self.app_config()),
+            None => confy::store(APP_NAME, "config", self.app_config()).map_err(anyhow::Error::from),
+        };
+        match saved {
+            Ok(_) => tracing::info!("Config saved to profile {}", self.profile),
             Err(e) => tracing::error!("Error saving config: {}", e),
         }
     }
+
+    fn profile_store(&self) -> Result<&ProfileStore> {
+        self.profile_store
+            .as_ref()
+            .context("Profiles are not available, the config directory could not be found")
+    }
+
+    // Loads the profile and makes it the one used at the next start
+    pub fn switch_profile(&mut self, name: &str) -> Result<()> {
+        self.open_profile(name, true)
+    }
+
+    // Loads the profile for this run only, eg. when given by a launcher, the next start uses the active one again
+    pub fn use_profile(&mut self, name: &str) -> Result<()> {
+        self.open_profile(name, false)
+    }
+
+    fn open_profile(&mut self, name: &str, set_active: bool) -> Result<()> {
+        let store = self.profile_store()?;
+        let cfg = store.load(name)?;
+        if set_active {
+            store.set_active(name)?;
+        }
+
+        self.profile = name.to_string();
+        self.profiles = store.list();
+        self.apply_config(cfg);
+        tracing::info!("Switched to profile {}", name);
+        Ok(())
+    }
+
+    // New profile with the default settings
+    pub fn create_profile(&mut self, name: &str) -> Result<()> {
+        self.profile_store()?.create(name, &AppConfig::default())?;
+        self.switch_profile(name)
+    }
+
+    pub fn clone_profile(&mut self, name: &str) -> Result<()> {
+        self.profile_store()?.create(name, &self.app_config())?;
+        self.switch_profile(name)
+    }
+
+    pub fn rename_profile(&mut self, name: &str) -> Result<()> {
+        let store = self.profile_store()?;
+        store.rename(&self.profile, name)?;
+
+        self.profile = name.to_string();
+        self.profiles = store.list();
+        Ok(())
+    }
+
+    pub fn delete_profile(&mut self) -> Result<()> {
+        let store = self.profile_store()?;
+        store.delete(&self.profile)?;
+
+        let active = store.active();
+        self.switch_profile(&active)
+    }
+
+    // The current settings, not only the saved ones, are exported
+    pub fn export_profile(&self, path: &Path) -> Result<()> {
+        let config = self.app_config();
+        export_file(path, |format| export_profile(&config, format))
+    }
+
+    pub fn export_diagnostics(&self, path: &Path) -> Result<()> {
+        Diagnostics::collect(
+            &self.profile,
+            self.app_config(),
+            Some(&self.metrics),
+            self.inference.lock().unwrap().clone(),
+            self.pose_broadcaster.recent(),
+        )
+        .write(path)
+    }
+
+    pub fn export_all(&self, path: &Path) -> Result<()> {
+        self.save_config();
+        let bundle = self.profile_store()?.bundle(&self.profile)?;
+        export_file(path, |format| export_bundle(&bundle, format))
+    }
+
+    // Read and validated, the changes are applied once the user has reviewed them
+    pub fn import_config(&mut self, path: &Path) -> Result<()> {
+        let imported = import_file(path)?;
+        let store = self.profile_store()?;
+        // Unsaved changes of the current profile are part of the comparison
+        self.save_config();
+        let changes = store.import_changes(&imported, &self.profile)?;
+
+        self.pending_import = Some((imported, changes));
+        Ok(())
+    }
+
+    pub fn apply_import(&mut self) -> Result<()> {
+        let (imported, _) = self.pending_import.take().context("Nothing to import")?;
+        self.profile_store()?.import(&imported, &self.profile)?;
+
+        // A bundle also brings the profile to use
+        let profile = match &imported {
+            ImportedConfig::Profile(_) => self.profile.clone(),
+            ImportedConfig::Bundle(bundle) => bundle.active.clone(),
+        };
+        self.switch_profile(&profile)
+    }
 }
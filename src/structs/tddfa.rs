@@ -5,6 +5,8 @@ use onnxruntime::{
 
 pub struct Tddfa {
     pub landmark_model: Session<'static>,
+    // Model and execution provider of the session, for the diagnostics
+    pub inference: String,
     pub size: i32,
     pub mean_array: [f32; 62],
     pub std_array: [f32; 62],
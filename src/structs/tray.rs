@@ -0,0 +1,19 @@
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::enums::tray_action::TrayAction;
+
+// What the tray menu shows, sent by the GUI when it changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrayState {
+    pub running: bool,
+    pub profile: String,
+    pub profiles: Vec<String>,
+}
+
+// Channels to the thread owning the tray icon, the icon is removed when they are dropped
+pub struct Tray {
+    pub actions: Receiver<TrayAction>,
+    pub updates: Sender<TrayState>,
+    // Last state sent, to only send the changes
+    pub state: TrayState,
+}
@@ -0,0 +1,33 @@
+use crate::enums::tuning_step::TuningStep;
+
+// Raw pose of a frame recorded by the wizard, its capture time in seconds and the x, y, z, yaw, pitch, roll values
+pub type TuningSample = (f32, [f32; 6]);
+
+// Guided measure of the noise and movements of the head, giving One Euro parameters for each axis
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuningWizard {
+    pub step: TuningStep,
+    // Capture time of the first frame of the current step
+    pub step_start: Option<f32>,
+    // Capture time of the last frame looked at, the pose history is read again on each tick
+    pub last_timestamp: Option<f32>,
+    pub still: Vec<TuningSample>,
+    pub slow: Vec<TuningSample>,
+    pub fast: Vec<TuningSample>,
+    // Set once the movements are recorded
+    pub result: Option<[AxisTuning; 6]>,
+}
+
+// Recommended parameters of an axis, with the filter scored on the recorded movements
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisTuning {
+    pub min_cutoff: f32,
+    pub beta: f32,
+    // RMS of the frame to frame change while holding still, unfiltered, with the current and the recommended parameters
+    pub raw_jitter: f32,
+    pub jitter_before: f32,
+    pub jitter_after: f32,
+    // Delay of the filtered movements in seconds, with the current and the recommended parameters
+    pub lag_before: f32,
+    pub lag_after: f32,
+}
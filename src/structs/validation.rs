@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::enums::{glitch::Glitch, glitch_action::GlitchAction};
+
+use super::pose::Pose;
+
+// Checks of the raw pose before the filter, in the [validation] section of the config file
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ValidationSettings {
+    pub enabled: bool,
+    // Applied to the poses moving faster than the limits, the other glitches are always rejected
+    pub action: GlitchAction,
+    // In degrees per second
+    pub max_angular_speed: f32,
+    // In units sent to opentrack per second
+    pub max_linear_speed: f32,
+}
+
+// Keeps the glitches of the head pose estimation from reaching the filter
+#[derive(Debug, Clone, Default)]
+pub struct PoseValidator {
+    pub settings: ValidationSettings,
+    // Last pose let through and its capture time, the next ones are checked against it
+    pub last: Option<(f32, Pose)>,
+}
+
+// Pose to give to the filter, with the glitch found if it was rejected or clamped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Validated {
+    pub pose: Pose,
+    pub glitch: Option<Glitch>,
+}
@@ -0,0 +1,10 @@
+use std::{
+    net::{SocketAddr, UdpSocket},
+    time::Instant,
+};
+
+pub struct VmcNetwork {
+    pub address: SocketAddr,
+    pub socket_network: UdpSocket,
+    pub start_time: Instant,
+}
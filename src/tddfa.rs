@@ -46,11 +46,18 @@ impl Tddfa {
             }
         });
 
+        let threads = 1;
         let landmark_model = ENVIRONMENT
             .new_session_builder()?
             .with_optimization_level(GraphOptimizationLevel::All)?
-            .with_number_threads(1)?
+            .with_number_threads(threads)?
             .with_model_from_memory(MODEL)?;
+        // No execution provider is registered, the session runs on the CPU
+        let inference = format!(
+            "Landmarks: 3DDFA mb05_{size}x{size} on ONNX Runtime, CPU provider, intra-op threads: {}, all graph optimizations",
+            threads,
+            size = size
+        );
 
         let data = serde_json::from_slice::<Jsondata>(DATA)?;
 
@@ -63,6 +70,7 @@ impl Tddfa {
 
         Ok(Self {
             landmark_model,
+            inference,
             size,
             mean_array,
             std_array,
@@ -0,0 +1,234 @@
+/// Tray icon keeping the tracking reachable while the window is hidden
+/// The icon lives on its own thread, which pumps the events of the platform (gtk on Linux, the message queue on
+/// Windows), forwards the clicks on the menu to the GUI and updates the menu with the state sent back
+use std::{thread, time::Duration};
+
+use anyhow::{bail, Result};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TryRecvError};
+use image::ImageFormat;
+use tray_icon::{
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu},
+    Icon, TrayIcon, TrayIconBuilder,
+};
+
+use crate::{
+    consts::{APP_NAME, ICON},
+    enums::tray_action::TrayAction,
+    structs::tray::{Tray, TrayState},
+};
+
+// Interval at which the thread pumps the events and reads the state sent by the GUI
+const TRAY_POLL: Duration = Duration::from_millis(50);
+
+struct TrayMenu {
+    // Removed from the tray when dropped
+    _icon: TrayIcon,
+    show: MenuItem,
+    toggle: MenuItem,
+    recenter: MenuItem,
+    profiles: Submenu,
+    profile_items: Vec<(String, CheckMenuItem)>,
+    quit: MenuItem,
+}
+
+impl TrayMenu {
+    fn new(state: &TrayState) -> Result<Self> {
+        let image = image::load_from_memory_with_format(ICON, ImageFormat::Ico)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        let icon = Icon::from_rgba(image.into_raw(), width, height)?;
+
+        let show = MenuItem::new(format!("Show {}", APP_NAME), true, None);
+        let toggle = MenuItem::new("Start Tracking", true, None);
+        let recenter = MenuItem::new("Recenter", true, None);
+        let profiles = Submenu::new("Profile", true);
+        let quit = MenuItem::new("Quit", true, None);
+
+        let menu = Menu::new();
+        menu.append_items(&[
+            &show,
+            &PredefinedMenuItem::separator(),
+            &toggle,
+            &recenter,
+            &profiles,
+            &PredefinedMenuItem::separator(),
+            &quit,
+        ])?;
+
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip(APP_NAME)
+            .with_icon(icon)
+            .build()?;
+
+        let mut tray_menu = Self {
+            _icon: icon,
+            show,
+            toggle,
+            recenter,
+            profiles,
+            profile_items: vec![],
+            quit,
+        };
+        tray_menu.update(state)?;
+        Ok(tray_menu)
+    }
+
+    fn update(&mut self, state: &TrayState) -> Result<()> {
+        self.toggle.set_text(match state.running {
+            true => "Stop Tracking",
+            false => "Start Tracking",
+        });
+
+        // Rebuilt when a profile is created, renamed or deleted
+        if !self
+            .profile_items
+            .iter()
+            .map(|(name, _)| name)
+            .eq(state.profiles.iter())
+        {
+            for (_, item) in self.profile_items.drain(..) {
+                self.profiles.remove(&item)?;
+            }
+            for name in &state.profiles {
+                let item = CheckMenuItem::new(name, true, false, None);
+                self.profiles.append(&item)?;
+                self.profile_items.push((name.clone(), item));
+            }
+        }
+        for (name, item) in &self.profile_items {
+            item.set_checked(*name == state.profile);
+        }
+        Ok(())
+    }
+
+    fn action(&self, id: &MenuId) -> Option<TrayAction> {
+        if id == self.show.id() {
+            return Some(TrayAction::ShowWindow);
+        }
+        if id == self.toggle.id() {
+            return Some(TrayAction::ToggleTracking);
+        }
+        if id == self.recenter.id() {
+            return Some(TrayAction::Recenter);
+        }
+        if id == self.quit.id() {
+            return Some(TrayAction::Quit);
+        }
+        self.profile_items
+            .iter()
+            .find(|(_, item)| id == item.id())
+            .map(|(name, _)| TrayAction::SwitchProfile(name.clone()))
+    }
+}
+
+// gtk is only used by this thread, rfd uses the xdg portal for the file dialogs of the GUI
+#[cfg(target_os = "linux")]
+fn init_events() -> Result<()> {
+    use anyhow::Context;
+
+    gtk::init().context("Unable to initialize gtk for the tray icon")
+}
+
+#[cfg(target_os = "linux")]
+fn pump_events() {
+    while gtk::events_pending() {
+        gtk::main_iteration_do(false);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn init_events() -> Result<()> {
+    Ok(())
+}
+
+// The icon receives its clicks through the message queue of the thread which created it
+#[cfg(target_os = "windows")]
+fn pump_events() {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE,
+    };
+
+    unsafe {
+        let mut message: MSG = std::mem::zeroed();
+        while PeekMessageW(&mut message, 0, 0, 0, PM_REMOVE) != 0 {
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+}
+
+// macOS only allows the tray on the main thread, which runs the window
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn init_events() -> Result<()> {
+    bail!("The tray icon is not available on this platform")
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn pump_events() {}
+
+fn run(
+    state: TrayState,
+    actions: Sender<TrayAction>,
+    updates: Receiver<TrayState>,
+    started: Sender<Result<()>>,
+) {
+    let mut menu = match init_events().and_then(|_| TrayMenu::new(&state)) {
+        Ok(menu) => {
+            let _ = started.send(Ok(()));
+            menu
+        }
+        Err(e) => {
+            let _ = started.send(Err(e));
+            return;
+        }
+    };
+
+    loop {
+        pump_events();
+
+        while let Ok(event) = MenuEvent::receiver().try_recv() {
+            if let Some(action) = menu.action(&event.id) {
+                if actions.send(action).is_err() {
+                    return;
+                }
+            }
+        }
+
+        loop {
+            match updates.try_recv() {
+                Ok(state) => {
+                    if let Err(e) = menu.update(&state) {
+                        tracing::error!("Unable to update the tray menu: {:?}", e);
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                // The GUI is closing
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+
+        thread::sleep(TRAY_POLL);
+    }
+}
+
+// Waits for the icon to be created, so the window is only started hidden when the tray can show it again
+pub fn spawn_tray(state: TrayState) -> Result<Tray> {
+    let (action_sender, actions) = unbounded();
+    let (updates, update_receiver) = unbounded();
+    let (started_sender, started) = bounded(1);
+
+    let thread_state = state.clone();
+    thread::Builder::new()
+        .name("tray".to_string())
+        .spawn(move || run(thread_state, action_sender, update_receiver, started_sender))?;
+
+    match started.recv() {
+        Ok(Ok(())) => Ok(Tray {
+            actions,
+            updates,
+            state,
+        }),
+        Ok(Err(e)) => Err(e),
+        Err(_) => bail!("The tray thread stopped before creating the icon"),
+    }
+}
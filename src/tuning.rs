@@ -0,0 +1,349 @@
+/// Filter tuning wizard: the jitter of each axis is measured while the head is held still and the lag on slow and
+/// fast movements, then the recorded poses are filtered again with each slider position to pick the One Euro parameters
+use crate::{
+    enums::tuning_step::TuningStep,
+    filter::{one_euro_response, slider_beta, slider_min_cutoff},
+    structs::{
+        graph::PoseHistory,
+        tuning::{AxisTuning, TuningSample, TuningWizard},
+    },
+};
+
+// Recorded time of each step, in seconds
+const STILL_SECONDS: f32 = 5.;
+const MOVES_SECONDS: f32 = 8.;
+// Jitter allowed with the recommended parameters, as a fraction of the unfiltered jitter
+const JITTER_RATIO: f32 = 0.25;
+// Frames on each side of the centered average taken as the true movement, it does not lag behind
+const REFERENCE_HALF_WIDTH: usize = 3;
+// Longest lag looked for, in frames
+const MAX_LAG_FRAMES: usize = 30;
+// Highest position of the Speed and Smooth sliders, the candidates are the values they can show
+const SLIDER_MAX: u32 = 50;
+
+impl TuningStep {
+    pub fn seconds(self) -> f32 {
+        match self {
+            TuningStep::HoldStill => STILL_SECONDS,
+            TuningStep::SlowMoves | TuningStep::FastMoves => MOVES_SECONDS,
+            TuningStep::Done => 0.,
+        }
+    }
+
+    pub fn instructions(self) -> &'static str {
+        match self {
+            TuningStep::HoldStill => "Look at the screen and keep your head as still as you can",
+            TuningStep::SlowMoves => "Slowly turn, nod, tilt and move your head in all directions",
+            TuningStep::FastMoves => "Quickly turn, nod, tilt and move your head in all directions",
+            TuningStep::Done => "Review the recommended parameters and apply them",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            TuningStep::HoldStill => TuningStep::SlowMoves,
+            TuningStep::SlowMoves => TuningStep::FastMoves,
+            TuningStep::FastMoves | TuningStep::Done => TuningStep::Done,
+        }
+    }
+}
+
+impl TuningWizard {
+    // Frames captured up to last_timestamp, already in the pose history, are not recorded
+    pub fn new(last_timestamp: Option<f32>) -> Self {
+        Self {
+            step: TuningStep::HoldStill,
+            step_start: None,
+            last_timestamp,
+            still: vec![],
+            slow: vec![],
+            fast: vec![],
+            result: None,
+        }
+    }
+
+    // Seconds left in the current step
+    pub fn remaining(&self) -> f32 {
+        let elapsed = match (self.step_start, self.last_timestamp) {
+            (Some(start), Some(last)) => last - start,
+            _ => 0.,
+        };
+        (self.step.seconds() - elapsed).max(0.)
+    }
+
+    fn recording(&mut self) -> Option<&mut Vec<TuningSample>> {
+        match self.step {
+            TuningStep::HoldStill => Some(&mut self.still),
+            TuningStep::SlowMoves => Some(&mut self.slow),
+            TuningStep::FastMoves => Some(&mut self.fast),
+            TuningStep::Done => None,
+        }
+    }
+
+    // Records the raw poses of the frames newer than the last one recorded, going to the next step once its time is over
+    pub fn record(&mut self, history: &PoseHistory) {
+        // Tracking restarted, the time went back to 0 and the recording starts over
+        if let (Some(latest), Some(last)) = (history.latest(), self.last_timestamp) {
+            if latest.timestamp < last {
+                *self = Self::new(None);
+            }
+        }
+
+        for sample in &history.samples {
+            if self.step == TuningStep::Done {
+                return;
+            }
+            if self
+                .last_timestamp
+                .is_some_and(|last| sample.timestamp <= last)
+            {
+                continue;
+            }
+            self.last_timestamp = Some(sample.timestamp);
+
+            let start = *self.step_start.get_or_insert(sample.timestamp);
+            if sample.timestamp - start >= self.step.seconds() {
+                self.step = self.step.next();
+                self.step_start = Some(sample.timestamp);
+            }
+            if let Some(recording) = self.recording() {
+                recording.push((sample.timestamp, sample.raw));
+            }
+        }
+    }
+
+    // Scores the current and candidate parameters of each axis on the recorded poses
+    pub fn tune(&mut self, min_cutoff: [f32; 6], beta: [f32; 6]) {
+        let axis_samples = |recording: &[TuningSample], axis: usize| -> Vec<(f32, f32)> {
+            recording.iter().map(|(t, data)| (*t, data[axis])).collect()
+        };
+        self.result = Some(std::array::from_fn(|axis| {
+            tune_axis(
+                &axis_samples(&self.still, axis),
+                [
+                    &axis_samples(&self.slow, axis),
+                    &axis_samples(&self.fast, axis),
+                ],
+                (min_cutoff[axis], beta[axis]),
+            )
+        }));
+    }
+}
+
+// RMS of the frame to frame change
+fn jitter(values: &[f32]) -> f32 {
+    if values.len() < 2 {
+        return 0.;
+    }
+    let sum: f32 = values
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).powi(2))
+        .sum();
+    (sum / (values.len() - 1) as f32).sqrt()
+}
+
+// Centered moving average of the raw values, standing for the movement without the noise
+fn reference(samples: &[(f32, f32)]) -> Vec<f32> {
+    (0..samples.len())
+        .map(|i| {
+            let window = &samples[i.saturating_sub(REFERENCE_HALF_WIDTH)
+                ..(i + REFERENCE_HALF_WIDTH + 1).min(samples.len())];
+            window.iter().map(|(_, x)| x).sum::<f32>() / window.len() as f32
+        })
+        .collect()
+}
+
+// Mean square distance between the filtered values and the reference shifted by a number of frames
+fn shifted_error(filtered: &[f32], reference: &[f32], shift: usize) -> f32 {
+    let count = filtered.len().saturating_sub(shift);
+    if count == 0 {
+        return f32::INFINITY;
+    }
+    filtered[shift..]
+        .iter()
+        .zip(reference)
+        .map(|(x, r)| (x - r).powi(2))
+        .sum::<f32>()
+        / count as f32
+}
+
+// Shift of the reference closest to the filtered values, in seconds
+fn lag(samples: &[(f32, f32)], filtered: &[f32], reference: &[f32]) -> f32 {
+    if samples.len() < 2 {
+        return 0.;
+    }
+    let frame_time = (samples[samples.len() - 1].0 - samples[0].0) / (samples.len() - 1) as f32;
+    let shift = (0..=MAX_LAG_FRAMES.min(samples.len() - 1))
+        .map(|shift| (shift, shifted_error(filtered, reference, shift)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(shift, _)| shift);
+    shift as f32 * frame_time
+}
+
+fn tune_axis(still: &[(f32, f32)], moves: [&[(f32, f32)]; 2], current: (f32, f32)) -> AxisTuning {
+    let references = moves.map(reference);
+    let still_jitter =
+        |min_cutoff: f32, beta: f32| jitter(&one_euro_response(still, min_cutoff, beta));
+    // Mean of the lag on the slow and the fast movements
+    let moves_lag = |min_cutoff: f32, beta: f32| {
+        moves
+            .iter()
+            .zip(&references)
+            .map(|(samples, reference)| {
+                lag(
+                    samples,
+                    &one_euro_response(samples, min_cutoff, beta),
+                    reference,
+                )
+            })
+            .sum::<f32>()
+            / moves.len() as f32
+    };
+    // Mean square distance to the movements, scoring the lag of a candidate without the frame steps of lag()
+    let moves_error = |min_cutoff: f32, beta: f32| {
+        moves
+            .iter()
+            .zip(&references)
+            .map(|(samples, reference)| {
+                shifted_error(&one_euro_response(samples, min_cutoff, beta), reference, 0)
+            })
+            .filter(|error| error.is_finite())
+            .sum::<f32>()
+    };
+
+    let raw_jitter = jitter(&still.iter().map(|(_, x)| *x).collect::<Vec<_>>());
+    let candidates: Vec<(f32, f32, f32)> = (1..=SLIDER_MAX)
+        .flat_map(|speed| {
+            (0..=SLIDER_MAX).map(move |smooth| (slider_min_cutoff(speed), slider_beta(smooth)))
+        })
+        .map(|(min_cutoff, beta)| (min_cutoff, beta, still_jitter(min_cutoff, beta)))
+        .collect();
+
+    // Least lag within the jitter allowed, or the least jitter when no parameters are smooth enough
+    let within: Vec<&(f32, f32, f32)> = candidates
+        .iter()
+        .filter(|(_, _, jitter)| *jitter <= JITTER_RATIO * raw_jitter)
+        .collect();
+    let best = match within.is_empty() {
+        false => within
+            .into_iter()
+            .map(|candidate| (candidate, moves_error(candidate.0, candidate.1)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(candidate, _)| *candidate),
+        true => candidates
+            .iter()
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .copied(),
+    };
+    let (min_cutoff, beta, jitter_after) = best.unwrap_or((current.0, current.1, raw_jitter));
+
+    AxisTuning {
+        min_cutoff,
+        beta,
+        raw_jitter,
+        jitter_before: still_jitter(current.0, current.1),
+        jitter_after,
+        lag_before: moves_lag(current.0, current.1),
+        lag_after: moves_lag(min_cutoff, beta),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{graph::GraphSample, pose::Quaternion};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const FPS: f32 = 30.;
+
+    fn history(start: usize, end: usize) -> PoseHistory {
+        let mut history = PoseHistory::default();
+        for i in start..end {
+            history.push(GraphSample {
+                timestamp: i as f32 / FPS,
+                raw: [i as f32; 6],
+                filtered: [i as f32; 6],
+                rotation: Quaternion::IDENTITY,
+            });
+        }
+        history
+    }
+
+    #[test]
+    fn test_record() {
+        // Frames already in the history when the wizard starts are skipped
+        let mut wizard = TuningWizard::new(Some(1.));
+        wizard.record(&history(0, 60));
+        assert_eq!(wizard.still.len(), 29);
+        assert_eq!(wizard.still[0].1[0], 31.);
+
+        // The history is read on each tick, overlapping the frames already recorded
+        let mut wizard = TuningWizard::new(None);
+        let mut end: usize = 0;
+        while wizard.step != TuningStep::Done {
+            end += 10;
+            wizard.record(&history(end.saturating_sub(200), end));
+        }
+        assert_eq!(wizard.still.len() as f32, STILL_SECONDS * FPS);
+        assert_eq!(wizard.slow.len() as f32, MOVES_SECONDS * FPS);
+        assert_eq!(wizard.fast.len() as f32, MOVES_SECONDS * FPS);
+        assert_eq!(wizard.remaining(), 0.);
+
+        // Tracking restarted while recording
+        let mut wizard = TuningWizard::new(None);
+        wizard.record(&history(0, 90));
+        assert_eq!(wizard.step, TuningStep::HoldStill);
+        assert!((wizard.remaining() - (STILL_SECONDS - 89. / FPS)).abs() < 1e-4);
+        wizard.record(&history(0, 10));
+        assert_eq!(wizard.still.len(), 10);
+    }
+
+    #[test]
+    fn test_tune() {
+        const NOISE: f32 = 0.3;
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut samples = |seconds: f32, movement: &dyn Fn(f32) -> f32| -> Vec<TuningSample> {
+            (0..(seconds * FPS) as usize)
+                .map(|i| {
+                    let t = i as f32 / FPS;
+                    (t, [movement(t) + rng.gen_range(-NOISE..NOISE); 6])
+                })
+                .collect()
+        };
+
+        let mut wizard = TuningWizard::new(None);
+        wizard.still = samples(STILL_SECONDS, &|_| 0.);
+        wizard.slow = samples(MOVES_SECONDS, &|t| 10. * (t * 1.).sin());
+        wizard.fast = samples(MOVES_SECONDS, &|t| 20. * (t * 8.).sin());
+
+        // Barely filtered translation, heavily smoothed rotation
+        wizard.tune([60., 60., 60., 0.03, 0.03, 0.03], [0., 0., 0., 0., 0., 0.]);
+        let result = wizard.result.unwrap();
+
+        for (axis, tuning) in result.iter().enumerate() {
+            assert!(
+                tuning.jitter_after <= JITTER_RATIO * tuning.raw_jitter,
+                "axis {}: {:?}",
+                axis,
+                tuning
+            );
+            assert!(tuning.lag_after < 0.2, "axis {}: {:?}", axis, tuning);
+            assert!(
+                tuning.min_cutoff > 0. && tuning.beta > 0.,
+                "axis {}: {:?}",
+                axis,
+                tuning
+            );
+        }
+        assert!(
+            result[0].jitter_after < result[0].jitter_before,
+            "{:?}",
+            result[0]
+        );
+        assert!(
+            result[3].lag_after < result[3].lag_before,
+            "{:?}",
+            result[3]
+        );
+    }
+}
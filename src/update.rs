@@ -0,0 +1,381 @@
+/// Checks GitHub for a newer release of StableView, at most once a day, without blocking the startup
+use crate::{
+    consts::{APP_GITHUB_API, APP_VERSION},
+    enums::update_channel::UpdateChannel,
+    structs::release::{Release, ReleaseVersion, UpdateCache, UpdateChecker},
+};
+use anyhow::{Context, Result};
+use std::{
+    cmp::Ordering,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub const UPDATE_CACHE_FILE: &str = "update_check.json";
+
+impl ReleaseVersion {
+    // Accepts tags like v1.2.0, V1.2, StableView-1.2.0 or 1.3.0-beta.2+build.5, missing numbers are 0
+    pub fn parse(tag: &str) -> Option<Self> {
+        let version = tag.trim().trim_start_matches(|c: char| !c.is_ascii_digit());
+        // Build metadata is ignored when comparing versions
+        let version = version.split('+').next()?;
+        let (numbers, pre) = match version.split_once('-') {
+            Some((numbers, pre)) => (numbers, Some(pre)),
+            None => (version, None),
+        };
+
+        let numbers = numbers
+            .split('.')
+            .map(|number| number.parse::<u64>().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        if numbers.is_empty() || numbers.len() > 3 {
+            return None;
+        }
+
+        let pre = match pre {
+            Some(pre) => {
+                let identifiers: Vec<String> = pre.split('.').map(str::to_string).collect();
+                if identifiers.iter().any(|identifier| identifier.is_empty()) {
+                    return None;
+                }
+                identifiers
+            }
+            None => vec![],
+        };
+
+        Some(Self {
+            major: numbers[0],
+            minor: numbers.get(1).copied().unwrap_or(0),
+            patch: numbers.get(2).copied().unwrap_or(0),
+            pre,
+        })
+    }
+}
+
+// Numeric identifiers are compared as numbers and are older than alphanumeric ones, as in semver
+fn compare_identifiers(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+impl Ord for ReleaseVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => {
+                    for (a, b) in self.pre.iter().zip(&other.pre) {
+                        let ordering = compare_identifiers(a, b);
+                        if ordering != Ordering::Equal {
+                            return ordering;
+                        }
+                    }
+                    self.pre.len().cmp(&other.pre.len())
+                }
+            })
+    }
+}
+
+impl PartialOrd for ReleaseVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Newest release of the channel, drafts and tags which are not versions are skipped
+pub fn newest_release(releases: &[Release], channel: UpdateChannel) -> Option<&Release> {
+    releases
+        .iter()
+        .filter(|release| !release.draft)
+        .filter(|release| channel == UpdateChannel::Prerelease || !release.prerelease)
+        .filter_map(|release| Some((ReleaseVersion::parse(&release.tag_name)?, release)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, release)| release)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl UpdateChecker {
+    pub fn new(channel: UpdateChannel, cache_path: Option<PathBuf>) -> Self {
+        Self {
+            url: APP_GITHUB_API.to_string(),
+            channel,
+            current: APP_VERSION.to_string(),
+            cache_path,
+            cache_max_age: Duration::from_secs(24 * 60 * 60),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    // Never fails, being offline or rate limited only means no update is shown
+    pub fn check(&self) -> Option<Release> {
+        let cache = self.read_cache();
+        let fresh = cache.as_ref().is_some_and(|cache| {
+            cache.channel == self.channel
+                && unix_time().saturating_sub(cache.checked_at) < self.cache_max_age.as_secs()
+        });
+
+        let release = match (fresh, cache) {
+            (true, Some(cache)) => cache.release,
+            (_, cache) => match self.fetch() {
+                Ok(release) => {
+                    self.write_cache(&release);
+                    release
+                }
+                Err(e) => {
+                    tracing::info!("Unable to check for new version: {:#}", e);
+                    // The result of an older check is better than nothing
+                    cache
+                        .filter(|cache| cache.channel == self.channel)
+                        .and_then(|cache| cache.release)
+                }
+            },
+        };
+
+        let current = ReleaseVersion::parse(&self.current)?;
+        let release = release?;
+        match ReleaseVersion::parse(&release.tag_name) {
+            Some(version) if version > current => {
+                tracing::info!(
+                    "New version available: {} (current: {})",
+                    release.tag_name,
+                    self.current
+                );
+                Some(release)
+            }
+            _ => None,
+        }
+    }
+
+    fn fetch(&self) -> Result<Option<Release>> {
+        let releases: Vec<Release> = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()?
+            .get(&self.url)
+            .header("User-Agent", "rust-app")
+            .header("Accept", "application/vnd.github+json")
+            .send()?
+            .error_for_status()?
+            .json()
+            .context("Unexpected list of releases")?;
+
+        Ok(newest_release(&releases, self.channel).cloned())
+    }
+
+    fn read_cache(&self) -> Option<UpdateCache> {
+        let contents = fs::read_to_string(self.cache_path.as_ref()?).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    fn write_cache(&self, release: &Option<Release>) {
+        let path = match &self.cache_path {
+            Some(path) => path,
+            None => return,
+        };
+        let cache = UpdateCache {
+            checked_at: unix_time(),
+            channel: self.channel,
+            release: release.clone(),
+        };
+        let written = serde_json::to_string_pretty(&cache)
+            .map_err(anyhow::Error::from)
+            .and_then(|contents| Ok(fs::write(path, contents)?));
+        if let Err(e) = written {
+            tracing::info!("Unable to save the update check to {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    const RELEASES: &str = r#"[
+        {"name": "Beta", "tag_name": "v1.3.0-beta.2", "html_url": "https://example.com/beta", "prerelease": true, "draft": false},
+        {"name": "Draft", "tag_name": "v9.0.0", "html_url": "https://example.com/draft", "prerelease": false, "draft": true},
+        {"name": "Nightly", "tag_name": "nightly", "html_url": "https://example.com/nightly", "prerelease": true, "draft": false},
+        {"name": "Stable", "tag_name": "v1.2.0", "html_url": "https://example.com/stable", "prerelease": false, "draft": false},
+        {"name": "Old", "tag_name": "v1.0.0", "html_url": "https://example.com/old", "prerelease": false, "draft": false}
+    ]"#;
+
+    // Answers every request with the given status and body, counting the requests
+    fn mock_server(status: &'static str, body: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/releases", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    line.clear();
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn checker(url: &str, channel: UpdateChannel, test: &str) -> UpdateChecker {
+        let cache_path = std::env::temp_dir().join(format!(
+            "stableview-update-{}-{}.json",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&cache_path);
+        UpdateChecker {
+            url: url.to_string(),
+            current: "1.0.0".to_string(),
+            timeout: Duration::from_secs(2),
+            ..UpdateChecker::new(channel, Some(cache_path))
+        }
+    }
+
+    #[test]
+    fn test_parse_version() {
+        let version = |tag| ReleaseVersion::parse(tag);
+        assert_eq!(version("v1.2.3"), version("1.2.3"));
+        assert_eq!(version("V1.2"), version("1.2.0"));
+        assert_eq!(version("StableView-1.2.0+build.5"), version("1.2.0"));
+        assert_eq!(version("v1.3.0-beta.2").unwrap().pre, ["beta", "2"]);
+        assert_eq!(version("nightly"), None);
+        assert_eq!(version("v"), None);
+        assert_eq!(version("v1.2.3.4"), None);
+        assert_eq!(version("v1..2"), None);
+        assert_eq!(version("v1.0.0-"), None);
+
+        assert!(version("v1.0.1") > version("v1.0.0"));
+        assert!(version("v1.10.0") > version("v1.9.9"));
+        assert!(version("v1.3.0") > version("v1.3.0-rc.1"));
+        assert!(version("v1.3.0-beta.11") > version("v1.3.0-beta.2"));
+        assert!(version("v1.3.0-rc.1") > version("v1.3.0-beta.2"));
+        assert!(version("v1.3.0-beta.2.1") > version("v1.3.0-beta.2"));
+    }
+
+    #[test]
+    fn test_channels() -> Result<()> {
+        let releases: Vec<Release> = serde_json::from_str(RELEASES)?;
+        assert_eq!(
+            newest_release(&releases, UpdateChannel::Stable).and_then(|r| r.name.as_deref()),
+            Some("Stable")
+        );
+        assert_eq!(
+            newest_release(&releases, UpdateChannel::Prerelease).and_then(|r| r.name.as_deref()),
+            Some("Beta")
+        );
+        assert_eq!(newest_release(&[], UpdateChannel::Stable), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_and_cache() {
+        let (url, requests) = mock_server("200 OK", RELEASES);
+        let stable = checker(&url, UpdateChannel::Stable, "cache");
+
+        let release = stable.check().map(|release| release.tag_name);
+        assert_eq!(release.as_deref(), Some("v1.2.0"));
+        // Answered from the cache the second time
+        assert_eq!(stable.check().map(|release| release.tag_name), release);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Another channel is asked again
+        let beta = UpdateChecker {
+            channel: UpdateChannel::Prerelease,
+            ..stable.clone()
+        };
+        assert_eq!(
+            beta.check().map(|release| release.tag_name).as_deref(),
+            Some("v1.3.0-beta.2")
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // Already up to date
+        let current = UpdateChecker {
+            current: "1.3.0".to_string(),
+            ..beta.clone()
+        };
+        assert_eq!(current.check(), None);
+
+        let _ = fs::remove_file(stable.cache_path.unwrap());
+    }
+
+    #[test]
+    fn test_check_unnamed_release() {
+        let (url, _) = mock_server(
+            "200 OK",
+            r#"[{"name": null, "tag_name": "v1.1.0", "html_url": "https://example.com/unnamed", "prerelease": false, "draft": false}]"#,
+        );
+        let checker = checker(&url, UpdateChannel::Stable, "unnamed");
+
+        let release = checker.check().unwrap();
+        assert_eq!(release.name, None);
+        assert_eq!(release.tag_name, "v1.1.0");
+
+        let _ = fs::remove_file(checker.cache_path.unwrap());
+    }
+
+    #[test]
+    fn test_check_offline() {
+        let (url, _) = mock_server("200 OK", RELEASES);
+        let online = checker(&url, UpdateChannel::Stable, "offline");
+        assert!(online.check().is_some());
+
+        // The expired cache is used when the server can not be reached
+        let offline = UpdateChecker {
+            url: "http://127.0.0.1:1/releases".to_string(),
+            cache_max_age: Duration::ZERO,
+            ..online.clone()
+        };
+        assert_eq!(
+            offline.check().map(|release| release.tag_name).as_deref(),
+            Some("v1.2.0")
+        );
+
+        let _ = fs::remove_file(online.cache_path.as_ref().unwrap());
+        assert_eq!(offline.check(), None);
+
+        // Errors and unexpected answers of the server
+        let (url, _) = mock_server("403 Forbidden", r#"{"message": "API rate limit exceeded"}"#);
+        assert_eq!(
+            checker(&url, UpdateChannel::Stable, "limited").check(),
+            None
+        );
+        let (url, _) = mock_server("200 OK", "<html></html>");
+        assert_eq!(
+            checker(&url, UpdateChannel::Stable, "invalid").check(),
+            None
+        );
+    }
+}
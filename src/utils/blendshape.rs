@@ -0,0 +1,91 @@
+/// Utility function to estimate simple facial expressions (blendshapes) from the 68 reconstructed landmarks
+/// Landmark indices follow the iBUG 300-W 68 points layout - https://ibug.doc.ic.ac.uk/resources/300-W/
+// Eye aspect ratio values for a fully open and a fully closed eye
+const EYE_OPEN_RATIO: f32 = 0.28;
+const EYE_CLOSED_RATIO: f32 = 0.12;
+
+// Mouth opening (relative to nose-chin distance) at which the mouth is considered fully open
+const MOUTH_OPEN_RATIO: f32 = 0.6;
+
+fn distance(pts: &[Vec<f32>], a: usize, b: usize) -> f32 {
+    (pts[0][a] - pts[0][b]).hypot(pts[1][a] - pts[1][b])
+}
+
+// Eye aspect ratio - https://vision.fe.uni-lj.si/cvww2016/proceedings/papers/05.pdf
+fn eye_aspect_ratio(pts: &[Vec<f32>], start: usize) -> f32 {
+    let width = distance(pts, start, start + 3);
+    if width <= f32::EPSILON {
+        return EYE_OPEN_RATIO;
+    }
+    (distance(pts, start + 1, start + 5) + distance(pts, start + 2, start + 4)) / (2. * width)
+}
+
+fn blink(pts: &[Vec<f32>], start: usize) -> f32 {
+    ((EYE_OPEN_RATIO - eye_aspect_ratio(pts, start)) / (EYE_OPEN_RATIO - EYE_CLOSED_RATIO))
+        .clamp(0., 1.)
+}
+
+// Returns the mouth open, left eye blink and right eye blink values, each in the range [0, 1]
+pub fn calc_blendshapes(pts: &[Vec<f32>]) -> [f32; 3] {
+    if pts.len() < 2 || pts[0].len() < 68 || pts[1].len() < 68 {
+        return [0.; 3];
+    }
+
+    let face_height = distance(pts, 33, 8);
+    let mouth_open = if face_height <= f32::EPSILON {
+        0.
+    } else {
+        (distance(pts, 62, 66) / face_height / MOUTH_OPEN_RATIO).clamp(0., 1.)
+    };
+
+    // Landmarks 36-41 belong to the right eye of the person, 42-47 to the left one
+    [mouth_open, blink(pts, 42), blink(pts, 36)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(eye_opening: f32, mouth_opening: f32) -> Vec<Vec<f32>> {
+        let mut pts = vec![vec![0.; 68], vec![0.; 68], vec![0.; 68]];
+
+        for start in [36, 42] {
+            let offset = start as f32;
+            pts[0][start] = offset;
+            pts[0][start + 3] = offset + 10.;
+            for (i, x) in [(1, 3.), (2, 7.), (4, 7.), (5, 3.)] {
+                pts[0][start + i] = offset + x;
+            }
+            pts[1][start + 1] = -eye_opening / 2.;
+            pts[1][start + 2] = -eye_opening / 2.;
+            pts[1][start + 4] = eye_opening / 2.;
+            pts[1][start + 5] = eye_opening / 2.;
+        }
+
+        pts[1][33] = 0.;
+        pts[1][8] = 100.;
+        pts[1][62] = 60.;
+        pts[1][66] = 60. + mouth_opening;
+
+        pts
+    }
+
+    #[test]
+    fn test_calc_blendshapes() {
+        assert_eq!(calc_blendshapes(&face(3., 0.)), [0., 0., 0.]);
+        assert_eq!(calc_blendshapes(&face(0., 60.)), [1., 1., 1.]);
+
+        let [mouth, left, right] = calc_blendshapes(&face(2., 30.));
+        assert!((mouth - 0.5).abs() < 1e-6);
+        assert!((left - 0.5).abs() < 1e-6);
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_calc_blendshapes_invalid_landmarks() {
+        assert_eq!(
+            calc_blendshapes(&[vec![1., 2., 3.], vec![4., 5., 6.], vec![7., 8., 9.]]),
+            [0.; 3]
+        );
+    }
+}
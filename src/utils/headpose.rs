@@ -58,6 +58,48 @@ fn matrix2angle(r: &[[f32; 3]]) -> (f32, f32, f32) {
     (x, y, z)
 }
 
+// Converting a rotation matrix to an unit quaternion [x, y, z, w]
+pub fn matrix2quaternion(r: &[[f32; 3]; 3]) -> [f32; 4] {
+    let trace = r[0][0] + r[1][1] + r[2][2];
+
+    let q = if trace > 0. {
+        let s = (trace + 1.).sqrt() * 2.;
+        [
+            (r[2][1] - r[1][2]) / s,
+            (r[0][2] - r[2][0]) / s,
+            (r[1][0] - r[0][1]) / s,
+            0.25 * s,
+        ]
+    } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+        let s = (1. + r[0][0] - r[1][1] - r[2][2]).sqrt() * 2.;
+        [
+            0.25 * s,
+            (r[0][1] + r[1][0]) / s,
+            (r[0][2] + r[2][0]) / s,
+            (r[2][1] - r[1][2]) / s,
+        ]
+    } else if r[1][1] > r[2][2] {
+        let s = (1. + r[1][1] - r[0][0] - r[2][2]).sqrt() * 2.;
+        [
+            (r[0][1] + r[1][0]) / s,
+            0.25 * s,
+            (r[1][2] + r[2][1]) / s,
+            (r[0][2] - r[2][0]) / s,
+        ]
+    } else {
+        let s = (1. + r[2][2] - r[0][0] - r[1][1]).sqrt() * 2.;
+        [
+            (r[0][2] + r[2][0]) / s,
+            (r[1][2] + r[2][1]) / s,
+            0.25 * s,
+            (r[1][0] - r[0][1]) / s,
+        ]
+    };
+
+    let norm = q.iter().map(|&x| x * x).sum::<f32>().sqrt();
+    [q[0] / norm, q[1] / norm, q[2] / norm, q[3] / norm]
+}
+
 pub fn calc_pose(param: &[f32; 62]) -> ([[f32; 4]; 3], [f32; 3]) {
     let p = [
         [param[0], param[1], param[2], param[3]],
@@ -165,6 +207,7 @@ pub fn gen_point2d(p: &[[f32; 4]; 3], ver: Vec<Vec<f32>>) -> (Vec<Vec<f32>>, f32
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::f32::consts::FRAC_PI_4;
 
     #[test]
     fn test_p2s_rt() {
@@ -218,6 +261,24 @@ mod tests {
         assert_eq!(updated_pose, [-11.762_708, 116.565_04, 90.0]);
     }
 
+    #[test]
+    fn test_matrix2quaternion() {
+        let identity = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+        assert_eq!(matrix2quaternion(&identity), [0., 0., 0., 1.]);
+
+        // 90 degrees around the z axis
+        let r = [[0., -1., 0.], [1., 0., 0.], [0., 0., 1.]];
+        let q = matrix2quaternion(&r);
+        let expected = [0., 0., FRAC_PI_4.sin(), FRAC_PI_4.cos()];
+        for i in 0..4 {
+            assert!((q[i] - expected[i]).abs() < 1e-6);
+        }
+
+        // 180 degrees around the x axis, where the trace is negative
+        let r = [[1., 0., 0.], [0., -1., 0.], [0., 0., -1.]];
+        assert_eq!(matrix2quaternion(&r), [1., 0., 0., 0.]);
+    }
+
     #[test]
     fn test_build_camera_box() {
         let llength = 90.0;
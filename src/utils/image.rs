@@ -1,7 +1,8 @@
 /// Utility function for processing image
 /// Python source - https://github.com/cleardusk/3DDFA/blob/d5c1f6a647a89070b1f9ea4e88c910b743a1a87a/utils/inference.py#L20
 use opencv::{
-    core::{Mat, Rect},
+    core::{Mat, Rect, Size},
+    imgproc,
     prelude::MatTraitConstManual,
 };
 use opencv::prelude::MatTraitConst;
@@ -51,6 +52,46 @@ pub fn crop_img(img: &Mat, roi_box: &[f32; 4]) -> Result<Mat, opencv::Error> {
     }
 }
 
+// RGBA pixels of the frame for the GUI preview, downscaled to the given height (never upscaled)
+// Returns the width, the height and the pixels, empty for an empty frame
+pub fn preview_pixels(img: &Mat, height: i32) -> Result<(u32, u32, Vec<u8>), opencv::Error> {
+    let size = img.size()?;
+    if size.width <= 0 || size.height <= 0 {
+        return Ok((0, 0, vec![]));
+    }
+
+    let mut resized = Mat::default();
+    let source = match size.height > height {
+        true => {
+            let width = (size.width as f32 * height as f32 / size.height as f32).round() as i32;
+            // INTER_AREA averages the pixels, avoiding the aliasing of INTER_LINEAR when shrinking
+            imgproc::resize(
+                img,
+                &mut resized,
+                Size {
+                    width: width.max(1),
+                    height,
+                },
+                0.0,
+                0.0,
+                imgproc::INTER_AREA,
+            )?;
+            &resized
+        }
+        false => img,
+    };
+
+    let mut rgba = Mat::default();
+    imgproc::cvt_color(source, &mut rgba, imgproc::COLOR_BGR2RGBA, 0)?;
+    let size = rgba.size()?;
+
+    Ok((
+        size.width as u32,
+        size.height as u32,
+        rgba.data_bytes()?.to_vec(),
+    ))
+}
+
 #[test]
 fn test_crop_img() -> Result<(), opencv::Error> {
     use opencv::{
@@ -73,3 +114,66 @@ fn test_crop_img() -> Result<(), opencv::Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_preview_pixels() -> Result<(), opencv::Error> {
+    use opencv::core::{Scalar, CV_8UC3};
+
+    // Blue in BGR
+    let frame =
+        Mat::new_rows_cols_with_default(480, 640, CV_8UC3, Scalar::new(255., 0., 0., 0.))?;
+    let (width, height, pixels) = preview_pixels(&frame, 200)?;
+    assert_eq!((width, height), (267, 200));
+    assert_eq!(pixels.len(), 267 * 200 * 4);
+    assert_eq!(&pixels[..4], &[0, 0, 255, 255]);
+
+    // Smaller frames keep their size
+    let (width, height, _) = preview_pixels(&frame, 720)?;
+    assert_eq!((width, height), (640, 480));
+
+    assert_eq!(preview_pixels(&Mat::default(), 200)?, (0, 0, vec![]));
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "Benchmark, run cargo test --release bench_preview -- --ignored --nocapture"]
+fn bench_preview() -> Result<(), opencv::Error> {
+    use opencv::{
+        core::{randu, Scalar, CV_8UC3},
+        imgcodecs,
+        types::{VectorOfi32, VectorOfu8},
+    };
+    use std::time::Instant;
+
+    let mut frame = Mat::new_rows_cols_with_default(720, 1280, CV_8UC3, Scalar::all(0.))?;
+    randu(&mut frame, &Scalar::all(0.), &Scalar::all(255.))?;
+    let iterations = 100;
+
+    // Previous preview, the full frame encoded to PNG then decoded again by iced
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let mut encoded = VectorOfu8::new();
+        imgcodecs::imencode(".PNG", &frame, &mut encoded, &VectorOfi32::new())?;
+        let decoded = ::image::load_from_memory(encoded.as_slice()).expect("Invalid PNG");
+        assert_eq!(decoded.width(), 1280);
+    }
+    let png = start.elapsed() / iterations;
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let (_, height, _) = preview_pixels(&frame, 200)?;
+        assert_eq!(height, 200);
+    }
+    let pixels = start.elapsed() / iterations;
+
+    println!(
+        "1280x720 preview frame: PNG {:?}, RGBA pixels {:?} ({:.0}x faster)",
+        png,
+        pixels,
+        png.as_secs_f64() / pixels.as_secs_f64()
+    );
+    assert!(pixels < png);
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
+pub mod blendshape;
 pub mod common;
 pub mod headpose;
 pub mod image;
+pub mod osc;
 pub mod tddfa;
 pub mod visualize;
@@ -0,0 +1,96 @@
+/// Minimal OSC 1.0 encoder, only supporting what the VMC protocol needs (int32, float32, string and bundles)
+/// Specification - https://opensoundcontrol.stanford.edu/spec-1_0.html
+use crate::enums::osc_arg::OscArg;
+
+// OSC timetag with the special meaning "immediately"
+const TIMETAG_IMMEDIATELY: u64 = 1;
+
+// Strings are null terminated and padded with nulls to a multiple of 4 bytes
+fn write_padded_str(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(value.as_bytes());
+    let padding = 4 - (value.len() % 4);
+    buffer.resize(buffer.len() + padding, 0);
+}
+
+pub fn encode_message(address: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    write_padded_str(&mut buffer, address);
+
+    let type_tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            OscArg::Int(_) => 'i',
+            OscArg::Float(_) => 'f',
+            OscArg::String(_) => 's',
+        }))
+        .collect();
+    write_padded_str(&mut buffer, &type_tags);
+
+    for arg in args {
+        match arg {
+            OscArg::Int(value) => buffer.extend_from_slice(&value.to_be_bytes()),
+            OscArg::Float(value) => buffer.extend_from_slice(&value.to_be_bytes()),
+            OscArg::String(value) => write_padded_str(&mut buffer, value),
+        }
+    }
+
+    buffer
+}
+
+// Wraps already encoded messages into a single bundle, so a whole frame is sent in one packet
+pub fn encode_bundle(messages: &[Vec<u8>]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    write_padded_str(&mut buffer, "#bundle");
+    buffer.extend_from_slice(&TIMETAG_IMMEDIATELY.to_be_bytes());
+
+    for message in messages {
+        buffer.extend_from_slice(&(message.len() as i32).to_be_bytes());
+        buffer.extend_from_slice(message);
+    }
+
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_message() {
+        let message = encode_message("/VMC/Ext/OK", &[OscArg::Int(1)]);
+        assert_eq!(
+            message,
+            [
+                b"/VMC/Ext/OK\0".as_slice(),
+                b",i\0\0".as_slice(),
+                &[0, 0, 0, 1]
+            ]
+            .concat()
+        );
+
+        let message = encode_message(
+            "/a",
+            &[OscArg::String("Head".to_owned()), OscArg::Float(1.)],
+        );
+        assert_eq!(
+            message,
+            [
+                b"/a\0\0".as_slice(),
+                b",sf\0".as_slice(),
+                b"Head\0\0\0\0".as_slice(),
+                &1f32.to_be_bytes()
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn test_encode_bundle() {
+        let message = encode_message("/VMC/Ext/Blend/Apply", &[]);
+        let bundle = encode_bundle(std::slice::from_ref(&message));
+
+        assert_eq!(&bundle[..8], b"#bundle\0");
+        assert_eq!(&bundle[8..16], &1u64.to_be_bytes());
+        assert_eq!(&bundle[16..20], &(message.len() as i32).to_be_bytes());
+        assert_eq!(&bundle[20..], message.as_slice());
+    }
+}
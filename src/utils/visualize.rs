@@ -1,90 +1,119 @@
 use anyhow::Result;
 use opencv::{
-    core::{Mat, Point, Point2i, Scalar},
-    imgproc::{circle, line, LINE_8},
+    core::{Mat, Point, Scalar},
+    imgproc::{arrowed_line, circle, line, LINE_8},
 };
 
-#[allow(dead_code)]
-pub fn draw_landmark(
-    frame: Mat,
-    pts_3d: Vec<Vec<f32>>,
-    face_box: [f32; 4],
+use crate::structs::overlay::{OverlayData, OverlaySettings};
+
+// Colors are in BGR, the order of the camera frames
+const LANDMARK_COLOR: (f64, f64, f64) = (0., 255., 0.);
+const FACE_BOX_COLOR: (f64, f64, f64) = (0., 0., 255.);
+const ROI_BOX_COLOR: (f64, f64, f64) = (255., 255., 0.);
+const CAMERA_BOX_COLOR: (f64, f64, f64) = (0., 215., 255.);
+// x, y and z axes in red, green and blue
+const AXIS_COLORS: [(f64, f64, f64); 3] = [(0., 0., 255.), (0., 255., 0.), (255., 0., 0.)];
+
+fn point(p: [f32; 2]) -> Point {
+    Point::new(p[0].round() as i32, p[1].round() as i32)
+}
+
+fn scalar(color: (f64, f64, f64)) -> Scalar {
+    Scalar::new(color.0, color.1, color.2, 0.)
+}
+
+pub fn draw_landmarks(
+    img: &mut Mat,
+    landmarks: &[[f32; 2]],
     color: (f64, f64, f64),
     size: i32,
-) -> Result<Mat> {
+) -> Result<()> {
+    for landmark in landmarks {
+        circle(img, point(*landmark), size, scalar(color), -1, LINE_8, 0)?;
+    }
+    Ok(())
+}
+
+// Box given as [left, top, right, bottom]
+pub fn draw_box(img: &mut Mat, bbox: [f32; 4], color: (f64, f64, f64)) -> Result<()> {
+    let corners = [
+        [bbox[0], bbox[1]],
+        [bbox[2], bbox[1]],
+        [bbox[2], bbox[3]],
+        [bbox[0], bbox[3]],
+        [bbox[0], bbox[1]],
+    ];
+    draw_polyline(img, &corners, color)
+}
+
+fn draw_polyline(img: &mut Mat, points: &[[f32; 2]], color: (f64, f64, f64)) -> Result<()> {
+    for pair in points.windows(2) {
+        line(
+            img,
+            point(pair[0]),
+            point(pair[1]),
+            scalar(color),
+            1,
+            LINE_8,
+            0,
+        )?;
+    }
+    Ok(())
+}
+
+// Rear square (points 0 to 4), front square (points 5 to 9) and the edges between them
+pub fn draw_camera_box(
+    img: &mut Mat,
+    camera_box: &[[f32; 2]],
+    color: (f64, f64, f64),
+) -> Result<()> {
+    if camera_box.len() < 10 {
+        return Ok(());
+    }
+    draw_polyline(img, &camera_box[..5], color)?;
+    draw_polyline(img, &camera_box[5..10], color)?;
+    for i in 1..4 {
+        draw_polyline(img, &[camera_box[i], camera_box[i + 5]], color)?;
+    }
+    Ok(())
+}
+
+pub fn draw_axes(img: &mut Mat, data: &OverlayData) -> Result<()> {
+    let ends = data.axes(data.axis_length());
+    for (end, color) in ends.iter().zip(AXIS_COLORS) {
+        arrowed_line(
+            img,
+            point(data.origin),
+            point(*end),
+            scalar(color),
+            2,
+            LINE_8,
+            0,
+            0.2,
+        )?;
+    }
+    Ok(())
+}
+
+// Copy of the frame with the enabled elements of the overlay
+pub fn draw_overlay(frame: &Mat, data: &OverlayData, settings: &OverlaySettings) -> Result<Mat> {
     let mut img = frame.clone();
-    let n = pts_3d[0].len();
-    if n <= 106 {
-        for i in 0..n {
-            circle(
-                &mut img,
-                Point::new(pts_3d[0][i] as i32, pts_3d[1][i] as i32),
-                size,
-                Scalar::new(color.0, color.1, color.2, 0.),
-                -1,
-                LINE_8,
-                0,
-            )?
-        }
-    } else {
-        let sep = 1;
-        for i in (0..n).step_by(sep) {
-            circle(
-                &mut img,
-                Point::new(pts_3d[0][i] as i32, pts_3d[1][i] as i32),
-                size,
-                Scalar::new(color.0, color.1, color.2, 0.),
-                1,
-                LINE_8,
-                0,
-            )?
-        }
-    };
 
-    let left = face_box[0].round() as i32;
-    let top = face_box[1].round() as i32;
-    let right = face_box[2].round() as i32;
-    let bottom = face_box[3].round() as i32;
-    let left_top = Point2i::new(left, top);
-    let right_top = Point2i::new(right, top);
-    let right_bottom = Point2i::new(right, bottom);
-    let left_bottom = Point2i::new(left, bottom);
-    line(
-        &mut img,
-        left_top,
-        right_top,
-        Scalar::new(0., 0., 255., 0.),
-        1,
-        LINE_8,
-        0,
-    )?;
-    line(
-        &mut img,
-        right_top,
-        right_bottom,
-        Scalar::new(0., 0., 255., 0.),
-        1,
-        LINE_8,
-        0,
-    )?;
-    line(
-        &mut img,
-        right_bottom,
-        left_bottom,
-        Scalar::new(0., 0., 255., 0.),
-        1,
-        LINE_8,
-        0,
-    )?;
-    line(
-        &mut img,
-        left_bottom,
-        left_top,
-        Scalar::new(0., 0., 255., 0.),
-        1,
-        LINE_8,
-        0,
-    )?;
+    if settings.face_box {
+        draw_box(&mut img, data.face_box, FACE_BOX_COLOR)?;
+    }
+    if settings.roi_box {
+        draw_box(&mut img, data.roi_box, ROI_BOX_COLOR)?;
+    }
+    if settings.camera_box {
+        draw_camera_box(&mut img, &data.camera_box, CAMERA_BOX_COLOR)?;
+    }
+    if settings.landmarks {
+        draw_landmarks(&mut img, &data.landmarks, LANDMARK_COLOR, 1)?;
+    }
+    if settings.axes && !data.landmarks.is_empty() {
+        draw_axes(&mut img, data)?;
+    }
 
     Ok(img)
 }
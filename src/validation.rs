@@ -0,0 +1,307 @@
+/// Validation of the raw pose before the filter, the glitches of the fit (NaN parameters, out of ordinary roi boxes
+/// near the border of the frame, jumps of the head) are rejected or clamped to the speed limits
+use anyhow::{bail, Result};
+
+use crate::{
+    consts::{MAX_ANGULAR_SPEED, MAX_LINEAR_SPEED, MIN_ROI_AREA},
+    enums::{glitch::Glitch, glitch_action::GlitchAction},
+    structs::{
+        pose::Pose,
+        quality::QualityInput,
+        validation::{PoseValidator, Validated, ValidationSettings},
+    },
+};
+
+impl Default for ValidationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            action: GlitchAction::default(),
+            max_angular_speed: MAX_ANGULAR_SPEED,
+            max_linear_speed: MAX_LINEAR_SPEED,
+        }
+    }
+}
+
+impl ValidationSettings {
+    pub fn validate(&self) -> Result<()> {
+        for (name, speed) in [
+            ("max_angular_speed", self.max_angular_speed),
+            ("max_linear_speed", self.max_linear_speed),
+        ] {
+            if !speed.is_finite() || speed <= 0. {
+                bail!("{} has to be a positive number", name);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The crop is clamped to the frame, a box beyond the border ends up inverted or empty
+fn is_plausible(roi_box: &[f32; 4], frame_size: [f32; 2]) -> bool {
+    let (width, height) = (roi_box[2] - roi_box[0], roi_box[3] - roi_box[1]);
+    width.is_finite()
+        && height.is_finite()
+        && width > 0.
+        && height > 0.
+        && width * height >= MIN_ROI_AREA
+        && width <= frame_size[0]
+        && height <= frame_size[1]
+}
+
+impl PoseValidator {
+    pub fn new(settings: ValidationSettings) -> Self {
+        Self {
+            settings,
+            last: None,
+        }
+    }
+
+    // The parameters are the 62 values of 3DDFA the pose was computed from
+    pub fn check(&mut self, input: &QualityInput, params: &[f32]) -> Validated {
+        if !self.settings.enabled {
+            return Validated {
+                pose: input.pose,
+                glitch: None,
+            };
+        }
+
+        if !input.pose.is_finite() || !params.iter().all(|value| value.is_finite()) {
+            return self.reject(Glitch::NonFinite);
+        }
+        if !is_plausible(&input.roi_box, input.frame_size) {
+            return self.reject(Glitch::RoiBox);
+        }
+
+        let (timestamp, last) = match self.last {
+            Some(last) => last,
+            None => return self.accept(input.timestamp, input.pose, None),
+        };
+        let elapsed = input.timestamp - timestamp;
+        let max_angle = self.settings.max_angular_speed * elapsed;
+        let max_distance = self.settings.max_linear_speed * elapsed;
+        let angle = last.angle_to(input.pose);
+        let distance = last.distance_to(input.pose);
+
+        if angle <= max_angle && distance <= max_distance {
+            return self.accept(input.timestamp, input.pose, None);
+        }
+
+        match self.settings.action {
+            GlitchAction::Reject => self.reject(Glitch::Jump),
+            GlitchAction::Clamp => {
+                let rotation = match angle > max_angle {
+                    true => last.rotation.slerp(input.pose.rotation, max_angle / angle),
+                    false => input.pose.rotation,
+                };
+                let t = (max_distance / distance).min(1.);
+                let mut translation = last.translation;
+                for (value, target) in translation.iter_mut().zip(input.pose.translation) {
+                    *value += (target - *value) * t;
+                }
+
+                self.accept(
+                    input.timestamp,
+                    Pose {
+                        translation,
+                        rotation,
+                    },
+                    Some(Glitch::Jump),
+                )
+            }
+        }
+    }
+
+    fn accept(&mut self, timestamp: f32, pose: Pose, glitch: Option<Glitch>) -> Validated {
+        self.last = Some((timestamp, pose));
+        Validated { pose, glitch }
+    }
+
+    // Holds the last pose, the time since it grows the allowed movement until the head is found again
+    fn reject(&self, glitch: Glitch) -> Validated {
+        Validated {
+            pose: self.last.map_or(Pose::default(), |(_, pose)| pose),
+            glitch: Some(glitch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::pose::Quaternion;
+
+    const FRAME: f32 = 1. / 60.;
+
+    fn pose(x: f32, yaw: f32) -> Pose {
+        Pose {
+            translation: [x, 0., 30.],
+            rotation: Quaternion::exp([0., yaw.to_radians(), 0.]),
+        }
+    }
+
+    fn input(frame: usize, pose: Pose) -> QualityInput {
+        QualityInput {
+            timestamp: frame as f32 * FRAME,
+            confidence: Some(0.9),
+            detection_box: [200., 120., 400., 360.],
+            landmarks_box: [210., 150., 390., 360.],
+            roi_box: [180., 100., 420., 380.],
+            frame_size: [640., 480.],
+            pose,
+        }
+    }
+
+    fn with_action(action: GlitchAction) -> PoseValidator {
+        PoseValidator::new(ValidationSettings {
+            action,
+            ..ValidationSettings::default()
+        })
+    }
+
+    fn yaw(pose: Pose) -> f32 {
+        pose.angle_to(Pose::default())
+    }
+
+    #[test]
+    fn test_steady_motion_passes() {
+        let mut validator = with_action(GlitchAction::Reject);
+        // Turning at 300 degrees per second and moving at 60 units per second
+        for i in 0..60 {
+            let frame = pose(i as f32, i as f32 * 5.);
+            let validated = validator.check(&input(i, frame), &[0.; 62]);
+            assert_eq!(
+                validated,
+                Validated {
+                    pose: frame,
+                    glitch: None
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_single_frame_spike_is_rejected() {
+        let mut validator = with_action(GlitchAction::Reject);
+        let sequence = [0., 1., 2., 90., 4., 5.];
+        let yaws: Vec<(f32, Option<Glitch>)> = sequence
+            .iter()
+            .enumerate()
+            .map(|(i, degrees)| {
+                let validated = validator.check(&input(i, pose(0., *degrees)), &[0.; 62]);
+                (yaw(validated.pose), validated.glitch)
+            })
+            .collect();
+
+        for (i, (degrees, glitch)) in yaws.into_iter().enumerate() {
+            match i {
+                // The last valid pose is held
+                3 => {
+                    assert!((degrees - 2.).abs() < 1e-3);
+                    assert_eq!(glitch, Some(Glitch::Jump));
+                }
+                _ => {
+                    assert!((degrees - sequence[i]).abs() < 1e-3);
+                    assert_eq!(glitch, None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_jump_is_clamped() {
+        let mut validator = with_action(GlitchAction::Clamp);
+        validator.check(&input(0, pose(0., 0.)), &[0.; 62]);
+
+        // 60 degrees and 20 units in a frame, at most 12 degrees and 3.3 units are allowed
+        let validated = validator.check(&input(1, pose(20., 60.)), &[0.; 62]);
+        assert_eq!(validated.glitch, Some(Glitch::Jump));
+        assert!((yaw(validated.pose) - 12.).abs() < 1e-2);
+        assert!((validated.pose.translation[0] - 200. / 60.).abs() < 1e-3);
+
+        // Catching up over the next frames
+        let mut validated = validated;
+        for i in 2..8 {
+            validated = validator.check(&input(i, pose(20., 60.)), &[0.; 62]);
+        }
+        assert!((yaw(validated.pose) - 60.).abs() < 1e-2);
+        assert_eq!(validated.glitch, None);
+    }
+
+    #[test]
+    fn test_lost_face_recovers() {
+        let mut validator = with_action(GlitchAction::Reject);
+        validator.check(&input(0, pose(0., 0.)), &[0.; 62]);
+
+        // Found again turned away, the pose is held until the time since the last valid one allows the turn
+        for i in 1..8 {
+            let validated = validator.check(&input(i, pose(0., 90.)), &[0.; 62]);
+            assert_eq!(validated.glitch, Some(Glitch::Jump));
+            assert_eq!(validated.pose, pose(0., 0.));
+        }
+        let validated = validator.check(&input(8, pose(0., 90.)), &[0.; 62]);
+        assert_eq!(validated.glitch, None);
+        assert!((yaw(validated.pose) - 90.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_broken_fits_are_rejected() {
+        let mut validator = with_action(GlitchAction::Clamp);
+        validator.check(&input(0, pose(1., 10.)), &[0.; 62]);
+
+        let mut params = [0.; 62];
+        params[12] = f32::NAN;
+        let validated = validator.check(&input(1, pose(1., 11.)), &params);
+        assert_eq!(validated.glitch, Some(Glitch::NonFinite));
+        assert_eq!(validated.pose, pose(1., 10.));
+
+        let validated = validator.check(&input(2, pose(f32::NAN, 11.)), &[0.; 62]);
+        assert_eq!(validated.glitch, Some(Glitch::NonFinite));
+
+        // Inverted after being clamped to the border of the frame, tiny and bigger than the frame
+        for roi_box in [
+            [640., 100., 600., 380.],
+            [300., 200., 330., 230.],
+            [0., -100., 640., 480.],
+        ] {
+            let validated = validator.check(
+                &QualityInput {
+                    roi_box,
+                    ..input(3, pose(1., 11.))
+                },
+                &[0.; 62],
+            );
+            assert_eq!(validated.glitch, Some(Glitch::RoiBox));
+            assert_eq!(validated.pose, pose(1., 10.));
+        }
+
+        // Nothing to hold yet
+        let mut empty = with_action(GlitchAction::Reject);
+        let validated = empty.check(&input(0, pose(f32::NAN, 0.)), &[0.; 62]);
+        assert_eq!(validated.pose, Pose::default());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut validator = PoseValidator::new(ValidationSettings {
+            enabled: false,
+            ..ValidationSettings::default()
+        });
+        let glitch = pose(f32::NAN, 0.);
+        let validated = validator.check(&input(0, glitch), &[0.; 62]);
+        assert!(validated.glitch.is_none());
+        assert!(validated.pose.translation[0].is_nan());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ValidationSettings::default().validate().is_ok());
+        for speed in [0., -5., f32::NAN] {
+            let settings = ValidationSettings {
+                max_linear_speed: speed,
+                ..ValidationSettings::default()
+            };
+            assert!(settings.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,292 @@
+/// Deals with sending the head pose to VTuber and avatar applications using the VMC protocol (OSC over UDP)
+/// Protocol specification - https://protocol.vmc.info/english
+use crate::{
+    enums::osc_arg::OscArg,
+    network::bind_for,
+    structs::{network::NetworkTarget, pose::Pose, vmc::VmcNetwork},
+    utils::osc::{encode_bundle, encode_message},
+};
+use anyhow::{Context, Result};
+use std::time::Instant;
+
+// VRM blendshape names matching the values returned by calc_blendshapes
+const BLENDSHAPE_NAMES: [&str; 3] = ["A", "Blink_L", "Blink_R"];
+
+impl VmcNetwork {
+    pub fn new(target: &NetworkTarget) -> Result<Self> {
+        let address = target.resolve()?;
+        tracing::info!("Sending VMC data to {}", address);
+
+        let socket_network = bind_for(&address).context("Unable to bind VMC socket")?;
+
+        Ok(Self {
+            address,
+            socket_network,
+            start_time: Instant::now(),
+        })
+    }
+
+    // Converts the pose into position in meters and head rotation as quaternion,
+    // both in Unity's left-handed coordinate system used by VMC
+    fn to_unity_space(pose: Pose) -> ([f32; 3], [f32; 4]) {
+        let position = [
+            pose.translation[0] / 100.,
+            pose.translation[1] / 100.,
+            -pose.translation[2] / 100.,
+        ];
+
+        // Mirroring the z axis like the position, yaw and pitch change their direction while roll keeps it
+        let q = pose.rotation;
+        let rotation = [-q.x, -q.y, q.z, q.w];
+
+        (position, rotation)
+    }
+
+    pub fn send(&mut self, pose: Pose, blendshapes: [f32; 3]) -> Result<()> {
+        let (position, rotation) = Self::to_unity_space(pose);
+
+        let mut messages = vec![
+            encode_message("/VMC/Ext/OK", &[OscArg::Int(1)]),
+            encode_message(
+                "/VMC/Ext/T",
+                &[OscArg::Float(self.start_time.elapsed().as_secs_f32())],
+            ),
+            encode_message(
+                "/VMC/Ext/Root/Pos",
+                &[
+                    OscArg::String("root".to_owned()),
+                    OscArg::Float(position[0]),
+                    OscArg::Float(position[1]),
+                    OscArg::Float(position[2]),
+                    OscArg::Float(0.),
+                    OscArg::Float(0.),
+                    OscArg::Float(0.),
+                    OscArg::Float(1.),
+                ],
+            ),
+            encode_message(
+                "/VMC/Ext/Bone/Pos",
+                &[
+                    OscArg::String("Head".to_owned()),
+                    OscArg::Float(0.),
+                    OscArg::Float(0.),
+                    OscArg::Float(0.),
+                    OscArg::Float(rotation[0]),
+                    OscArg::Float(rotation[1]),
+                    OscArg::Float(rotation[2]),
+                    OscArg::Float(rotation[3]),
+                ],
+            ),
+        ];
+
+        for (name, value) in BLENDSHAPE_NAMES.iter().zip(blendshapes) {
+            messages.push(encode_message(
+                "/VMC/Ext/Blend/Val",
+                &[OscArg::String((*name).to_owned()), OscArg::Float(value)],
+            ));
+        }
+        messages.push(encode_message("/VMC/Ext/Blend/Apply", &[]));
+
+        // Send data
+        self.socket_network
+            .send_to(&encode_bundle(&messages), self.address)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{enums::rotation_order::RotationOrder, structs::pose::Quaternion};
+    use std::{net::UdpSocket, time::Duration};
+
+    fn read_padded_str(packet: &[u8], offset: &mut usize) -> String {
+        let end = *offset + packet[*offset..].iter().position(|&b| b == 0).unwrap();
+        let value = String::from_utf8(packet[*offset..end].to_vec()).unwrap();
+        *offset = (end / 4 + 1) * 4;
+        value
+    }
+
+    fn read_bytes(packet: &[u8], offset: &mut usize) -> [u8; 4] {
+        let bytes = packet[*offset..*offset + 4].try_into().unwrap();
+        *offset += 4;
+        bytes
+    }
+
+    fn decode_message(packet: &[u8]) -> (String, Vec<OscArg>) {
+        let mut offset = 0;
+        let address = read_padded_str(packet, &mut offset);
+        let type_tags = read_padded_str(packet, &mut offset);
+
+        let args = type_tags
+            .chars()
+            .skip(1)
+            .map(|tag| match tag {
+                'i' => OscArg::Int(i32::from_be_bytes(read_bytes(packet, &mut offset))),
+                'f' => OscArg::Float(f32::from_be_bytes(read_bytes(packet, &mut offset))),
+                's' => OscArg::String(read_padded_str(packet, &mut offset)),
+                _ => panic!("Unexpected type tag {tag}"),
+            })
+            .collect();
+
+        (address, args)
+    }
+
+    fn decode_bundle(packet: &[u8]) -> Vec<(String, Vec<OscArg>)> {
+        assert_eq!(&packet[..8], b"#bundle\0");
+
+        let mut messages = vec![];
+        let mut offset = 16;
+        while offset < packet.len() {
+            let size = i32::from_be_bytes(read_bytes(packet, &mut offset)) as usize;
+            messages.push(decode_message(&packet[offset..offset + size]));
+            offset += size;
+        }
+        messages
+    }
+
+    fn receive(pose: Pose, blendshapes: [f32; 3]) -> Result<Vec<(String, Vec<OscArg>)>> {
+        let listener = UdpSocket::bind("127.0.0.1:0")?;
+        listener.set_read_timeout(Some(Duration::from_secs(2)))?;
+
+        let mut vmc_network = VmcNetwork::new(&NetworkTarget::parse(
+            "127.0.0.1",
+            &listener.local_addr()?.port().to_string(),
+        )?)?;
+        vmc_network.send(pose, blendshapes)?;
+
+        let mut buffer = [0; 2048];
+        let size = listener.recv(&mut buffer)?;
+
+        Ok(decode_bundle(&buffer[..size]))
+    }
+
+    #[test]
+    fn test_vmc_network() -> Result<()> {
+        let pose = Pose {
+            translation: [1., 2., 3.],
+            rotation: Quaternion::IDENTITY,
+        };
+        let messages = receive(pose, [0.5, 0., 1.])?;
+        let addresses: Vec<&str> = messages
+            .iter()
+            .map(|(address, _)| address.as_str())
+            .collect();
+
+        assert_eq!(
+            addresses,
+            [
+                "/VMC/Ext/OK",
+                "/VMC/Ext/T",
+                "/VMC/Ext/Root/Pos",
+                "/VMC/Ext/Bone/Pos",
+                "/VMC/Ext/Blend/Val",
+                "/VMC/Ext/Blend/Val",
+                "/VMC/Ext/Blend/Val",
+                "/VMC/Ext/Blend/Apply"
+            ]
+        );
+
+        assert_eq!(
+            messages[2].1,
+            [
+                OscArg::String("root".to_owned()),
+                OscArg::Float(0.01),
+                OscArg::Float(0.02),
+                OscArg::Float(-0.03),
+                OscArg::Float(0.),
+                OscArg::Float(0.),
+                OscArg::Float(0.),
+                OscArg::Float(1.)
+            ]
+        );
+
+        // No rotation results in the identity quaternion
+        assert_eq!(messages[3].1[0], OscArg::String("Head".to_owned()));
+        assert_eq!(
+            messages[3].1[4..],
+            [
+                OscArg::Float(0.),
+                OscArg::Float(0.),
+                OscArg::Float(0.),
+                OscArg::Float(1.)
+            ]
+        );
+
+        assert_eq!(
+            messages[4].1,
+            [OscArg::String("A".to_owned()), OscArg::Float(0.5)]
+        );
+        assert_eq!(
+            messages[6].1,
+            [OscArg::String("Blink_R".to_owned()), OscArg::Float(1.)]
+        );
+
+        Ok(())
+    }
+
+    fn head_rotation(messages: &[(String, Vec<OscArg>)]) -> Quaternion {
+        let values: Vec<f32> = messages[3].1[4..]
+            .iter()
+            .map(|arg| match arg {
+                OscArg::Float(value) => *value,
+                _ => panic!("Expected a float"),
+            })
+            .collect();
+        Quaternion {
+            x: values[0],
+            y: values[1],
+            z: values[2],
+            w: values[3],
+        }
+    }
+
+    fn rotate(rotation: Quaternion, v: [f32; 3]) -> [f32; 3] {
+        let r = rotation.to_rotation_matrix();
+        [0, 1, 2].map(|i| r[i][0] * v[0] + r[i][1] * v[1] + r[i][2] * v[2])
+    }
+
+    #[test]
+    fn test_vmc_head_yaw() -> Result<()> {
+        // Same yaw opentrack receives from SocketNetwork
+        let order = RotationOrder::default();
+        let pose = Pose::from_euler_data([0., 0., 0., 30., 0., 0.], order);
+        assert!((pose.to_euler_data(order)[3] - 30.).abs() < 1e-4);
+        let rotation = head_rotation(&receive(pose, [0.; 3])?);
+
+        // The direction the face points to, z is flipped in Unity's space
+        let facing = rotate(pose.rotation, [0., 0., 1.]);
+        let avatar_facing = rotate(rotation, [0., 0., -1.]);
+
+        // The avatar turns to the same side, by the same amount
+        assert!(
+            facing[0].abs() > 0.4 && (avatar_facing[0] - facing[0]).abs() < 1e-5,
+            "head turned to {:?}, avatar to {:?}",
+            facing,
+            avatar_facing
+        );
+        assert!((avatar_facing[2] + facing[2]).abs() < 1e-5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vmc_head_rotation() -> Result<()> {
+        let pose = Pose {
+            translation: [0.; 3],
+            rotation: Quaternion::exp([0., 0., std::f32::consts::FRAC_PI_2]),
+        };
+        let rotation = head_rotation(&receive(pose, [0.; 3])?);
+
+        // 90 degrees of roll is a quarter turn around the z axis, which mirroring z leaves unchanged
+        let half_angle = std::f32::consts::FRAC_PI_4;
+        let expected = [0., 0., half_angle.sin(), half_angle.cos()];
+        let rotation = [rotation.x, rotation.y, rotation.z, rotation.w];
+        for i in 0..4 {
+            assert!((rotation[i] - expected[i]).abs() < 1e-5);
+        }
+
+        Ok(())
+    }
+}